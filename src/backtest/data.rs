@@ -0,0 +1,159 @@
+use std::io::BufRead;
+use std::path::Path;
+
+use super::BacktestError;
+use crate::models::dto::MarketPriceData;
+
+/// One historical `MarketPrice` observation. `timestamp` is milliseconds since
+/// the epoch; without it every tick counts as one time unit, which
+/// `adaptive_spread` refuses.
+#[derive(Debug, Clone, PartialEq, serde::Deserialize)]
+pub struct HistoricalTick {
+    #[serde(default)]
+    pub timestamp: Option<u64>,
+    #[serde(flatten)]
+    pub tick: MarketPriceData,
+}
+
+/// Loads ticks from a `.csv` file (header row with `MarketPriceData` field
+/// names) or, for any other extension, from JSON lines.
+pub fn load_ticks(path: &Path) -> Result<Vec<HistoricalTick>, BacktestError> {
+    let reader = std::io::BufReader::new(std::fs::File::open(path)?);
+    let is_csv = path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("csv"));
+
+    if is_csv {
+        parse_csv(reader)
+    } else {
+        parse_json_lines(reader)
+    }
+}
+
+pub fn parse_json_lines(reader: impl BufRead) -> Result<Vec<HistoricalTick>, BacktestError> {
+    let mut ticks = Vec::new();
+
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let tick = serde_json::from_str(&line).map_err(|error| BacktestError::Parse {
+            line: index + 1,
+            reason: error.to_string(),
+        })?;
+        ticks.push(tick);
+    }
+
+    Ok(ticks)
+}
+
+pub fn parse_csv(reader: impl BufRead) -> Result<Vec<HistoricalTick>, BacktestError> {
+    let mut lines = reader.lines().enumerate();
+
+    let Some((_, header)) = lines.next() else {
+        return Ok(Vec::new());
+    };
+    let header = header?;
+    let columns: Vec<String> = header.split(',').map(|c| c.trim().to_string()).collect();
+
+    let mut ticks = Vec::new();
+    for (index, line) in lines {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let parse_error = |reason: String| BacktestError::Parse {
+            line: index + 1,
+            reason,
+        };
+
+        let cells: Vec<&str> = line.split(',').map(str::trim).collect();
+        if cells.len() != columns.len() {
+            return Err(parse_error(format!(
+                "expected {} columns, found {}",
+                columns.len(),
+                cells.len()
+            )));
+        }
+
+        let mut record = serde_json::Map::new();
+        for (column, cell) in columns.iter().zip(cells) {
+            let value = if column == "timestamp" {
+                cell.parse::<u64>()
+                    .map(serde_json::Value::from)
+                    .map_err(|error| parse_error(format!("timestamp: {error}")))?
+            } else {
+                serde_json::Value::String(cell.to_string())
+            };
+            record.insert(column.clone(), value);
+        }
+
+        let tick = serde_json::from_value(serde_json::Value::Object(record))
+            .map_err(|error| parse_error(error.to_string()))?;
+        ticks.push(tick);
+    }
+
+    Ok(ticks)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use bigdecimal::BigDecimal;
+
+    use super::{parse_csv, parse_json_lines};
+    use crate::backtest::BacktestError;
+
+    #[test]
+    fn parses_json_lines_with_and_without_timestamp() {
+        let input = concat!(
+            r#"{"timestamp":1000,"productId":"bc7d5575-3711-4532-a000-312bfacfb767","bestBidPrice":"65102","bestAskPrice":"65107","oraclePrice":"65100","price24hAgo":"63202"}"#,
+            "\n\n",
+            r#"{"productId":"bc7d5575-3711-4532-a000-312bfacfb767","bestBidPrice":"65103","bestAskPrice":"65108","oraclePrice":"65101","price24hAgo":"63202"}"#,
+            "\n"
+        );
+
+        let ticks = parse_json_lines(input.as_bytes()).unwrap();
+        assert_eq!(ticks.len(), 2);
+        assert_eq!(ticks[0].timestamp, Some(1000));
+        assert_eq!(ticks[1].timestamp, None);
+        assert_eq!(
+            ticks[1].tick.best_bid_price,
+            BigDecimal::from_str("65103").unwrap()
+        );
+    }
+
+    #[test]
+    fn parses_csv_by_header_names() {
+        let input = "\
+timestamp,productId,bestAskPrice,bestBidPrice,oraclePrice,price24hAgo
+1000,bc7d5575-3711-4532-a000-312bfacfb767,65107,65102,65100,63202
+";
+
+        let ticks = parse_csv(input.as_bytes()).unwrap();
+        assert_eq!(ticks.len(), 1);
+        assert_eq!(ticks[0].timestamp, Some(1000));
+        assert_eq!(
+            ticks[0].tick.best_ask_price,
+            BigDecimal::from_str("65107").unwrap()
+        );
+    }
+
+    #[test]
+    fn reports_line_of_malformed_record() {
+        let input = "\
+productId,bestAskPrice,bestBidPrice,oraclePrice,price24hAgo
+bc7d5575-3711-4532-a000-312bfacfb767,65107,65102,65100,63202
+not-a-uuid,65107,65102,65100,63202
+";
+
+        match parse_csv(input.as_bytes()) {
+            Err(BacktestError::Parse { line, .. }) => assert_eq!(line, 3),
+            other => panic!("unexpected result: {other:?}"),
+        }
+    }
+}
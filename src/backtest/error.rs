@@ -0,0 +1,13 @@
+#[derive(Debug, thiserror::Error)]
+pub enum BacktestError {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("line {line}: {reason}")]
+    Parse { line: usize, reason: String },
+    #[error(transparent)]
+    Serde(#[from] serde_json::Error),
+    #[error("strategy `{0}` must be set for a backtest")]
+    Unset(&'static str),
+    #[error("tick {0} has no timestamp, which adaptive_spread needs to measure volatility")]
+    NoTimestamp(usize),
+}
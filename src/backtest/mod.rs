@@ -0,0 +1,660 @@
+mod data;
+mod error;
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::Path;
+
+pub use data::{HistoricalTick, load_ticks};
+pub use error::BacktestError;
+use uuid::Uuid;
+
+use crate::executor::PaperBook;
+use crate::models::common::OrderStatus;
+use crate::models::dto::{OrderUpdateData, TradeOrderData};
+use crate::models::util::{
+    ORDER_DECIMALS, decimal_to_raw, format_order_decimal, format_signed_order_decimal,
+};
+use crate::trading::position::PositionKey;
+use crate::trading::settings::StrategyConfig;
+use crate::trading::state::{OrderState, Side, StrategyState, TrackedOrder};
+use crate::trading::strategy::{OrderIntent, Strategy, StrategyRuntime};
+use crate::trading::{is_terminal_order_status, reconcile_order_update};
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct BacktestFill {
+    pub timestamp: u64,
+    pub side: String,
+    pub client_order_id: Uuid,
+    pub price: String,
+    pub quantity: String,
+    pub position_after: String,
+}
+
+/// Share of simulated time each side had a resting quote, in `[0, 1]`.
+#[derive(Debug, Clone, Copy, PartialEq, Default, serde::Serialize)]
+pub struct QuoteUptime {
+    pub buy: f64,
+    pub sell: f64,
+    pub both: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct BacktestSummary {
+    pub ticks: usize,
+    pub orders_placed: usize,
+    pub orders_rejected: usize,
+    pub orders_canceled: usize,
+    pub fills: usize,
+    pub buy_volume: String,
+    pub sell_volume: String,
+    pub final_position: String,
+    pub cash: String,
+    pub mark_price: Option<String>,
+    /// Cash plus the final position marked at the last mid.
+    pub pnl: String,
+    pub quote_uptime: QuoteUptime,
+}
+
+#[derive(Debug, Clone)]
+pub struct BacktestReport {
+    pub summary: BacktestSummary,
+    pub fills: Vec<BacktestFill>,
+}
+
+impl BacktestReport {
+    /// Writes `summary.json` and `fills.csv` (with the running position) into `directory`.
+    pub fn write_to(&self, directory: &Path) -> Result<(), BacktestError> {
+        std::fs::create_dir_all(directory)?;
+
+        let summary = serde_json::to_string_pretty(&self.summary)?;
+        std::fs::write(directory.join("summary.json"), summary)?;
+
+        let mut fills =
+            std::io::BufWriter::new(std::fs::File::create(directory.join("fills.csv"))?);
+        writeln!(
+            fills,
+            "timestamp,side,client_order_id,price,quantity,position_after"
+        )?;
+        for fill in &self.fills {
+            writeln!(
+                fills,
+                "{},{},{},{},{},{}",
+                fill.timestamp,
+                fill.side,
+                fill.client_order_id,
+                fill.price,
+                fill.quantity,
+                fill.position_after
+            )?;
+        }
+        fills.flush()?;
+
+        Ok(())
+    }
+}
+
+/// Replays `ticks` through the strategy selected by `config.kind`, using the
+/// paper matching book as the fill model: quotes fill in full once the touch
+/// trades through them.
+pub fn run_backtest(
+    config: &StrategyConfig,
+    ticks: impl IntoIterator<Item = HistoricalTick>,
+) -> Result<BacktestReport, BacktestError> {
+    run_backtest_with(config, StrategyRuntime::from_config(config), ticks)
+}
+
+/// [`run_backtest`] with a caller-supplied [`Strategy`]. Timers do not fire
+/// since historical ticks carry no wall-clock schedule. With
+/// `adaptive_spread` enabled every tick must carry a timestamp, as tick
+/// counts would stand in for milliseconds and inflate the volatility.
+pub fn run_backtest_with<S: Strategy>(
+    config: &StrategyConfig,
+    strategy: S,
+    ticks: impl IntoIterator<Item = HistoricalTick>,
+) -> Result<BacktestReport, BacktestError> {
+    check_product_fields(config)?;
+    let needs_time = config.adaptive_spread.half_life_ms > 0;
+    let mut simulation = Simulation::new(config, strategy);
+    for (index, tick) in ticks.into_iter().enumerate() {
+        if needs_time && tick.timestamp.is_none() {
+            return Err(BacktestError::NoTimestamp(index + 1));
+        }
+        simulation.on_tick(tick);
+    }
+    Ok(simulation.finish())
+}
+
+pub fn run_backtest_file(
+    config: &StrategyConfig,
+    path: &Path,
+) -> Result<BacktestReport, BacktestError> {
+    run_backtest(config, load_ticks(path)?)
+}
+
+/// The product fields are filled from the exchange when trading live; a
+/// backtest has no exchange to ask, so they must come from the settings.
+fn check_product_fields(config: &StrategyConfig) -> Result<(), BacktestError> {
+    if config.tick_size_raw == 0 {
+        return Err(BacktestError::Unset("tick_size_raw"));
+    }
+    if config.onchain_product_id == 0 {
+        return Err(BacktestError::Unset("onchain_product_id"));
+    }
+    Ok(())
+}
+
+struct SimulatedOrder {
+    side: Side,
+    price_raw: u128,
+    qty_raw: u128,
+}
+
+#[derive(Default)]
+struct UptimeClock {
+    last_timestamp: Option<u64>,
+    total: u64,
+    buy: u64,
+    sell: u64,
+    both: u64,
+}
+
+impl UptimeClock {
+    /// Credits the time since the previous tick to whichever sides were quoting.
+    fn advance(&mut self, timestamp: u64, buy_quoted: bool, sell_quoted: bool) {
+        if let Some(last) = self.last_timestamp {
+            let elapsed = timestamp.saturating_sub(last);
+            self.total += elapsed;
+            if buy_quoted {
+                self.buy += elapsed;
+            }
+            if sell_quoted {
+                self.sell += elapsed;
+            }
+            if buy_quoted && sell_quoted {
+                self.both += elapsed;
+            }
+        }
+        self.last_timestamp = Some(timestamp);
+    }
+
+    fn uptime(&self) -> QuoteUptime {
+        if self.total == 0 {
+            return QuoteUptime::default();
+        }
+        let total = self.total as f64;
+        QuoteUptime {
+            buy: self.buy as f64 / total,
+            sell: self.sell as f64 / total,
+            both: self.both as f64 / total,
+        }
+    }
+}
+
+struct Simulation<'a, S> {
+    config: &'a StrategyConfig,
+    strategy: S,
+    state: StrategyState,
+    /// Returned by the strategy's order update and fill callbacks, executed
+    /// after the tick that caused them.
+    intents: Vec<OrderIntent>,
+    book: PaperBook,
+    orders: HashMap<Uuid, SimulatedOrder>,
+    fills: Vec<BacktestFill>,
+    uptime: UptimeClock,
+    ticks: usize,
+    orders_placed: usize,
+    orders_rejected: usize,
+    orders_canceled: usize,
+    buy_volume_raw: u128,
+    sell_volume_raw: u128,
+    position_raw: i128,
+    cash_raw: i128,
+    last_mid_raw: Option<u128>,
+}
+
+impl<'a, S: Strategy> Simulation<'a, S> {
+    fn new(config: &'a StrategyConfig, strategy: S) -> Self {
+        let mut book = PaperBook::default();
+        book.register_product(config.product_id, config.onchain_product_id);
+
+        Self {
+            config,
+            strategy,
+            state: StrategyState::default(),
+            intents: Vec::new(),
+            book,
+            orders: HashMap::new(),
+            fills: Vec::new(),
+            uptime: UptimeClock::default(),
+            ticks: 0,
+            orders_placed: 0,
+            orders_rejected: 0,
+            orders_canceled: 0,
+            buy_volume_raw: 0,
+            sell_volume_raw: 0,
+            position_raw: 0,
+            cash_raw: 0,
+            last_mid_raw: None,
+        }
+    }
+
+    fn on_tick(&mut self, historical: HistoricalTick) {
+        let tick = historical.tick;
+        if tick.product_id != self.config.product_id {
+            return;
+        }
+
+        let timestamp = historical.timestamp.unwrap_or(self.ticks as u64);
+        self.ticks += 1;
+        self.state.now_ms = timestamp;
+        self.uptime.advance(
+            timestamp,
+            self.state.orders.first_on(Side::Buy).is_some(),
+            self.state.orders.first_on(Side::Sell).is_some(),
+        );
+
+        if let (Some(bid), Some(ask)) = (
+            decimal_to_raw(&tick.best_bid_price),
+            decimal_to_raw(&tick.best_ask_price),
+        ) {
+            self.last_mid_raw = Some((bid + ask) / 2);
+        }
+
+        for update in self.book.on_market_price(&tick, timestamp) {
+            self.apply_update(&update, timestamp);
+        }
+
+        self.state.last_market = Some(tick.clone());
+        let mut intents = std::mem::take(&mut self.intents);
+        intents.extend(
+            self.strategy
+                .on_market_tick(&self.state.context(self.config), &tick),
+        );
+        for intent in intents {
+            self.execute(intent, timestamp);
+        }
+    }
+
+    fn execute(&mut self, intent: OrderIntent, timestamp: u64) {
+        match intent {
+            OrderIntent::Place {
+                side,
+                level,
+                price_raw,
+                qty_raw,
+            } => self.place(side, level, price_raw, qty_raw, timestamp),
+            OrderIntent::Cancel { client_order_id } => self.cancel(client_order_id, timestamp),
+            OrderIntent::Replace {
+                client_order_id,
+                side,
+                level,
+                price_raw,
+                qty_raw,
+            } => {
+                self.cancel(client_order_id, timestamp);
+                self.place(side, level, price_raw, qty_raw, timestamp);
+            }
+        }
+    }
+
+    fn place(&mut self, side: Side, level: u32, price_raw: u128, qty_raw: u128, timestamp: u64) {
+        let order = TradeOrderData {
+            sender: String::new(),
+            subaccount: String::new(),
+            quantity: format_order_decimal(qty_raw),
+            price: format_order_decimal(price_raw),
+            reduce_only: false,
+            side: side as u8,
+            engine_type: 0,
+            onchain_id: self.config.onchain_product_id,
+            nonce: timestamp.to_string(),
+            signed_at: timestamp / 1_000,
+            order_type: "LIMIT".to_string(),
+            time_in_force: self.config.time_in_force,
+            post_only: self.config.post_only,
+            client_order_id: Uuid::new_v4(),
+        };
+
+        match self.book.submit(&order, timestamp) {
+            Ok(update) => {
+                self.orders_placed += 1;
+                self.orders.insert(order.client_order_id, SimulatedOrder {
+                    side,
+                    price_raw,
+                    qty_raw,
+                });
+                self.state.orders.insert(TrackedOrder {
+                    client_order_id: order.client_order_id,
+                    side,
+                    level,
+                    price_raw,
+                    qty_raw,
+                    state: OrderState::PendingNew,
+                    created_at_ms: timestamp,
+                });
+                self.apply_update(&update, timestamp);
+            }
+            Err(_) => self.orders_rejected += 1,
+        }
+    }
+
+    fn cancel(&mut self, client_order_id: Uuid, timestamp: u64) {
+        if let Some(update) = self.book.cancel(client_order_id, timestamp) {
+            self.orders_canceled += 1;
+            self.apply_update(&update, timestamp);
+        }
+        self.state.orders.remove(client_order_id);
+    }
+
+    fn apply_update(&mut self, update: &OrderUpdateData, timestamp: u64) {
+        if update.status == OrderStatus::Filled
+            && let Some(order) = self.orders.get(&update.client_order_id)
+        {
+            let notional_raw = (order.price_raw * order.qty_raw / ORDER_DECIMALS) as i128;
+            match order.side {
+                Side::Buy => {
+                    self.position_raw += order.qty_raw as i128;
+                    self.cash_raw -= notional_raw;
+                    self.buy_volume_raw += order.qty_raw;
+                }
+                Side::Sell => {
+                    self.position_raw -= order.qty_raw as i128;
+                    self.cash_raw += notional_raw;
+                    self.sell_volume_raw += order.qty_raw;
+                }
+            }
+
+            self.fills.push(BacktestFill {
+                timestamp,
+                side: order.side.to_string(),
+                client_order_id: update.client_order_id,
+                price: format_order_decimal(order.price_raw),
+                quantity: format_order_decimal(order.qty_raw),
+                position_after: format_signed_order_decimal(self.position_raw),
+            });
+        }
+
+        if is_terminal_order_status(update.status) {
+            self.orders.remove(&update.client_order_id);
+        }
+        let fill = self.state.positions.apply_order_update(
+            update,
+            PositionKey::for_strategy(self.config),
+            self.config.fee_rate_raw,
+        );
+        reconcile_order_update(&mut self.state, update);
+
+        let ctx = self.state.context(self.config);
+        self.intents
+            .extend(self.strategy.on_order_update(&ctx, update));
+        if let Some(fill) = fill {
+            self.intents.extend(self.strategy.on_fill(&ctx, &fill));
+        }
+    }
+
+    fn finish(self) -> BacktestReport {
+        let marked_position_raw = self.last_mid_raw.map_or(0, |mid| {
+            self.position_raw * mid as i128 / ORDER_DECIMALS as i128
+        });
+
+        BacktestReport {
+            summary: BacktestSummary {
+                ticks: self.ticks,
+                orders_placed: self.orders_placed,
+                orders_rejected: self.orders_rejected,
+                orders_canceled: self.orders_canceled,
+                fills: self.fills.len(),
+                buy_volume: format_order_decimal(self.buy_volume_raw),
+                sell_volume: format_order_decimal(self.sell_volume_raw),
+                final_position: format_signed_order_decimal(self.position_raw),
+                cash: format_signed_order_decimal(self.cash_raw),
+                mark_price: self.last_mid_raw.map(format_order_decimal),
+                pnl: format_signed_order_decimal(self.cash_raw + marked_position_raw),
+                quote_uptime: self.uptime.uptime(),
+            },
+            fills: self.fills,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use bigdecimal::BigDecimal;
+    use uuid::Uuid;
+
+    use super::{BacktestError, HistoricalTick, QuoteUptime, run_backtest, run_backtest_with};
+    use crate::models::dto::MarketPriceData;
+    use crate::trading::position::Fill;
+    use crate::trading::settings::{
+        AdaptiveSpreadConfig, HaltConfig, InventoryConfig, LadderConfig, OracleConfig, QuoteMode,
+        QuotePlacement, StaleOrderConfig, StartupOrderPolicy, StrategyConfig, StrategyKind,
+        TimeInForce,
+    };
+    use crate::trading::state::Side;
+    use crate::trading::strategy::{OrderIntent, Strategy, StrategyContext};
+
+    fn config(product_id: Uuid) -> StrategyConfig {
+        StrategyConfig {
+            kind: StrategyKind::default(),
+            subaccount: Uuid::new_v4(),
+            product_id,
+            onchain_product_id: 1,
+            qty_raw: 1_000_000_000,
+            post_only: true,
+            time_in_force: TimeInForce::Gtd,
+            tick_size_raw: 1_000_000_000,
+            lot_size_raw: 0,
+            min_qty_raw: 0,
+            min_spread_ticks: 1,
+            placement: QuotePlacement::default(),
+            offset_ticks: 0,
+            requote_min_ticks: 1,
+            min_quote_lifetime_ms: 0,
+            quote_mode: QuoteMode::default(),
+            inventory: InventoryConfig::default(),
+            oracle: OracleConfig::default(),
+            adaptive_spread: AdaptiveSpreadConfig::default(),
+            ladder: LadderConfig::default(),
+            shutdown_timeout_ms: 5_000,
+            fee_rate_raw: 0,
+            startup_orders: StartupOrderPolicy::default(),
+            halt: HaltConfig::default(),
+            stale_orders: StaleOrderConfig::default(),
+            market_data_timeout_ms: 10_000,
+        }
+    }
+
+    fn tick(product_id: Uuid, timestamp: u64, bid: &str, ask: &str) -> HistoricalTick {
+        HistoricalTick {
+            timestamp: Some(timestamp),
+            tick: MarketPriceData {
+                product_id,
+                best_bid_price: BigDecimal::from_str(bid).unwrap(),
+                best_ask_price: BigDecimal::from_str(ask).unwrap(),
+                oracle_price: BigDecimal::from_str(bid).unwrap(),
+                price24h_ago: BigDecimal::from_str(bid).unwrap(),
+            },
+        }
+    }
+
+    #[test]
+    fn fills_follow_the_touch_and_mark_to_mid() {
+        let product_id = Uuid::new_v4();
+        let ticks = vec![
+            tick(product_id, 0, "100", "102"),
+            // Ask trades down through our 100 bid: buy fills, sell is requoted to 100.
+            tick(product_id, 1_000, "99", "100"),
+            // Bid trades up through our 100 ask: sell fills.
+            tick(product_id, 2_000, "101", "102"),
+        ];
+
+        let open = run_backtest(&config(product_id), ticks[..2].to_vec()).unwrap();
+        assert_eq!(open.summary.final_position, "1.000000000");
+        assert_eq!(open.summary.mark_price.as_deref(), Some("99.500000000"));
+        assert_eq!(open.summary.pnl, "-0.500000000");
+
+        let report = run_backtest(&config(product_id), ticks).unwrap();
+        let summary = &report.summary;
+
+        assert_eq!(summary.ticks, 3);
+        assert_eq!(summary.fills, 2);
+        assert_eq!(report.fills[0].side, "buy");
+        assert_eq!(report.fills[0].price, "100.000000000");
+        assert_eq!(report.fills[0].timestamp, 1_000);
+        assert_eq!(report.fills[0].position_after, "1.000000000");
+        assert_eq!(report.fills[1].side, "sell");
+        assert_eq!(report.fills[1].price, "100.000000000");
+        assert_eq!(report.fills[1].position_after, "0.000000000");
+        assert_eq!(summary.final_position, "0.000000000");
+        assert_eq!(summary.pnl, "0.000000000");
+        assert_eq!(summary.buy_volume, "1.000000000");
+        assert_eq!(summary.sell_volume, "1.000000000");
+    }
+
+    #[test]
+    fn tight_spread_guard_keeps_the_book_empty() {
+        let product_id = Uuid::new_v4();
+        let mut config = config(product_id);
+        config.min_spread_ticks = 3;
+        let ticks = vec![
+            tick(product_id, 0, "100", "101"),
+            tick(product_id, 1_000, "100", "102"),
+        ];
+
+        let report = run_backtest(&config, ticks).unwrap();
+
+        assert_eq!(report.summary.orders_placed, 0);
+        assert_eq!(report.summary.quote_uptime, QuoteUptime::default());
+    }
+
+    #[test]
+    fn uptime_is_time_weighted() {
+        let product_id = Uuid::new_v4();
+        let mut config = config(product_id);
+        config.min_spread_ticks = 2;
+        let ticks = vec![
+            // Spread too tight: nothing quoted for the first 3s.
+            tick(product_id, 0, "100", "101"),
+            tick(product_id, 3_000, "100", "102"),
+            // Quoted on both sides for the last 1s.
+            tick(product_id, 4_000, "100", "102"),
+        ];
+
+        let report = run_backtest(&config, ticks).unwrap();
+
+        assert_eq!(report.summary.quote_uptime.both, 0.25);
+        assert_eq!(report.summary.quote_uptime.buy, 0.25);
+        assert_eq!(report.summary.fills, 0);
+    }
+
+    /// Bids once below the market and bids a tick lower after each fill.
+    #[derive(Default)]
+    struct LadderBuyer {
+        fills: Vec<Fill>,
+    }
+
+    impl Strategy for LadderBuyer {
+        fn on_market_tick(
+            &mut self,
+            ctx: &StrategyContext<'_>,
+            _tick: &MarketPriceData,
+        ) -> Vec<OrderIntent> {
+            if ctx.orders.is_empty() && self.fills.is_empty() {
+                return vec![OrderIntent::Place {
+                    side: Side::Buy,
+                    level: 0,
+                    price_raw: 99_000_000_000,
+                    qty_raw: ctx.config.qty_raw,
+                }];
+            }
+            Vec::new()
+        }
+
+        fn on_fill(&mut self, ctx: &StrategyContext<'_>, fill: &Fill) -> Vec<OrderIntent> {
+            self.fills.push(fill.clone());
+            vec![OrderIntent::Place {
+                side: Side::Buy,
+                level: 0,
+                price_raw: fill.price_raw - ctx.config.tick_size_raw,
+                qty_raw: fill.qty_raw,
+            }]
+        }
+    }
+
+    #[test]
+    fn custom_strategy_intents_follow_its_callbacks() {
+        let product_id = Uuid::new_v4();
+        let ticks = vec![
+            tick(product_id, 0, "100", "101"),
+            // Ask trades through the 99 bid; the fill callback rests a 98 bid.
+            tick(product_id, 1_000, "98", "99"),
+            tick(product_id, 2_000, "100", "102"),
+        ];
+
+        let report = run_backtest_with(&config(product_id), LadderBuyer::default(), ticks).unwrap();
+
+        assert_eq!(report.summary.fills, 1);
+        assert_eq!(report.summary.orders_placed, 2);
+        assert_eq!(report.fills[0].price, "99.000000000");
+        assert_eq!(report.summary.quote_uptime.buy, 1.0);
+    }
+
+    #[test]
+    fn ignores_ticks_for_other_products() {
+        let product_id = Uuid::new_v4();
+        let ticks = vec![tick(Uuid::new_v4(), 0, "100", "102")];
+
+        let report = run_backtest(&config(product_id), ticks).unwrap();
+
+        assert_eq!(report.summary.ticks, 0);
+        assert_eq!(report.summary.orders_placed, 0);
+    }
+
+    #[test]
+    fn refuses_unset_product_fields() {
+        let product_id = Uuid::new_v4();
+        let unquantized = StrategyConfig {
+            tick_size_raw: 0,
+            ..config(product_id)
+        };
+        let no_product = StrategyConfig {
+            onchain_product_id: 0,
+            ..config(product_id)
+        };
+
+        assert!(matches!(
+            run_backtest(&unquantized, Vec::new()),
+            Err(BacktestError::Unset("tick_size_raw"))
+        ));
+        assert!(matches!(
+            run_backtest(&no_product, Vec::new()),
+            Err(BacktestError::Unset("onchain_product_id"))
+        ));
+    }
+
+    #[test]
+    fn adaptive_spread_needs_timestamped_ticks() {
+        let product_id = Uuid::new_v4();
+        let adaptive = StrategyConfig {
+            adaptive_spread: AdaptiveSpreadConfig {
+                half_life_ms: 1_000,
+                ..AdaptiveSpreadConfig::default()
+            },
+            ..config(product_id)
+        };
+        let mut untimed = tick(product_id, 0, "100", "101");
+        untimed.timestamp = None;
+
+        assert!(matches!(
+            run_backtest(&adaptive, vec![
+                tick(product_id, 0, "100", "101"),
+                untimed.clone(),
+            ]),
+            Err(BacktestError::NoTimestamp(2))
+        ));
+        assert!(run_backtest(&config(product_id), vec![untimed]).is_ok());
+    }
+}
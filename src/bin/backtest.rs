@@ -0,0 +1,51 @@
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    let mut args = std::env::args_os().skip(1);
+    let Some(ticks_path) = args.next().map(PathBuf::from) else {
+        eprintln!("usage: backtest <ticks.jsonl|ticks.csv> [out-dir]");
+        return ExitCode::FAILURE;
+    };
+    let out_dir = args
+        .next()
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("backtest-out"));
+
+    let config = ethereal_bot::Config::new();
+
+    let several = config.strategies.len() > 1;
+    for strategy in &config.strategies {
+        let report = match ethereal_bot::run_backtest_file(strategy, &ticks_path) {
+            Ok(report) => report,
+            Err(error) => {
+                eprintln!(
+                    "backtest of {} on {} failed: {error}",
+                    strategy.product_id,
+                    ticks_path.display()
+                );
+                return ExitCode::FAILURE;
+            }
+        };
+
+        // With several products each report goes to its own subdirectory.
+        let product_dir = if several {
+            out_dir.join(strategy.product_id.to_string())
+        } else {
+            out_dir.clone()
+        };
+        if let Err(error) = report.write_to(&product_dir) {
+            eprintln!(
+                "failed to write report to {}: {error}",
+                product_dir.display()
+            );
+            return ExitCode::FAILURE;
+        }
+
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&report.summary).expect("summary serializes")
+        );
+    }
+    ExitCode::SUCCESS
+}
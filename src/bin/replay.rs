@@ -0,0 +1,49 @@
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use ethereal_bot::ReplaySpeed;
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1);
+    let Some(capture_path) = args.next().map(PathBuf::from) else {
+        eprintln!("usage: replay <capture-file|capture-dir> [original|max|<speedup>]");
+        return ExitCode::FAILURE;
+    };
+    let speed = match args.next().as_deref() {
+        None | Some("original") => ReplaySpeed::Original,
+        Some("max") => ReplaySpeed::Unthrottled,
+        Some(factor) => match factor.parse::<f64>() {
+            Ok(factor) if factor > 0.0 => ReplaySpeed::Accelerated(factor),
+            _ => {
+                eprintln!("invalid replay speed `{factor}`");
+                return ExitCode::FAILURE;
+            }
+        },
+    };
+
+    let (event_sender, mut events) = tokio::sync::mpsc::unbounded_channel();
+    let printer = tokio::spawn(async move {
+        while let Some(event) = events.recv().await {
+            println!("{event:?}");
+        }
+    });
+
+    let result = ethereal_bot::replay_capture(&capture_path, speed, &event_sender).await;
+    drop(event_sender);
+    let _ = printer.await;
+
+    match result {
+        Ok(summary) => {
+            eprintln!(
+                "replayed {} frame(s), {} event(s)",
+                summary.frames, summary.events
+            );
+            ExitCode::SUCCESS
+        }
+        Err(error) => {
+            eprintln!("replay failed: {error}");
+            ExitCode::FAILURE
+        }
+    }
+}
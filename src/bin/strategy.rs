@@ -1,9 +1,35 @@
+use std::process::ExitCode;
+
+use ethereal_bot::{EtherealRuntimeError, targets};
+
 #[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
+async fn main() -> ExitCode {
     let config = ethereal_bot::Config::new();
-    let _logging_guards = ethereal_bot::init_logging(&config.logging);
+    let logging_guards = ethereal_bot::init_logging(&config.logging);
+
+    let exit_code = match ethereal_bot::run_strategy(&config, ethereal_bot::shutdown_signal()).await
+    {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error @ EtherealRuntimeError::ShutdownIncomplete(_)) => {
+            tracing::error!(
+                target: targets::TRADING_DECISION,
+                %error,
+                "shutdown cleanup incomplete"
+            );
+            ExitCode::from(2)
+        }
+        Err(error) => {
+            tracing::error!(
+                target: targets::TRADING_DECISION,
+                %error,
+                "strategy stopped"
+            );
+            ExitCode::FAILURE
+        }
+    };
 
-    ethereal_bot::run_strategy(&config).await?;
+    // Flush the non-blocking file writers before the process exits.
+    drop(logging_guards);
 
-    Ok(())
+    exit_code
 }
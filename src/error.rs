@@ -6,14 +6,32 @@ pub enum EtherealRuntimeError {
     #[error("websocket error: {0}")]
     WS(String),
 
+    #[error("websocket protocol error: {0}")]
+    Protocol(#[from] crate::runtime::ProtocolError),
+
     #[error("invalid address: {0}")]
     InvalidHexAddress(#[from] alloy::hex::FromHexError),
 
     #[error("execution mode `{0}` is not implemented yet")]
     ExecutionModeNotImplemented(&'static str),
 
+    #[error("invalid strategy configuration: {0}")]
+    InvalidStrategies(&'static str),
+
+    #[error("websocket capture error: {0}")]
+    Capture(#[from] crate::runtime::CaptureError),
+
+    #[error("product metadata error: {0}")]
+    Product(#[from] crate::runtime::ProductError),
+
+    #[error("order refused by risk check: {0}")]
+    Risk(#[from] crate::RiskRejection),
+
     #[error(transparent)]
     Executor(#[from] crate::ExecutorError),
+
+    #[error("shutdown left {0} order(s) without a terminal update")]
+    ShutdownIncomplete(usize),
 }
 
 impl From<tokio_tungstenite::tungstenite::Error> for EtherealRuntimeError {
@@ -6,6 +6,98 @@ pub enum ExecutorError {
     HttpError(#[from] reqwest::Error),
     #[error(transparent)]
     Serde(#[from] serde_json::Error),
+    #[error("post-only order would cross the book")]
+    PostOnlyWouldCross,
+    #[error("insufficient margin for the order")]
+    InsufficientMargin,
+    #[error("nonce already used")]
+    NonceAlreadyUsed,
+    #[error("client order id already used")]
+    DuplicateClientOrderId,
+    #[error("rate limited by exchange")]
+    RateLimited,
+    /// Refused with a status or code that has no variant of its own.
     #[error("request rejected by exchange (status: {status:?})")]
     Rejected { status: u16, payload: String },
 }
+
+impl ExecutorError {
+    /// The variant for an exchange error code; `None` for codes without one.
+    pub fn from_code(code: &str) -> Option<Self> {
+        match code {
+            "PostOnlyWouldCross" => Some(Self::PostOnlyWouldCross),
+            "InsufficientMargin" => Some(Self::InsufficientMargin),
+            "NonceAlreadyUsed" => Some(Self::NonceAlreadyUsed),
+            "DuplicateClientOrderId" => Some(Self::DuplicateClientOrderId),
+            "RateLimited" => Some(Self::RateLimited),
+            _ => None,
+        }
+    }
+
+    /// A response that came back with `code` instead of `Ok`.
+    pub(crate) fn refused(status: u16, code: &str, payload: String) -> Self {
+        Self::from_code(code).unwrap_or(Self::Rejected { status, payload })
+    }
+
+    /// A non-success HTTP response, read from its status and any `code` in
+    /// the body.
+    pub(crate) fn from_response(status: u16, body: String) -> Self {
+        if status == 429 {
+            return Self::RateLimited;
+        }
+        let code = serde_json::from_str::<serde_json::Value>(&body)
+            .ok()
+            .and_then(|value| value.get("code")?.as_str().map(str::to_owned));
+        match code {
+            Some(code) => Self::refused(status, &code, body),
+            None => Self::Rejected {
+                status,
+                payload: body,
+            },
+        }
+    }
+
+    /// Whether the exchange refused the request, as opposed to the request
+    /// or its response not getting through.
+    pub fn is_rejection(&self) -> bool {
+        !matches!(
+            self,
+            Self::SendRequestError(_) | Self::HttpError(_) | Self::Serde(_)
+        )
+    }
+
+    /// Whether the refusal counts toward a halt on repeated rejections. A
+    /// post-only order meeting a book that moved and the exchange pacing
+    /// requests are part of normal quoting and do not.
+    pub fn counts_toward_halt(&self) -> bool {
+        self.is_rejection() && !matches!(self, Self::PostOnlyWouldCross | Self::RateLimited)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ExecutorError;
+
+    #[test]
+    fn error_responses_map_to_their_code() {
+        assert!(matches!(
+            ExecutorError::from_response(429, String::new()),
+            ExecutorError::RateLimited
+        ));
+        assert!(matches!(
+            ExecutorError::from_response(
+                400,
+                r#"{"code":"PostOnlyWouldCross","message":"would cross"}"#.to_string()
+            ),
+            ExecutorError::PostOnlyWouldCross
+        ));
+        assert!(matches!(
+            ExecutorError::from_response(400, r#"{"code":"Malformed"}"#.to_string()),
+            ExecutorError::Rejected { status: 400, .. }
+        ));
+        assert!(matches!(
+            ExecutorError::from_response(503, "unavailable".to_string()),
+            ExecutorError::Rejected { status: 503, payload } if payload == "unavailable"
+        ));
+    }
+}
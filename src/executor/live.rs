@@ -1,5 +1,13 @@
-use super::{ExecutorError, OrderExecutor, is_cancel_accepted};
-use crate::models::dto::{CancelOrderRequest, OrderRequest};
+use uuid::Uuid;
+
+use super::{ExecutorError, OrderExecutor, check_cancel, endpoint, parse_accepted};
+use crate::models::dto::{
+    CancelOrderRequest, CancelOrderResponse, OpenOrderData, OrderRequest, PageData, PositionData,
+    SubmitOrderResponse,
+};
+
+/// Page size requested from paginated listings; the exchange caps it at 100.
+const PAGE_LIMIT: u32 = 100;
 
 pub(crate) struct LiveExecutor {
     http_client: reqwest::Client,
@@ -15,74 +23,136 @@ impl LiveExecutor {
     }
 }
 
-fn is_submit_created(payload: &serde_json::Value) -> bool {
-    payload.get("id").is_some()
-        && payload.get("filled").is_some()
-        && payload.get("result").is_some()
+impl LiveExecutor {
+    /// Follows the cursor of a paginated listing at `path` to its last page.
+    async fn fetch_pages<T: serde::de::DeserializeOwned>(
+        &self,
+        path: &str,
+        params: &[(&str, &str)],
+    ) -> Result<Vec<T>, ExecutorError> {
+        let mut items = Vec::new();
+        let mut cursor: Option<String> = None;
+
+        loop {
+            let mut url: url::Url =
+                endpoint(&self.rest_url, path)
+                    .parse()
+                    .map_err(|error: url::ParseError| {
+                        ExecutorError::SendRequestError(error.to_string())
+                    })?;
+            {
+                let mut query = url.query_pairs_mut();
+                query
+                    .extend_pairs(params)
+                    .append_pair("limit", &PAGE_LIMIT.to_string());
+                if let Some(cursor) = &cursor {
+                    query.append_pair("cursor", cursor);
+                }
+            }
+
+            let response = self.http_client.get(url).send().await?;
+            let status = response.status();
+
+            if !status.is_success() {
+                let body = response.text().await?;
+                return Err(ExecutorError::from_response(status.as_u16(), body));
+            }
+
+            let page: PageData<T> = serde_json::from_slice(&response.bytes().await?)?;
+            items.extend(page.data);
+
+            match page.next_cursor {
+                Some(next) if page.has_next => cursor = Some(next),
+                _ => break,
+            }
+        }
+
+        Ok(items)
+    }
 }
 
 impl OrderExecutor for LiveExecutor {
     async fn submit_order(
         &self,
         request: &OrderRequest,
-    ) -> Result<serde_json::Value, ExecutorError> {
+    ) -> Result<SubmitOrderResponse, ExecutorError> {
         let response = self
             .http_client
-            .post(format!("{}/v1/order", self.rest_url))
+            .post(endpoint(&self.rest_url, "/v1/order"))
             .json(request)
             .send()
             .await?;
         let status = response.status();
+        let body = response.text().await?;
 
         if !status.is_success() {
-            let body = response.text().await?;
-            return Err(ExecutorError::Rejected {
-                status: status.as_u16(),
-                payload: body,
-            });
+            return Err(ExecutorError::from_response(status.as_u16(), body));
         }
 
-        let payload: serde_json::Value = response.json().await?;
-
-        if is_submit_created(&payload) {
-            Ok(payload)
-        } else {
-            Err(ExecutorError::Rejected {
-                status: status.as_u16(),
-                payload: payload.to_string(),
-            })
-        }
+        parse_accepted(status.as_u16(), body, "result")
     }
 
     async fn cancel_order(
         &self,
         request: &CancelOrderRequest,
-    ) -> Result<serde_json::Value, ExecutorError> {
+    ) -> Result<CancelOrderResponse, ExecutorError> {
         let response = self
             .http_client
-            .post(format!("{}/v1/order/cancel", self.rest_url))
+            .post(endpoint(&self.rest_url, "/v1/order/cancel"))
             .json(request)
             .send()
             .await?;
         let status = response.status();
+        let body = response.text().await?;
 
         if !status.is_success() {
-            let body = response.text().await?;
-            return Err(ExecutorError::Rejected {
-                status: status.as_u16(),
-                payload: body,
-            });
+            return Err(ExecutorError::from_response(status.as_u16(), body));
         }
 
-        let payload: serde_json::Value = response.json().await?;
+        let cancelled: CancelOrderResponse = serde_json::from_str(&body)?;
+        check_cancel(status.as_u16(), &cancelled, body)?;
+        Ok(cancelled)
+    }
 
-        if is_cancel_accepted(&payload) {
-            Ok(payload)
-        } else {
-            Err(ExecutorError::Rejected {
-                status: status.as_u16(),
-                payload: payload.to_string(),
-            })
-        }
+    async fn open_orders(
+        &self,
+        subaccount_id: Uuid,
+        product_id: Uuid,
+    ) -> Result<Vec<OpenOrderData>, ExecutorError> {
+        let subaccount_id = subaccount_id.to_string();
+        let product_id = product_id.to_string();
+        self.fetch_pages("/v1/order", &[
+            ("subaccountId", &subaccount_id),
+            ("productIds", &product_id),
+            ("isWorking", "true"),
+            ("orderBy", "createdAt"),
+            ("order", "asc"),
+        ])
+        .await
+    }
+
+    async fn order_by_client_id(
+        &self,
+        subaccount_id: Uuid,
+        client_order_id: Uuid,
+    ) -> Result<Option<OpenOrderData>, ExecutorError> {
+        let subaccount_id = subaccount_id.to_string();
+        let client_order_id = client_order_id.to_string();
+        let orders: Vec<OpenOrderData> = self
+            .fetch_pages("/v1/order", &[
+                ("subaccountId", &subaccount_id),
+                ("clientOrderId", &client_order_id),
+            ])
+            .await?;
+        Ok(orders.into_iter().next())
+    }
+
+    async fn positions(&self, subaccount_id: Uuid) -> Result<Vec<PositionData>, ExecutorError> {
+        let subaccount_id = subaccount_id.to_string();
+        self.fetch_pages("/v1/position", &[
+            ("subaccountId", &subaccount_id),
+            ("open", "true"),
+        ])
+        .await
     }
 }
@@ -0,0 +1,432 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use bigdecimal::BigDecimal;
+use uuid::Uuid;
+
+use crate::models::common::{OrderStatus, TimeInForce};
+use crate::models::dto::{MarketPriceData, OpenOrderData, OrderUpdateData, TradeOrderData};
+use crate::models::util::{decimal_to_raw, format_order_decimal, parse_order_decimal};
+
+const BUY_SIDE: u8 = 0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub(crate) enum PaperReject {
+    #[error("post-only order would cross the book")]
+    PostOnlyWouldCross,
+    #[error("client order id is already resting")]
+    DuplicateClientOrderId,
+    #[error("order field `{0}` is malformed")]
+    Malformed(&'static str),
+}
+
+impl PaperReject {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::PostOnlyWouldCross => "PostOnlyWouldCross",
+            Self::DuplicateClientOrderId => "DuplicateClientOrderId",
+            Self::Malformed(_) => "Malformed",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct TopOfBook {
+    best_bid_raw: Option<u128>,
+    best_ask_raw: Option<u128>,
+}
+
+impl TopOfBook {
+    fn crosses(&self, side: u8, price_raw: u128) -> bool {
+        if side == BUY_SIDE {
+            self.best_ask_raw.is_some_and(|ask| price_raw >= ask)
+        } else {
+            self.best_bid_raw.is_some_and(|bid| price_raw <= bid)
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct RestingOrder {
+    id: Uuid,
+    client_order_id: Uuid,
+    product_id: Option<Uuid>,
+    onchain_id: u32,
+    side: u8,
+    price_raw: u128,
+    quantity_raw: u128,
+    created_at: u64,
+}
+
+/// Simulated exchange book for paper trading.
+///
+/// Fills are all-or-nothing: an order fills completely as soon as the opposite
+/// top of book trades through its price, since `MarketPrice` carries no depth.
+/// For the same reason FOK behaves exactly like IOC.
+#[derive(Debug, Default)]
+pub(crate) struct PaperBook {
+    products: HashMap<Uuid, u32>,
+    tops: HashMap<u32, TopOfBook>,
+    resting: HashMap<Uuid, RestingOrder>,
+}
+
+impl PaperBook {
+    pub fn register_product(&mut self, product_id: Uuid, onchain_id: u32) {
+        self.products.insert(product_id, onchain_id);
+    }
+
+    /// Accepts an order and returns the update the exchange would publish for it.
+    pub fn submit(
+        &mut self,
+        order: &TradeOrderData,
+        now_ms: u64,
+    ) -> Result<OrderUpdateData, PaperReject> {
+        let price_raw = parse_order_decimal(&order.price).ok_or(PaperReject::Malformed("price"))?;
+        let quantity_raw =
+            parse_order_decimal(&order.quantity).ok_or(PaperReject::Malformed("quantity"))?;
+        if self.resting.contains_key(&order.client_order_id) {
+            return Err(PaperReject::DuplicateClientOrderId);
+        }
+
+        let crosses = self
+            .tops
+            .get(&order.onchain_id)
+            .is_some_and(|top| top.crosses(order.side, price_raw));
+
+        if order.post_only && crosses {
+            return Err(PaperReject::PostOnlyWouldCross);
+        }
+
+        let id = Uuid::new_v4();
+        let product_id = self.product_id(order.onchain_id);
+        let status = match (order.time_in_force, crosses) {
+            (_, true) => OrderStatus::Filled,
+            (TimeInForce::Ioc | TimeInForce::Fok, false) => OrderStatus::Canceled,
+            (TimeInForce::Gtd, false) => {
+                self.resting.insert(order.client_order_id, RestingOrder {
+                    id,
+                    client_order_id: order.client_order_id,
+                    product_id,
+                    onchain_id: order.onchain_id,
+                    side: order.side,
+                    price_raw,
+                    quantity_raw,
+                    created_at: now_ms,
+                });
+                OrderStatus::New
+            }
+        };
+
+        let filled_raw = if status == OrderStatus::Filled {
+            quantity_raw
+        } else {
+            0
+        };
+
+        Ok(OrderUpdateData {
+            id,
+            status,
+            created_at: now_ms,
+            updated_at: now_ms,
+            client_order_id: order.client_order_id,
+            product_id,
+            subaccount_id: None,
+            side: Some(order.side),
+            price: Some(raw_to_decimal(price_raw)),
+            filled: Some(raw_to_decimal(filled_raw)),
+        })
+    }
+
+    fn product_id(&self, onchain_id: u32) -> Option<Uuid> {
+        self.products
+            .iter()
+            .find(|&(_, &registered)| registered == onchain_id)
+            .map(|(&product_id, _)| product_id)
+    }
+
+    pub fn cancel(&mut self, client_order_id: Uuid, now_ms: u64) -> Option<OrderUpdateData> {
+        self.resting
+            .remove(&client_order_id)
+            .map(|order| order.into_update(OrderStatus::Canceled, now_ms))
+    }
+
+    pub fn cancel_by_order_id(&mut self, order_id: Uuid, now_ms: u64) -> Option<OrderUpdateData> {
+        let client_order_id = self
+            .resting
+            .values()
+            .find(|order| order.id == order_id)?
+            .client_order_id;
+        self.cancel(client_order_id, now_ms)
+    }
+
+    /// Resting orders on `product_id`, oldest first, as `GET /v1/order` lists them.
+    pub fn open_orders(&self, product_id: Uuid) -> Vec<OpenOrderData> {
+        let Some(&onchain_id) = self.products.get(&product_id) else {
+            return Vec::new();
+        };
+
+        let mut orders: Vec<_> = self
+            .resting
+            .values()
+            .filter(|order| order.onchain_id == onchain_id)
+            .map(|order| order.to_open_order(product_id))
+            .collect();
+        orders.sort_by_key(|order| order.created_at);
+        orders
+    }
+
+    /// Records the new top of book and fills every resting order it trades through.
+    pub fn on_market_price(&mut self, tick: &MarketPriceData, now_ms: u64) -> Vec<OrderUpdateData> {
+        let Some(&onchain_id) = self.products.get(&tick.product_id) else {
+            return Vec::new();
+        };
+
+        let top = TopOfBook {
+            best_bid_raw: decimal_to_raw(&tick.best_bid_price).filter(|price| *price > 0),
+            best_ask_raw: decimal_to_raw(&tick.best_ask_price).filter(|price| *price > 0),
+        };
+        self.tops.insert(onchain_id, top);
+
+        let crossed: Vec<Uuid> = self
+            .resting
+            .values()
+            .filter(|order| {
+                order.onchain_id == onchain_id && top.crosses(order.side, order.price_raw)
+            })
+            .map(|order| order.client_order_id)
+            .collect();
+
+        crossed
+            .into_iter()
+            .filter_map(|client_order_id| self.resting.remove(&client_order_id))
+            .map(|order| order.into_update(OrderStatus::Filled, now_ms))
+            .collect()
+    }
+}
+
+impl RestingOrder {
+    fn to_open_order(&self, product_id: Uuid) -> OpenOrderData {
+        OpenOrderData {
+            id: self.id,
+            client_order_id: Some(self.client_order_id),
+            product_id,
+            side: self.side,
+            price: raw_to_decimal(self.price_raw),
+            quantity: raw_to_decimal(self.quantity_raw),
+            filled: BigDecimal::default(),
+            status: OrderStatus::New,
+            created_at: self.created_at,
+        }
+    }
+
+    fn into_update(self, status: OrderStatus, now_ms: u64) -> OrderUpdateData {
+        let filled_raw = if status == OrderStatus::Filled {
+            self.quantity_raw
+        } else {
+            0
+        };
+
+        OrderUpdateData {
+            id: self.id,
+            status,
+            created_at: self.created_at,
+            updated_at: now_ms,
+            client_order_id: self.client_order_id,
+            product_id: self.product_id,
+            subaccount_id: None,
+            side: Some(self.side),
+            price: Some(raw_to_decimal(self.price_raw)),
+            filled: Some(raw_to_decimal(filled_raw)),
+        }
+    }
+}
+
+fn raw_to_decimal(raw: u128) -> BigDecimal {
+    BigDecimal::from_str(&format_order_decimal(raw)).expect("formatted decimal parses")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use bigdecimal::BigDecimal;
+    use uuid::Uuid;
+
+    use super::{PaperBook, PaperReject};
+    use crate::models::common::{OrderStatus, TimeInForce};
+    use crate::models::dto::{MarketPriceData, TradeOrderData};
+
+    const ONCHAIN_ID: u32 = 7;
+
+    fn book(product_id: Uuid) -> PaperBook {
+        let mut book = PaperBook::default();
+        book.register_product(product_id, ONCHAIN_ID);
+        book
+    }
+
+    fn order(side: u8, price: &str, post_only: bool, time_in_force: TimeInForce) -> TradeOrderData {
+        TradeOrderData {
+            sender: "0x0".to_string(),
+            subaccount: "0x0".to_string(),
+            quantity: "0.100000000".to_string(),
+            price: price.to_string(),
+            reduce_only: false,
+            side,
+            engine_type: 0,
+            onchain_id: ONCHAIN_ID,
+            nonce: "1".to_string(),
+            signed_at: 1,
+            order_type: "LIMIT".to_string(),
+            time_in_force,
+            post_only,
+            client_order_id: Uuid::new_v4(),
+        }
+    }
+
+    fn tick(product_id: Uuid, bid: &str, ask: &str) -> MarketPriceData {
+        MarketPriceData {
+            product_id,
+            best_bid_price: BigDecimal::from_str(bid).unwrap(),
+            best_ask_price: BigDecimal::from_str(ask).unwrap(),
+            oracle_price: BigDecimal::from_str(bid).unwrap(),
+            price24h_ago: BigDecimal::from_str(bid).unwrap(),
+        }
+    }
+
+    #[test]
+    fn resting_buy_fills_when_ask_trades_through() {
+        let product_id = Uuid::new_v4();
+        let mut book = book(product_id);
+        book.on_market_price(&tick(product_id, "100", "101"), 1);
+
+        let buy = order(0, "100.000000000", true, TimeInForce::Gtd);
+        let update = book.submit(&buy, 2).unwrap();
+        assert_eq!(update.status, OrderStatus::New);
+
+        assert!(
+            book.on_market_price(&tick(product_id, "100.5", "101"), 3)
+                .is_empty()
+        );
+
+        let fills = book.on_market_price(&tick(product_id, "99", "100"), 4);
+        assert_eq!(fills.len(), 1);
+        assert_eq!(fills[0].client_order_id, buy.client_order_id);
+        assert_eq!(fills[0].status, OrderStatus::Filled);
+        assert_eq!(fills[0].id, update.id);
+        assert_eq!(fills[0].created_at, 2);
+        assert_eq!(fills[0].updated_at, 4);
+        assert_eq!(fills[0].product_id, Some(product_id));
+        assert_eq!(fills[0].filled, Some(BigDecimal::from_str("0.1").unwrap()));
+    }
+
+    #[test]
+    fn resting_sell_fills_when_bid_trades_through() {
+        let product_id = Uuid::new_v4();
+        let mut book = book(product_id);
+
+        let sell = order(1, "101.000000000", true, TimeInForce::Gtd);
+        assert_eq!(book.submit(&sell, 1).unwrap().status, OrderStatus::New);
+
+        let fills = book.on_market_price(&tick(product_id, "101", "102"), 2);
+        assert_eq!(fills.len(), 1);
+        assert_eq!(fills[0].status, OrderStatus::Filled);
+    }
+
+    #[test]
+    fn post_only_crossing_order_is_rejected() {
+        let product_id = Uuid::new_v4();
+        let mut book = book(product_id);
+        book.on_market_price(&tick(product_id, "100", "101"), 1);
+
+        let buy = order(0, "101.000000000", true, TimeInForce::Gtd);
+        assert_eq!(book.submit(&buy, 2), Err(PaperReject::PostOnlyWouldCross));
+        assert!(book.cancel(buy.client_order_id, 3).is_none());
+    }
+
+    #[test]
+    fn marketable_taker_order_fills_immediately() {
+        let product_id = Uuid::new_v4();
+        let mut book = book(product_id);
+        book.on_market_price(&tick(product_id, "100", "101"), 1);
+
+        let sell = order(1, "100.000000000", false, TimeInForce::Gtd);
+        assert_eq!(book.submit(&sell, 2).unwrap().status, OrderStatus::Filled);
+        assert!(book.cancel(sell.client_order_id, 3).is_none());
+    }
+
+    #[test]
+    fn ioc_and_fok_never_rest() {
+        let product_id = Uuid::new_v4();
+        let mut book = book(product_id);
+        book.on_market_price(&tick(product_id, "100", "101"), 1);
+
+        for time_in_force in [TimeInForce::Ioc, TimeInForce::Fok] {
+            let passive = order(0, "99.000000000", false, time_in_force);
+            assert_eq!(
+                book.submit(&passive, 2).unwrap().status,
+                OrderStatus::Canceled
+            );
+            assert!(book.cancel(passive.client_order_id, 3).is_none());
+
+            let aggressive = order(0, "101.000000000", false, time_in_force);
+            assert_eq!(
+                book.submit(&aggressive, 4).unwrap().status,
+                OrderStatus::Filled
+            );
+        }
+    }
+
+    #[test]
+    fn cancel_removes_resting_order_once() {
+        let product_id = Uuid::new_v4();
+        let mut book = book(product_id);
+
+        let buy = order(0, "100.000000000", true, TimeInForce::Gtd);
+        let accepted = book.submit(&buy, 1).unwrap();
+
+        let canceled = book.cancel_by_order_id(accepted.id, 2).unwrap();
+        assert_eq!(canceled.status, OrderStatus::Canceled);
+        assert_eq!(canceled.client_order_id, buy.client_order_id);
+        assert!(book.cancel(buy.client_order_id, 3).is_none());
+        assert!(
+            book.on_market_price(&tick(product_id, "98", "99"), 4)
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn ticks_for_other_products_do_not_fill() {
+        let product_id = Uuid::new_v4();
+        let mut book = book(product_id);
+
+        let buy = order(0, "100.000000000", true, TimeInForce::Gtd);
+        book.submit(&buy, 1).unwrap();
+
+        assert!(
+            book.on_market_price(&tick(Uuid::new_v4(), "98", "99"), 2)
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn open_orders_list_resting_orders_oldest_first() {
+        let product_id = Uuid::new_v4();
+        let mut book = book(product_id);
+
+        let sell = order(1, "101.000000000", true, TimeInForce::Gtd);
+        let buy = order(0, "100.000000000", true, TimeInForce::Gtd);
+        book.submit(&sell, 1).unwrap();
+        book.submit(&buy, 2).unwrap();
+        book.submit(&order(0, "99.000000000", false, TimeInForce::Ioc), 3)
+            .unwrap();
+
+        let open = book.open_orders(product_id);
+        assert_eq!(open.len(), 2);
+        assert_eq!(open[0].client_order_id, Some(sell.client_order_id));
+        assert_eq!(open[0].price, BigDecimal::from_str("101").unwrap());
+        assert_eq!(open[0].quantity, BigDecimal::from_str("0.1").unwrap());
+        assert_eq!(open[1].client_order_id, Some(buy.client_order_id));
+        assert_eq!(open[1].side, 0);
+        assert!(book.open_orders(Uuid::new_v4()).is_empty());
+    }
+}
@@ -1,42 +1,84 @@
 mod error;
 mod live;
+mod matching;
 mod paper;
 
 pub use error::ExecutorError;
+use uuid::Uuid;
 
-use crate::models::dto::{CancelOrderRequest, OrderRequest};
+use crate::models::dto::{
+    CancelOrderRequest, CancelOrderResponse, OpenOrderData, OrderRequest, PositionData,
+    SubmitOrderResponse,
+};
 
 pub(crate) trait OrderExecutor: Send + Sync {
     async fn submit_order(
         &self,
         request: &OrderRequest,
-    ) -> Result<serde_json::Value, ExecutorError>;
+    ) -> Result<SubmitOrderResponse, ExecutorError>;
 
     async fn cancel_order(
         &self,
         request: &CancelOrderRequest,
-    ) -> Result<serde_json::Value, ExecutorError>;
+    ) -> Result<CancelOrderResponse, ExecutorError>;
+
+    /// Working orders of `subaccount_id` on `product_id`, oldest first.
+    async fn open_orders(
+        &self,
+        subaccount_id: Uuid,
+        product_id: Uuid,
+    ) -> Result<Vec<OpenOrderData>, ExecutorError>;
+
+    /// The order of `subaccount_id` with `client_order_id`, whatever its status.
+    async fn order_by_client_id(
+        &self,
+        subaccount_id: Uuid,
+        client_order_id: Uuid,
+    ) -> Result<Option<OpenOrderData>, ExecutorError>;
+
+    /// Open positions of `subaccount_id` across every product.
+    async fn positions(&self, subaccount_id: Uuid) -> Result<Vec<PositionData>, ExecutorError>;
 }
 
-pub(crate) fn is_cancel_accepted(payload: &serde_json::Value) -> bool {
-    let Some(items) = payload.get("data").and_then(|value| value.as_array()) else {
-        return false;
-    };
+/// Joins an API path onto the configured base url; `Url` always keeps a
+/// trailing slash on the root, which would otherwise double up.
+pub(crate) fn endpoint(rest_url: &url::Url, path: &str) -> String {
+    format!("{}{path}", rest_url.as_str().trim_end_matches('/'))
+}
 
-    if items.is_empty() {
-        return false;
+/// Reads a successful response whose `field` is `Ok` for an accepted
+/// request, refusing it with the code otherwise. The code is read first, as a
+/// refusal leaves out the fields an accepted request carries.
+pub(crate) fn parse_accepted<T: serde::de::DeserializeOwned>(
+    status: u16,
+    body: String,
+    field: &str,
+) -> Result<T, ExecutorError> {
+    let value: serde_json::Value = serde_json::from_str(&body)?;
+    match value.get(field).and_then(serde_json::Value::as_str) {
+        Some("Ok") => Ok(serde_json::from_value(value)?),
+        Some(code) => Err(ExecutorError::refused(status, code, body)),
+        None => Err(ExecutorError::Rejected {
+            status,
+            payload: body,
+        }),
     }
+}
 
-    items.iter().all(|item| {
-        matches!(
-            item.get("result").and_then(|value| value.as_str()),
-            Some("Ok")
-                | Some("AlreadyCanceled")
-                | Some("AlreadyExpired")
-                | Some("AlreadyFilled")
-                | Some("NotFound")
-        )
-    })
+/// Refuses a cancel response unless every targeted order is accepted as
+/// no longer working; an empty result list is refused too.
+pub(crate) fn check_cancel(
+    status: u16,
+    response: &CancelOrderResponse,
+    payload: String,
+) -> Result<(), ExecutorError> {
+    if response.data.is_empty() {
+        return Err(ExecutorError::Rejected { status, payload });
+    }
+    match response.data.iter().find(|item| !item.is_accepted()) {
+        Some(item) => Err(ExecutorError::refused(status, &item.result, payload)),
+        None => Ok(()),
+    }
 }
 
 pub(crate) enum OrderExecutorRuntime {
@@ -48,7 +90,7 @@ impl OrderExecutorRuntime {
     pub(crate) async fn submit_order(
         &self,
         request: &OrderRequest,
-    ) -> Result<serde_json::Value, ExecutorError> {
+    ) -> Result<SubmitOrderResponse, ExecutorError> {
         match self {
             Self::Live(executor) => executor.submit_order(request).await,
             Self::Paper(executor) => executor.submit_order(request).await,
@@ -58,20 +100,67 @@ impl OrderExecutorRuntime {
     pub(crate) async fn cancel_order(
         &self,
         request: &CancelOrderRequest,
-    ) -> Result<serde_json::Value, ExecutorError> {
+    ) -> Result<CancelOrderResponse, ExecutorError> {
         match self {
             Self::Live(executor) => executor.cancel_order(request).await,
             Self::Paper(executor) => executor.cancel_order(request).await,
         }
     }
+
+    pub(crate) async fn open_orders(
+        &self,
+        subaccount_id: Uuid,
+        product_id: Uuid,
+    ) -> Result<Vec<OpenOrderData>, ExecutorError> {
+        match self {
+            Self::Live(executor) => executor.open_orders(subaccount_id, product_id).await,
+            Self::Paper(executor) => executor.open_orders(subaccount_id, product_id).await,
+        }
+    }
+
+    pub(crate) async fn order_by_client_id(
+        &self,
+        subaccount_id: Uuid,
+        client_order_id: Uuid,
+    ) -> Result<Option<OpenOrderData>, ExecutorError> {
+        match self {
+            Self::Live(executor) => {
+                executor
+                    .order_by_client_id(subaccount_id, client_order_id)
+                    .await
+            }
+            Self::Paper(executor) => {
+                executor
+                    .order_by_client_id(subaccount_id, client_order_id)
+                    .await
+            }
+        }
+    }
+
+    pub(crate) async fn positions(
+        &self,
+        subaccount_id: Uuid,
+    ) -> Result<Vec<PositionData>, ExecutorError> {
+        match self {
+            Self::Live(executor) => executor.positions(subaccount_id).await,
+            Self::Paper(executor) => executor.positions(subaccount_id).await,
+        }
+    }
 }
 
 pub(crate) use live::LiveExecutor;
-pub(crate) use paper::PaperExecutor;
+pub(crate) use matching::PaperBook;
+pub(crate) use paper::{PaperExchange, PaperExecutor};
 
 #[cfg(test)]
 mod tests {
-    use super::is_cancel_accepted;
+    use super::{ExecutorError, check_cancel, endpoint, parse_accepted};
+    use crate::models::dto::{CancelOrderResponse, DryRunResponse, SubmitOrderResponse};
+
+    fn cancel(body: serde_json::Value) -> Result<(), ExecutorError> {
+        let response: CancelOrderResponse = serde_json::from_value(body.clone()).unwrap();
+        check_cancel(200, &response, body.to_string())
+    }
 
     #[test]
     fn cancel_accepts_ok_and_idempotent_results() {
@@ -83,7 +172,7 @@ mod tests {
             ]
         });
 
-        assert!(is_cancel_accepted(&payload));
+        assert!(cancel(payload).is_ok());
     }
 
     #[test]
@@ -95,7 +184,57 @@ mod tests {
             "data": [{ "id": "9036443a-441a-4a66-87f2-bd5c44cdca7a", "result": "NonceAlreadyUsed" }]
         });
 
-        assert!(!is_cancel_accepted(&unknown));
-        assert!(!is_cancel_accepted(&nonce));
+        assert!(matches!(
+            cancel(unknown),
+            Err(ExecutorError::Rejected { status: 200, .. })
+        ));
+        assert!(matches!(
+            cancel(nonce),
+            Err(ExecutorError::NonceAlreadyUsed)
+        ));
+        assert!(cancel(serde_json::json!({ "data": [] })).is_err());
+    }
+
+    #[test]
+    fn refusals_without_order_fields_keep_their_code() {
+        let submit =
+            |body: &str| parse_accepted::<SubmitOrderResponse>(200, body.to_string(), "result");
+        let dry_run = |body: &str| parse_accepted::<DryRunResponse>(200, body.to_string(), "code");
+
+        assert!(matches!(
+            submit(r#"{"result":"PostOnlyWouldCross"}"#),
+            Err(ExecutorError::PostOnlyWouldCross)
+        ));
+        assert!(matches!(
+            dry_run(r#"{"code":"InsufficientMargin"}"#),
+            Err(ExecutorError::InsufficientMargin)
+        ));
+        assert!(matches!(
+            submit(r#"{"result":"Unlisted"}"#),
+            Err(ExecutorError::Rejected { status: 200, .. })
+        ));
+        assert!(matches!(
+            submit(r#"{"result":"Ok"}"#),
+            Err(ExecutorError::Serde(_))
+        ));
+        let accepted =
+            submit(r#"{"id":"9036443a-441a-4a66-87f2-bd5c44cdca7a","filled":"0","result":"Ok"}"#)
+                .unwrap();
+        assert_eq!(accepted.result, "Ok");
+    }
+
+    #[test]
+    fn endpoint_does_not_double_slashes() {
+        let root: url::Url = "https://api.ethereal.trade".parse().unwrap();
+        let prefixed: url::Url = "https://proxy.local/ethereal/".parse().unwrap();
+
+        assert_eq!(
+            endpoint(&root, "/v1/order"),
+            "https://api.ethereal.trade/v1/order"
+        );
+        assert_eq!(
+            endpoint(&prefixed, "/v1/order"),
+            "https://proxy.local/ethereal/v1/order"
+        );
     }
 }
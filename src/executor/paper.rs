@@ -1,77 +1,207 @@
-use super::{ExecutorError, OrderExecutor};
-use crate::models::dto::{CancelOrderRequest, OrderRequest};
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+use super::matching::{PaperBook, PaperReject};
+use super::{ExecutorError, OrderExecutor, endpoint, parse_accepted};
+use crate::logging::targets;
+use crate::models::dto::{
+    CancelOrderData, CancelOrderRequest, CancelOrderResponse, CancelResultData, DryRunResponse,
+    MarketPriceData, OpenOrderData, OrderRequest, OrderUpdateData, PositionData,
+    SubmitOrderResponse,
+};
+use crate::runtime::RuntimeEvent;
 
 pub(crate) struct PaperExecutor {
     http_client: reqwest::Client,
     rest_url: url::Url,
+    exchange: PaperExchange,
 }
 
 impl PaperExecutor {
-    pub fn new(http_client: reqwest::Client, rest_url: url::Url) -> Self {
+    pub fn new(http_client: reqwest::Client, rest_url: url::Url, exchange: PaperExchange) -> Self {
         Self {
             http_client,
             rest_url,
+            exchange,
         }
     }
 }
 
-fn is_submit_created(payload: &serde_json::Value) -> bool {
-    payload.get("marginRequired").is_some()
-        && payload.get("marginAvailable").is_some()
-        && payload.get("totalUsedMargin").is_some()
-        && payload.get("riskUsed").is_some()
-        && payload.get("riskAvailable").is_some()
-        && payload.get("code").is_some()
+/// Local simulated exchange shared between the paper executor and the market
+/// data feed. Every state change is published as a synthetic `OrderUpdate`.
+#[derive(Clone)]
+pub(crate) struct PaperExchange {
+    book: Arc<Mutex<PaperBook>>,
+    event_sender: mpsc::UnboundedSender<RuntimeEvent>,
+}
+
+impl PaperExchange {
+    pub fn new(event_sender: mpsc::UnboundedSender<RuntimeEvent>) -> Self {
+        Self {
+            book: Arc::new(Mutex::new(PaperBook::default())),
+            event_sender,
+        }
+    }
+
+    pub fn register_product(&self, product_id: Uuid, onchain_id: u32) {
+        self.book
+            .lock()
+            .expect("paper book poisoned")
+            .register_product(product_id, onchain_id);
+    }
+
+    pub fn on_market_price(&self, tick: &MarketPriceData) {
+        let fills = self
+            .book
+            .lock()
+            .expect("paper book poisoned")
+            .on_market_price(tick, now_ms());
+
+        for fill in fills {
+            tracing::info!(
+                target: targets::RUNTIME_EXEC,
+                client_order_id = %fill.client_order_id,
+                product_id = %tick.product_id,
+                "paper order filled"
+            );
+            self.publish(fill);
+        }
+    }
+
+    pub fn open_orders(&self, product_id: Uuid) -> Vec<OpenOrderData> {
+        self.book
+            .lock()
+            .expect("paper book poisoned")
+            .open_orders(product_id)
+    }
+
+    fn submit(&self, request: &OrderRequest) -> Result<OrderUpdateData, PaperReject> {
+        let update = self
+            .book
+            .lock()
+            .expect("paper book poisoned")
+            .submit(&request.data, now_ms())?;
+        self.publish(update.clone());
+        Ok(update)
+    }
+
+    fn cancel(&self, data: &CancelOrderData) -> CancelOrderResponse {
+        let now = now_ms();
+        let mut results = Vec::new();
+        let mut canceled = Vec::new();
+
+        {
+            let mut book = self.book.lock().expect("paper book poisoned");
+            for &client_order_id in &data.client_order_ids {
+                let update = book.cancel(client_order_id, now);
+                results.push(cancel_result(client_order_id, update.is_some()));
+                canceled.extend(update);
+            }
+            for &order_id in &data.order_ids {
+                let update = book.cancel_by_order_id(order_id, now);
+                results.push(cancel_result(order_id, update.is_some()));
+                canceled.extend(update);
+            }
+        }
+
+        for update in canceled {
+            self.publish(update);
+        }
+
+        CancelOrderResponse { data: results }
+    }
+
+    fn publish(&self, update: OrderUpdateData) {
+        // The strategy may already be gone during shutdown; nothing to notify then.
+        let _ = self.event_sender.send(RuntimeEvent::OrderUpdate(update));
+    }
+}
+
+fn cancel_result(id: Uuid, canceled: bool) -> CancelResultData {
+    let result = if canceled { "Ok" } else { "NotFound" };
+    CancelResultData {
+        id,
+        result: result.to_string(),
+    }
+}
+
+fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as u64
 }
 
 impl OrderExecutor for PaperExecutor {
     async fn submit_order(
         &self,
         request: &OrderRequest,
-    ) -> Result<serde_json::Value, ExecutorError> {
+    ) -> Result<SubmitOrderResponse, ExecutorError> {
         let payload = serde_json::json!({ "data": &request.data });
         let response = self
             .http_client
-            .post(format!("{}/v1/order/dry-run", self.rest_url))
+            .post(endpoint(&self.rest_url, "/v1/order/dry-run"))
             .json(&payload)
             .send()
             .await?;
         let status = response.status();
+        let body = response.text().await?;
 
         if !status.is_success() {
-            let body = response.text().await?;
-            return Err(ExecutorError::Rejected {
-                status: status.as_u16(),
-                payload: body,
-            });
+            return Err(ExecutorError::from_response(status.as_u16(), body));
         }
 
-        let payload: serde_json::Value = response.json().await?;
+        let _: DryRunResponse = parse_accepted(status.as_u16(), body, "code")?;
 
-        if is_submit_created(&payload) {
-            Ok(payload)
-        } else {
-            Err(ExecutorError::Rejected {
-                status: status.as_u16(),
-                payload: payload.to_string(),
-            })
+        match self.exchange.submit(request) {
+            Ok(update) => Ok(SubmitOrderResponse {
+                id: update.id,
+                client_order_id: Some(update.client_order_id),
+                filled: update.filled.unwrap_or_default(),
+                result: "Ok".to_string(),
+            }),
+            Err(reject) => Err(ExecutorError::refused(
+                400,
+                reject.code(),
+                serde_json::json!({
+                    "code": reject.code(),
+                    "message": reject.to_string(),
+                })
+                .to_string(),
+            )),
         }
     }
 
     async fn cancel_order(
         &self,
-        _request: &CancelOrderRequest,
-    ) -> Result<serde_json::Value, ExecutorError> {
-        let payload = serde_json::json!({
-            "data": [{
-                "result": "PaperCancelUnsupported",
-                "message": "cancel is not supported for paper executor yet"
-            }]
-        });
-
-        Err(ExecutorError::Rejected {
-            status: 422,
-            payload: payload.to_string(),
-        })
+        request: &CancelOrderRequest,
+    ) -> Result<CancelOrderResponse, ExecutorError> {
+        Ok(self.exchange.cancel(&request.data))
+    }
+
+    /// The simulated book is process-local, so there is only one subaccount.
+    async fn open_orders(
+        &self,
+        _subaccount_id: Uuid,
+        product_id: Uuid,
+    ) -> Result<Vec<OpenOrderData>, ExecutorError> {
+        Ok(self.exchange.open_orders(product_id))
+    }
+
+    /// Paper updates are published as they happen and never go missing, so
+    /// closed orders are not kept.
+    async fn order_by_client_id(
+        &self,
+        _subaccount_id: Uuid,
+        _client_order_id: Uuid,
+    ) -> Result<Option<OpenOrderData>, ExecutorError> {
+        Ok(None)
+    }
+
+    /// Paper fills never reach the exchange, so every run starts flat.
+    async fn positions(&self, _subaccount_id: Uuid) -> Result<Vec<PositionData>, ExecutorError> {
+        Ok(Vec::new())
     }
 }
@@ -1,7 +1,11 @@
+mod backtest;
 mod error;
 mod executor;
 mod logging;
+#[cfg(any(test, feature = "mock-server"))]
+pub mod mock;
 mod models;
+mod risk;
 mod runtime;
 mod settings;
 mod signer;
@@ -10,25 +14,96 @@ mod trading;
 #[cfg(test)]
 mod tests;
 
-use error::EtherealRuntimeError;
+use std::future::Future;
+
+pub use backtest::{
+    BacktestError, BacktestReport, HistoricalTick, load_ticks, run_backtest, run_backtest_file,
+    run_backtest_with,
+};
+pub use error::EtherealRuntimeError;
 use executor::ExecutorError;
-pub use logging::{LoggingGuards, init_logging};
-use runtime::{EtherealRuntime, RuntimeEvent};
+pub use logging::{LoggingGuards, init_logging, targets};
+pub use models::common::OrderStatus;
+pub use models::dto::{MarketPriceData, OrderUpdateData};
+pub use risk::{RiskConfig, RiskRejection};
+use runtime::EtherealRuntime;
+pub use runtime::{
+    CaptureError, ChannelListener, ChannelPeer, ChannelTransport, ProductClient, ProductError,
+    ProductInfo, ReplaySpeed, ReplaySummary, RuntimeEvent, TungsteniteTransport,
+    WsTransportRuntime, replay_capture, resolve_product_config, shutdown_signal,
+};
 pub use settings::Config;
+pub use trading::policy::MarketMaker;
+pub use trading::position::{Fill, Position, PositionKey, PositionLedger};
+pub use trading::settings::{StrategyConfig, StrategyKind};
+pub use trading::state::{OrderState, OrderTracker, Side, TrackedOrder};
+pub use trading::strategy::{OrderIntent, Strategy, StrategyContext, StrategyRuntime};
+
+/// Runs the strategy until `shutdown` resolves, then cancels open orders and
+/// closes the websocket. `ShutdownIncomplete` means some orders may still rest.
+///
+/// Refuses to start when the strategy's product fields disagree with
+/// `/v1/product`; fields left at 0 are filled from it.
+pub async fn run_strategy(
+    config: &Config,
+    shutdown: impl Future<Output = ()>,
+) -> Result<(), EtherealRuntimeError> {
+    run_strategy_with(config, StrategyRuntime::from_config, shutdown).await
+}
+
+/// [`run_strategy`] with caller-supplied strategies in place of the ones
+/// selected by `kind`; `make_strategy` is called once per product.
+pub async fn run_strategy_with<S: Strategy>(
+    config: &Config,
+    make_strategy: impl FnMut(&StrategyConfig) -> S,
+    shutdown: impl Future<Output = ()>,
+) -> Result<(), EtherealRuntimeError> {
+    let config = resolve_product_config(config).await?;
+    let (runtime, market_events) = EtherealRuntime::new(&config).await?;
+    drive_strategy(&config, runtime, make_strategy, market_events, shutdown).await
+}
 
-async fn build_runtime(
+/// [`run_strategy`] over a caller-supplied transport, e.g. a [`ChannelTransport`]
+/// driven by an in-process simulator.
+pub async fn run_strategy_with_transport(
     config: &Config,
-) -> Result<
-    (
-        EtherealRuntime,
-        tokio::sync::mpsc::UnboundedReceiver<RuntimeEvent>,
-    ),
-    EtherealRuntimeError,
-> {
-    EtherealRuntime::new(config).await
+    transport: WsTransportRuntime,
+    shutdown: impl Future<Output = ()>,
+) -> Result<(), EtherealRuntimeError> {
+    let config = resolve_product_config(config).await?;
+    let (runtime, market_events) = EtherealRuntime::with_transport(&config, transport).await?;
+    drive_strategy(
+        &config,
+        runtime,
+        StrategyRuntime::from_config,
+        market_events,
+        shutdown,
+    )
+    .await
 }
 
-pub async fn run_strategy(config: &Config) -> Result<(), EtherealRuntimeError> {
-    let (runtime, market_events) = build_runtime(config).await?;
-    trading::run_strategy_loop(&runtime, &config.strategy, market_events).await
+async fn drive_strategy<S: Strategy>(
+    config: &Config,
+    runtime: EtherealRuntime,
+    make_strategy: impl FnMut(&StrategyConfig) -> S,
+    market_events: tokio::sync::mpsc::UnboundedReceiver<RuntimeEvent>,
+    shutdown: impl Future<Output = ()>,
+) -> Result<(), EtherealRuntimeError> {
+    let halt_commands =
+        trading::halt::spawn_operator_controls(config.strategies.iter().map(|s| &s.halt));
+    let result = trading::run_strategy_loop(
+        &runtime,
+        &config.strategies,
+        make_strategy,
+        market_events,
+        halt_commands,
+        shutdown,
+    )
+    .await;
+
+    if let Err(error) = runtime.shutdown().await {
+        tracing::warn!(target: targets::RUNTIME_WS, %error, "websocket did not close cleanly");
+    }
+
+    result
 }
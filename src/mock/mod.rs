@@ -0,0 +1,299 @@
+//! Local stand-in for the Ethereal REST API, for executor tests that must not
+//! touch testnet. Serves `/v1/order`, `/v1/order/cancel`, `/v1/order/dry-run`,
+//! `/v1/position` and `/v1/product`, verifies EIP-712 signatures against `make_domain`, keeps
+//! order state and can inject one-shot faults per route.
+
+mod state;
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use alloy::primitives::Address;
+use axum::Json;
+use axum::extract::{RawQuery, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use uuid::Uuid;
+
+use self::state::{
+    ApiError, CancelPayload, ListOrdersQuery, MockState, OrderPayload, SignedRequest,
+};
+use crate::models::common::{OrderStatus, TimeInForce};
+use crate::models::util::parse_order_decimal;
+use crate::runtime::make_domain;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MockRoute {
+    SubmitOrder,
+    ListOrders,
+    CancelOrder,
+    DryRun,
+    Positions,
+    Products,
+}
+
+/// Applied to the next request on a route, in the order they were injected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    /// Answer with this status and an exchange-style error body.
+    Status(u16),
+    /// Sleep before handling the request normally.
+    Delay(Duration),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MockProduct {
+    pub id: Uuid,
+    pub onchain_id: u32,
+    pub ticker: String,
+    pub tick_size: String,
+    pub lot_size: String,
+    pub min_quantity: String,
+    pub max_quantity: String,
+    pub max_leverage: u32,
+    pub maker_fee: String,
+    pub taker_fee: String,
+}
+
+impl MockProduct {
+    pub fn new(id: Uuid, onchain_id: u32) -> Self {
+        Self {
+            id,
+            onchain_id,
+            ticker: "BTCUSD".to_string(),
+            tick_size: "1".to_string(),
+            lot_size: "0.0001".to_string(),
+            min_quantity: "0.0001".to_string(),
+            max_quantity: "1000".to_string(),
+            max_leverage: 20,
+            maker_fee: "0".to_string(),
+            taker_fee: "0.0003".to_string(),
+        }
+    }
+
+    fn tick_size_raw(&self) -> u128 {
+        parse_order_decimal(&self.tick_size).unwrap_or(1).max(1)
+    }
+
+    fn lot_size_raw(&self) -> u128 {
+        parse_order_decimal(&self.lot_size).unwrap_or(1).max(1)
+    }
+
+    fn min_quantity_raw(&self) -> u128 {
+        parse_order_decimal(&self.min_quantity).unwrap_or(0)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MockOrder {
+    pub id: Uuid,
+    pub client_order_id: Uuid,
+    pub product_id: Uuid,
+    pub onchain_id: u32,
+    pub side: u8,
+    pub price: String,
+    pub quantity: String,
+    pub time_in_force: TimeInForce,
+    pub post_only: bool,
+    pub status: OrderStatus,
+    pub created_at: u64,
+}
+
+/// An open position served by `GET /v1/position`, in the exchange's decimals.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MockPosition {
+    pub product_id: Uuid,
+    pub side: u8,
+    pub size: String,
+    pub cost: String,
+}
+
+type SharedState = Arc<Mutex<MockState>>;
+
+/// Mock exchange bound to an ephemeral localhost port; stops when dropped.
+pub struct MockExchange {
+    url: url::Url,
+    state: SharedState,
+    server: tokio::task::JoinHandle<()>,
+}
+
+impl MockExchange {
+    pub async fn start(chain_id: u64, exchange: Address) -> std::io::Result<Self> {
+        let state = Arc::new(Mutex::new(MockState::new(make_domain(chain_id, exchange))));
+
+        let router = axum::Router::new()
+            .route("/v1/order", post(submit_order).get(list_orders))
+            .route("/v1/order/cancel", post(cancel_order))
+            .route("/v1/order/dry-run", post(dry_run_order))
+            .route("/v1/position", get(list_positions))
+            .route("/v1/product", get(list_products))
+            .with_state(state.clone());
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let address = listener.local_addr()?;
+        let server = tokio::spawn(async move {
+            let _ = axum::serve(listener, router).await;
+        });
+
+        Ok(Self {
+            url: format!("http://{address}")
+                .parse()
+                .expect("socket address is a valid url"),
+            state,
+            server,
+        })
+    }
+
+    /// Base url to use as `rest_url`.
+    pub fn url(&self) -> &url::Url {
+        &self.url
+    }
+
+    pub fn add_product(&self, product: MockProduct) {
+        self.lock().add_product(product);
+    }
+
+    pub fn inject(&self, route: MockRoute, fault: Fault) {
+        self.lock().push_fault(route, fault);
+    }
+
+    /// Applies `fault` to the next `POST /v1/order` for `side` only.
+    pub fn inject_on_side(&self, side: u8, fault: Fault) {
+        self.lock().push_side_fault(side, fault);
+    }
+
+    pub fn orders(&self) -> Vec<MockOrder> {
+        self.lock().orders()
+    }
+
+    pub fn order(&self, client_order_id: Uuid) -> Option<MockOrder> {
+        self.lock().order(client_order_id)
+    }
+
+    /// Replaces the open position on the position's product.
+    pub fn set_position(&self, position: MockPosition) {
+        self.lock().set_position(position);
+    }
+
+    /// Caps the page size of `GET /v1/order` and `GET /v1/position` below whatever `limit` the client asks for.
+    pub fn set_page_limit(&self, limit: usize) {
+        self.lock().set_page_limit(limit);
+    }
+
+    /// Moves an order to `status`, e.g. to simulate a fill before a cancel.
+    pub fn set_order_status(&self, client_order_id: Uuid, status: OrderStatus) -> bool {
+        self.lock().set_status(client_order_id, status)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, MockState> {
+        self.state.lock().expect("mock state poisoned")
+    }
+}
+
+impl Drop for MockExchange {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.status, Json(self.body())).into_response()
+    }
+}
+
+/// Runs the queued fault for `route`, if any; `Err` short-circuits the handler.
+async fn apply_fault(state: &SharedState, route: MockRoute) -> Result<(), ApiError> {
+    let fault = state.lock().expect("mock state poisoned").next_fault(route);
+    run_fault(fault).await
+}
+
+async fn run_fault(fault: Option<Fault>) -> Result<(), ApiError> {
+    match fault {
+        None => Ok(()),
+        Some(Fault::Delay(delay)) => {
+            tokio::time::sleep(delay).await;
+            Ok(())
+        }
+        Some(Fault::Status(status)) => {
+            let status = StatusCode::from_u16(status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+            Err(ApiError::new(status, "injected fault"))
+        }
+    }
+}
+
+fn parse_body<T: serde::de::DeserializeOwned>(body: &[u8]) -> Result<T, ApiError> {
+    serde_json::from_slice(body)
+        .map_err(|error| ApiError::new(StatusCode::BAD_REQUEST, error.to_string()))
+}
+
+async fn submit_order(
+    State(state): State<SharedState>,
+    body: axum::body::Bytes,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    apply_fault(&state, MockRoute::SubmitOrder).await?;
+    let request: SignedRequest<OrderPayload> = parse_body(&body)?;
+    let side_fault = state
+        .lock()
+        .expect("mock state poisoned")
+        .next_side_fault(request.data.side());
+    run_fault(side_fault).await?;
+    let mut state = state.lock().expect("mock state poisoned");
+    state.submit(request, now_ms()).map(Json)
+}
+
+async fn list_orders(
+    State(state): State<SharedState>,
+    RawQuery(query): RawQuery,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    apply_fault(&state, MockRoute::ListOrders).await?;
+    let query = ListOrdersQuery::parse(query.as_deref().unwrap_or_default())?;
+    let state = state.lock().expect("mock state poisoned");
+    Ok(Json(state.orders_page(&query)))
+}
+
+async fn cancel_order(
+    State(state): State<SharedState>,
+    body: axum::body::Bytes,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    apply_fault(&state, MockRoute::CancelOrder).await?;
+    let request: SignedRequest<CancelPayload> = parse_body(&body)?;
+    let mut state = state.lock().expect("mock state poisoned");
+    state.cancel(request).map(Json)
+}
+
+async fn dry_run_order(
+    State(state): State<SharedState>,
+    body: axum::body::Bytes,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    apply_fault(&state, MockRoute::DryRun).await?;
+    let request: SignedRequest<OrderPayload> = parse_body(&body)?;
+    let state = state.lock().expect("mock state poisoned");
+    state.dry_run(request).map(Json)
+}
+
+async fn list_positions(
+    State(state): State<SharedState>,
+    RawQuery(query): RawQuery,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    apply_fault(&state, MockRoute::Positions).await?;
+    let query = ListOrdersQuery::parse(query.as_deref().unwrap_or_default())?;
+    let state = state.lock().expect("mock state poisoned");
+    Ok(Json(state.positions_page(&query)))
+}
+
+async fn list_products(
+    State(state): State<SharedState>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    apply_fault(&state, MockRoute::Products).await?;
+    let state = state.lock().expect("mock state poisoned");
+    Ok(Json(state.products_page()))
+}
+
+fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as u64
+}
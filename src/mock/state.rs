@@ -0,0 +1,547 @@
+use std::collections::{HashMap, VecDeque};
+
+use alloy::primitives::{Address, FixedBytes, Signature};
+use alloy_sol_types::{Eip712Domain, SolStruct};
+use axum::http::StatusCode;
+use uuid::Uuid;
+
+use super::{Fault, MockOrder, MockPosition, MockProduct, MockRoute};
+use crate::models::common::{OrderStatus, TimeInForce};
+use crate::models::contracts::{CancelOrder, TradeOrder};
+use crate::models::util::{ORDER_DECIMALS, format_order_decimal, parse_order_decimal};
+
+const DEFAULT_PAGE_LIMIT: usize = 100;
+
+/// Error body in the shape the exchange uses for non-2xx responses.
+#[derive(Debug)]
+pub(super) struct ApiError {
+    pub status: StatusCode,
+    pub message: String,
+}
+
+impl ApiError {
+    pub fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+        }
+    }
+
+    fn bad_request(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, message)
+    }
+
+    pub fn body(&self) -> serde_json::Value {
+        serde_json::json!({
+            "statusCode": self.status.as_u16(),
+            "message": self.message,
+            "error": self.status.canonical_reason().unwrap_or("Error"),
+        })
+    }
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub(super) struct SignedRequest<T> {
+    pub data: T,
+    #[serde(default)]
+    pub signature: Option<String>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(super) struct OrderPayload {
+    sender: Address,
+    subaccount: FixedBytes<32>,
+    quantity: String,
+    price: String,
+    reduce_only: bool,
+    side: u8,
+    engine_type: u8,
+    onchain_id: u32,
+    nonce: String,
+    signed_at: u64,
+    #[serde(rename = "type")]
+    order_type: String,
+    #[serde(default)]
+    time_in_force: TimeInForce,
+    #[serde(default)]
+    post_only: bool,
+    client_order_id: Uuid,
+}
+
+impl OrderPayload {
+    pub fn side(&self) -> u8 {
+        self.side
+    }
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(super) struct CancelPayload {
+    sender: Address,
+    subaccount: FixedBytes<32>,
+    nonce: String,
+    #[serde(default)]
+    order_ids: Vec<Uuid>,
+    #[serde(default)]
+    client_order_ids: Vec<Uuid>,
+}
+
+/// Query of `GET /v1/order` and `GET /v1/position`. The mock serves a single subaccount, so
+/// `subaccountId` is required but not matched against anything.
+#[derive(Debug, Default)]
+pub(super) struct ListOrdersQuery {
+    product_ids: Vec<Uuid>,
+    client_order_id: Option<Uuid>,
+    is_working: bool,
+    limit: Option<usize>,
+    cursor: usize,
+}
+
+impl ListOrdersQuery {
+    pub fn parse(query: &str) -> Result<Self, ApiError> {
+        let mut parsed = Self::default();
+        let mut has_subaccount = false;
+
+        for (key, value) in url::form_urlencoded::parse(query.as_bytes()) {
+            match key.as_ref() {
+                "subaccountId" => has_subaccount = true,
+                "productIds" => parsed.product_ids.push(
+                    value
+                        .parse()
+                        .map_err(|_| ApiError::bad_request("productIds must be uuids"))?,
+                ),
+                "clientOrderId" => {
+                    parsed.client_order_id = Some(
+                        value
+                            .parse()
+                            .map_err(|_| ApiError::bad_request("clientOrderId must be a uuid"))?,
+                    )
+                }
+                "isWorking" => parsed.is_working = value == "true",
+                "limit" => {
+                    parsed.limit = Some(
+                        value
+                            .parse()
+                            .map_err(|_| ApiError::bad_request("limit must be an integer"))?,
+                    )
+                }
+                "cursor" => {
+                    parsed.cursor = value
+                        .parse()
+                        .map_err(|_| ApiError::bad_request("cursor is not valid"))?
+                }
+                _ => {}
+            }
+        }
+
+        if !has_subaccount {
+            return Err(ApiError::bad_request("subaccountId is required"));
+        }
+        Ok(parsed)
+    }
+}
+
+/// A validated order request with its decimal fields parsed to raw units.
+struct ParsedOrder {
+    price_raw: u128,
+    quantity_raw: u128,
+    product: MockProduct,
+}
+
+pub(super) struct MockState {
+    domain: Eip712Domain,
+    products: Vec<MockProduct>,
+    orders: HashMap<Uuid, MockOrder>,
+    positions: Vec<MockPosition>,
+    faults: HashMap<MockRoute, VecDeque<Fault>>,
+    /// Faults for the next order submits on a side, after any route fault.
+    side_faults: HashMap<u8, VecDeque<Fault>>,
+    page_limit: usize,
+}
+
+impl MockState {
+    pub fn new(domain: Eip712Domain) -> Self {
+        Self {
+            domain,
+            products: Vec::new(),
+            orders: HashMap::new(),
+            positions: Vec::new(),
+            faults: HashMap::new(),
+            side_faults: HashMap::new(),
+            page_limit: DEFAULT_PAGE_LIMIT,
+        }
+    }
+
+    pub fn add_product(&mut self, product: MockProduct) {
+        self.products.retain(|existing| existing.id != product.id);
+        self.products.push(product);
+    }
+
+    pub fn push_fault(&mut self, route: MockRoute, fault: Fault) {
+        self.faults.entry(route).or_default().push_back(fault);
+    }
+
+    pub fn next_fault(&mut self, route: MockRoute) -> Option<Fault> {
+        self.faults.get_mut(&route)?.pop_front()
+    }
+
+    pub fn push_side_fault(&mut self, side: u8, fault: Fault) {
+        self.side_faults.entry(side).or_default().push_back(fault);
+    }
+
+    pub fn next_side_fault(&mut self, side: u8) -> Option<Fault> {
+        self.side_faults.get_mut(&side)?.pop_front()
+    }
+
+    pub fn orders(&self) -> Vec<MockOrder> {
+        let mut orders: Vec<_> = self.orders.values().cloned().collect();
+        orders.sort_by_key(|order| (order.created_at, order.id));
+        orders
+    }
+
+    pub fn order(&self, client_order_id: Uuid) -> Option<MockOrder> {
+        self.orders.get(&client_order_id).cloned()
+    }
+
+    pub fn set_position(&mut self, position: MockPosition) {
+        self.positions
+            .retain(|existing| existing.product_id != position.product_id);
+        self.positions.push(position);
+    }
+
+    pub fn set_page_limit(&mut self, limit: usize) {
+        self.page_limit = limit.max(1);
+    }
+
+    pub fn set_status(&mut self, client_order_id: Uuid, status: OrderStatus) -> bool {
+        self.orders
+            .get_mut(&client_order_id)
+            .map(|order| order.status = status)
+            .is_some()
+    }
+
+    pub fn products_page(&self) -> serde_json::Value {
+        let data: Vec<_> = self.products.iter().map(product_json).collect();
+        serde_json::json!({ "hasNext": false, "data": data })
+    }
+
+    /// Lists orders oldest first.
+    pub fn orders_page(&self, query: &ListOrdersQuery) -> serde_json::Value {
+        let matching: Vec<_> = self
+            .orders()
+            .into_iter()
+            .filter(|order| {
+                query.product_ids.is_empty() || query.product_ids.contains(&order.product_id)
+            })
+            .filter(|order| {
+                query
+                    .client_order_id
+                    .is_none_or(|client_order_id| client_order_id == order.client_order_id)
+            })
+            .filter(|order| !query.is_working || is_working(order.status))
+            .collect();
+
+        self.page(query, &matching, order_json)
+    }
+
+    /// Lists positions in the order they were set; `productIds` is ignored.
+    pub fn positions_page(&self, query: &ListOrdersQuery) -> serde_json::Value {
+        self.page(query, &self.positions, position_json)
+    }
+
+    /// The page of `items` starting at the query's cursor, which is the
+    /// offset of the next page.
+    fn page<T>(
+        &self,
+        query: &ListOrdersQuery,
+        items: &[T],
+        to_json: fn(&T) -> serde_json::Value,
+    ) -> serde_json::Value {
+        let limit = query
+            .limit
+            .unwrap_or(self.page_limit)
+            .clamp(1, self.page_limit);
+        let end = items.len().min(query.cursor.saturating_add(limit));
+        let data: Vec<_> = items
+            .get(query.cursor..end)
+            .unwrap_or_default()
+            .iter()
+            .map(to_json)
+            .collect();
+        let has_next = end < items.len();
+
+        serde_json::json!({
+            "hasNext": has_next,
+            "nextCursor": has_next.then(|| end.to_string()),
+            "data": data,
+        })
+    }
+
+    pub fn submit(
+        &mut self,
+        request: SignedRequest<OrderPayload>,
+        now_ms: u64,
+    ) -> Result<serde_json::Value, ApiError> {
+        let order = &request.data;
+        let parsed = self.validate_order(order)?;
+
+        let signature = request
+            .signature
+            .as_deref()
+            .ok_or_else(|| ApiError::bad_request("signature is required"))?;
+        let typed = TradeOrder {
+            sender: order.sender,
+            subaccount: order.subaccount,
+            quantity: parsed.quantity_raw,
+            price: parsed.price_raw,
+            reduceOnly: order.reduce_only,
+            side: order.side,
+            engineType: order.engine_type,
+            productId: order.onchain_id,
+            nonce: parse_nonce(&order.nonce)?,
+            signedAt: order.signed_at,
+        };
+        self.verify(&typed, order.sender, signature)?;
+
+        if self.orders.contains_key(&order.client_order_id) {
+            return Err(ApiError::bad_request("Duplicate clientOrderId"));
+        }
+
+        let id = Uuid::new_v4();
+        self.orders.insert(order.client_order_id, MockOrder {
+            id,
+            client_order_id: order.client_order_id,
+            product_id: parsed.product.id,
+            onchain_id: order.onchain_id,
+            side: order.side,
+            price: format_order_decimal(parsed.price_raw),
+            quantity: format_order_decimal(parsed.quantity_raw),
+            time_in_force: order.time_in_force,
+            post_only: order.post_only,
+            status: OrderStatus::New,
+            created_at: now_ms,
+        });
+
+        Ok(serde_json::json!({
+            "id": id,
+            "clientOrderId": order.client_order_id,
+            "filled": "0",
+            "result": "Ok",
+        }))
+    }
+
+    pub fn dry_run(
+        &self,
+        request: SignedRequest<OrderPayload>,
+    ) -> Result<serde_json::Value, ApiError> {
+        let parsed = self.validate_order(&request.data)?;
+
+        let notional_raw = parsed.price_raw * parsed.quantity_raw / ORDER_DECIMALS;
+        let margin_raw = notional_raw / parsed.product.max_leverage.max(1) as u128;
+
+        Ok(serde_json::json!({
+            "marginRequired": format_order_decimal(margin_raw),
+            "marginAvailable": "1000000.000000000",
+            "totalUsedMargin": format_order_decimal(margin_raw),
+            "riskUsed": "0",
+            "riskAvailable": "1",
+            "code": "Ok",
+        }))
+    }
+
+    pub fn cancel(
+        &mut self,
+        request: SignedRequest<CancelPayload>,
+    ) -> Result<serde_json::Value, ApiError> {
+        let cancel = &request.data;
+        if cancel.order_ids.is_empty() && cancel.client_order_ids.is_empty() {
+            return Err(ApiError::bad_request(
+                "orderIds or clientOrderIds must not be empty",
+            ));
+        }
+
+        let signature = request
+            .signature
+            .as_deref()
+            .ok_or_else(|| ApiError::bad_request("signature is required"))?;
+        let typed = CancelOrder {
+            sender: cancel.sender,
+            subaccount: cancel.subaccount,
+            nonce: parse_nonce(&cancel.nonce)?,
+        };
+        self.verify(&typed, cancel.sender, signature)?;
+
+        let by_order_id = cancel.order_ids.iter().map(|id| {
+            let client_order_id = self
+                .orders
+                .values()
+                .find(|order| order.id == *id)
+                .map(|order| order.client_order_id);
+            (*id, client_order_id)
+        });
+        let by_client_id = cancel.client_order_ids.iter().map(|id| (*id, Some(*id)));
+        let targets: Vec<_> = by_order_id.chain(by_client_id).collect();
+
+        let data: Vec<_> = targets
+            .into_iter()
+            .map(|(id, client_order_id)| {
+                let order = client_order_id.and_then(|key| self.orders.get_mut(&key));
+                let result = match order {
+                    None => "NotFound",
+                    Some(order) => match order.status {
+                        OrderStatus::Canceled => "AlreadyCanceled",
+                        OrderStatus::Expired => "AlreadyExpired",
+                        OrderStatus::Filled => "AlreadyFilled",
+                        _ => {
+                            order.status = OrderStatus::Canceled;
+                            "Ok"
+                        }
+                    },
+                };
+                serde_json::json!({ "id": id, "result": result })
+            })
+            .collect();
+
+        Ok(serde_json::json!({ "data": data }))
+    }
+
+    fn validate_order(&self, order: &OrderPayload) -> Result<ParsedOrder, ApiError> {
+        let product = self
+            .products
+            .iter()
+            .find(|product| product.onchain_id == order.onchain_id)
+            .cloned()
+            .ok_or_else(|| {
+                ApiError::bad_request(format!("Product {} not found", order.onchain_id))
+            })?;
+
+        if order.order_type != "LIMIT" {
+            return Err(ApiError::bad_request(format!(
+                "Unsupported order type {}",
+                order.order_type
+            )));
+        }
+        if order.side > 1 {
+            return Err(ApiError::bad_request("side must be 0 or 1"));
+        }
+
+        let price_raw = parse_order_decimal(&order.price)
+            .ok_or_else(|| ApiError::bad_request("price is not a valid decimal"))?;
+        let quantity_raw = parse_order_decimal(&order.quantity)
+            .ok_or_else(|| ApiError::bad_request("quantity is not a valid decimal"))?;
+
+        if price_raw == 0 || price_raw % product.tick_size_raw() != 0 {
+            return Err(ApiError::bad_request(format!(
+                "price must be a positive multiple of tickSize {}",
+                product.tick_size
+            )));
+        }
+        if quantity_raw % product.lot_size_raw() != 0 {
+            return Err(ApiError::bad_request(format!(
+                "quantity must be a multiple of lotSize {}",
+                product.lot_size
+            )));
+        }
+        if quantity_raw < product.min_quantity_raw() {
+            return Err(ApiError::bad_request(format!(
+                "quantity is below minQuantity {}",
+                product.min_quantity
+            )));
+        }
+
+        Ok(ParsedOrder {
+            price_raw,
+            quantity_raw,
+            product,
+        })
+    }
+
+    fn verify<T: SolStruct>(
+        &self,
+        message: &T,
+        sender: Address,
+        signature: &str,
+    ) -> Result<(), ApiError> {
+        let invalid = || ApiError::bad_request("Invalid signature");
+
+        let signature: Signature = signature.parse().map_err(|_| invalid())?;
+        let hash = message.eip712_signing_hash(&self.domain);
+        let signer = signature
+            .recover_address_from_prehash(&hash)
+            .map_err(|_| invalid())?;
+
+        if signer == sender {
+            Ok(())
+        } else {
+            Err(invalid())
+        }
+    }
+}
+
+fn parse_nonce(nonce: &str) -> Result<u64, ApiError> {
+    nonce
+        .parse()
+        .map_err(|_| ApiError::bad_request("nonce is not a valid integer"))
+}
+
+fn is_working(status: OrderStatus) -> bool {
+    matches!(
+        status,
+        OrderStatus::New | OrderStatus::Pending | OrderStatus::FilledPartial
+    )
+}
+
+/// Orders fill all at once, so `filled` is either nothing or everything.
+fn order_json(order: &MockOrder) -> serde_json::Value {
+    let (available, filled) = match order.status {
+        OrderStatus::Filled => ("0", order.quantity.as_str()),
+        _ => (order.quantity.as_str(), "0"),
+    };
+    serde_json::json!({
+        "id": order.id,
+        "clientOrderId": order.client_order_id,
+        "type": "LIMIT",
+        "productId": order.product_id,
+        "side": order.side,
+        "price": order.price,
+        "quantity": order.quantity,
+        "availableQuantity": available,
+        "filled": filled,
+        "status": order.status,
+        "timeInForce": order.time_in_force,
+        "postOnly": order.post_only,
+        "createdAt": order.created_at,
+        "updatedAt": order.created_at,
+    })
+}
+
+fn position_json(position: &MockPosition) -> serde_json::Value {
+    serde_json::json!({
+        "id": Uuid::new_v4(),
+        "productId": position.product_id,
+        "side": position.side,
+        "size": position.size,
+        "cost": position.cost,
+        "realizedPnl": "0",
+        "createdAt": 0,
+        "updatedAt": 0,
+    })
+}
+
+fn product_json(product: &MockProduct) -> serde_json::Value {
+    serde_json::json!({
+        "id": product.id,
+        "ticker": product.ticker,
+        "displayTicker": product.ticker,
+        "engineType": 0,
+        "onchainId": product.onchain_id,
+        "tickSize": product.tick_size,
+        "lotSize": product.lot_size,
+        "minQuantity": product.min_quantity,
+        "maxQuantity": product.max_quantity,
+        "maxLeverage": product.max_leverage,
+        "makerFee": product.maker_fee,
+        "takerFee": product.taker_fee,
+    })
+}
@@ -1,3 +1,5 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
 use bigdecimal::BigDecimal;
 use serde::Serialize;
 use uuid::Uuid;
@@ -5,6 +7,7 @@ use uuid::Uuid;
 use super::common::{OrderStatus, TimeInForce};
 use super::contracts::{CancelOrder, TradeOrder};
 use super::util::format_order_decimal;
+use crate::runtime::protocol::{EnginePacket, STREAM_NAMESPACE, SocketPacket, decode_frame};
 
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -51,18 +54,29 @@ impl TradeOrderData {
     }
 }
 
+/// Last nonce handed out by [`Timestamp::now`].
+static LAST_NONCE: AtomicU64 = AtomicU64::new(0);
+
 pub struct Timestamp {
     pub nonce: u64,
     pub signed_at: u64,
 }
 
 impl Timestamp {
+    /// The nonce is the time in nanoseconds, bumped past the previous one
+    /// when two requests are signed within the same nanosecond.
     pub fn now() -> Self {
         let now = std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
             .unwrap();
+        let now_nanos = now.as_nanos() as u64;
+        let previous = LAST_NONCE
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |last| {
+                Some(now_nanos.max(last + 1))
+            })
+            .expect("nonce update never declines");
         Self {
-            nonce: now.as_nanos() as u64,
+            nonce: now_nanos.max(previous + 1),
             signed_at: now.as_secs(),
         }
     }
@@ -114,6 +128,142 @@ pub struct OrderUpdateData {
     pub created_at: u64,
     pub updated_at: u64,
     pub client_order_id: Uuid,
+    #[serde(default)]
+    pub product_id: Option<Uuid>,
+    #[serde(default)]
+    pub subaccount_id: Option<Uuid>,
+    #[serde(default)]
+    pub side: Option<u8>,
+    /// Limit price of the order.
+    #[serde(default)]
+    pub price: Option<BigDecimal>,
+    /// Cumulative filled quantity.
+    #[serde(default)]
+    pub filled: Option<BigDecimal>,
+}
+
+/// One page of a cursor-paginated REST listing.
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PageData<T> {
+    pub data: Vec<T>,
+    #[serde(default)]
+    pub has_next: bool,
+    #[serde(default)]
+    pub next_cursor: Option<String>,
+}
+
+/// An order as listed by `GET /v1/order`.
+#[derive(Debug, Clone, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenOrderData {
+    pub id: Uuid,
+    #[serde(default)]
+    pub client_order_id: Option<Uuid>,
+    pub product_id: Uuid,
+    pub side: u8,
+    pub price: BigDecimal,
+    pub quantity: BigDecimal,
+    /// Quantity already filled.
+    #[serde(default)]
+    pub filled: BigDecimal,
+    pub status: OrderStatus,
+    pub created_at: u64,
+}
+
+impl OpenOrderData {
+    /// The order update this listing amounts to, for an order whose update
+    /// never arrived over the websocket.
+    pub fn to_update(&self, subaccount_id: Uuid) -> OrderUpdateData {
+        OrderUpdateData {
+            id: self.id,
+            status: self.status,
+            created_at: self.created_at,
+            updated_at: self.created_at,
+            client_order_id: self.client_order_id.unwrap_or_default(),
+            product_id: Some(self.product_id),
+            subaccount_id: Some(subaccount_id),
+            side: Some(self.side),
+            price: Some(self.price.clone()),
+            filled: Some(self.filled.clone()),
+        }
+    }
+}
+
+/// An open position as listed by `GET /v1/position`. `size` is unsigned and
+/// `side` is 0 for long, 1 for short; `cost` is the entry notional of `size`.
+#[derive(Debug, Clone, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PositionData {
+    pub product_id: Uuid,
+    pub side: u8,
+    pub size: BigDecimal,
+    #[serde(default)]
+    pub cost: Option<BigDecimal>,
+}
+
+/// Response to `POST /v1/order`; `result` is `Ok` for an accepted order. A
+/// refused order gets its error code there and none of the other fields.
+#[derive(Debug, Clone, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubmitOrderResponse {
+    pub id: Uuid,
+    #[serde(default)]
+    pub client_order_id: Option<Uuid>,
+    pub filled: BigDecimal,
+    pub result: String,
+}
+
+/// Response to `POST /v1/order/dry-run`: the margin the order would use.
+/// `code` is `Ok` when the order would be accepted; otherwise it is the only
+/// field.
+#[derive(Debug, Clone, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DryRunResponse {
+    pub margin_required: BigDecimal,
+    pub margin_available: BigDecimal,
+    pub total_used_margin: BigDecimal,
+    pub risk_used: BigDecimal,
+    pub risk_available: BigDecimal,
+    pub code: String,
+}
+
+/// Response to `POST /v1/order/cancel`, one result per order targeted.
+#[derive(Debug, Clone, PartialEq, serde::Deserialize)]
+pub struct CancelOrderResponse {
+    pub data: Vec<CancelResultData>,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Deserialize)]
+pub struct CancelResultData {
+    pub id: Uuid,
+    pub result: String,
+}
+
+impl CancelResultData {
+    /// Whether the order is no longer working: cancelled now, or already
+    /// closed or unknown to the exchange.
+    pub fn is_accepted(&self) -> bool {
+        matches!(
+            self.result.as_str(),
+            "Ok" | "AlreadyCanceled" | "AlreadyExpired" | "AlreadyFilled" | "NotFound"
+        )
+    }
+}
+
+/// A product as listed by `GET /v1/product`.
+#[derive(Debug, Clone, PartialEq, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProductData {
+    pub id: Uuid,
+    pub ticker: String,
+    pub onchain_id: u32,
+    pub tick_size: BigDecimal,
+    pub lot_size: BigDecimal,
+    pub min_quantity: BigDecimal,
+    pub max_leverage: u32,
+    pub maker_fee: BigDecimal,
+    pub taker_fee: BigDecimal,
 }
 
 #[derive(Debug, Clone, PartialEq, serde::Deserialize)]
@@ -138,9 +288,17 @@ pub enum WsEvent {
 }
 
 pub fn parse_ws_event(msg: &str) -> Option<WsEvent> {
-    let payload = msg.strip_prefix("42/v1/stream,")?;
-    let (event, payload): (String, serde_json::Value) = serde_json::from_str(payload).ok()?;
+    match decode_frame(msg).ok()? {
+        EnginePacket::Message(SocketPacket::Event {
+            namespace,
+            event,
+            payload,
+        }) if namespace == STREAM_NAMESPACE => parse_stream_event(event, payload),
+        _ => None,
+    }
+}
 
+fn parse_stream_event(event: String, payload: serde_json::Value) -> Option<WsEvent> {
     match event.as_str() {
         "OrderUpdate" => parse_event_items(payload).map(WsEvent::OrderUpdate),
         "MarketPrice" => parse_event_items(payload).map(WsEvent::MarketPrice),
@@ -176,9 +334,35 @@ mod tests {
     use bigdecimal::BigDecimal;
     use uuid::Uuid;
 
-    use super::{WsEvent, parse_ws_event};
+    use super::{Timestamp, WsEvent, parse_ws_event};
     use crate::models::common::OrderStatus;
 
+    #[test]
+    fn nonces_strictly_increase_across_threads() {
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                std::thread::spawn(|| {
+                    (0..1_000)
+                        .map(|_| Timestamp::now().nonce)
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        let per_thread: Vec<Vec<u64>> = threads
+            .into_iter()
+            .map(|thread| thread.join().unwrap())
+            .collect();
+
+        for nonces in &per_thread {
+            assert!(nonces.windows(2).all(|pair| pair[0] < pair[1]));
+        }
+        let mut all: Vec<u64> = per_thread.into_iter().flatten().collect();
+        let count = all.len();
+        all.sort_unstable();
+        all.dedup();
+        assert_eq!(all.len(), count);
+    }
+
     #[test]
     fn parse_order_update_object_payload() {
         let msg = concat!(
@@ -201,6 +385,24 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parse_order_update_fill_fields() {
+        let msg = concat!(
+            "42/v1/stream,",
+            r#"["OrderUpdate",{"data":[{"id":"11111111-1111-1111-1111-111111111111","status":"FILLED_PARTIAL","createdAt":1712019600000,"updatedAt":1712019600100,"clientOrderId":"22222222-2222-2222-2222-222222222222","productId":"bc7d5575-3711-4532-a000-312bfacfb767","side":1,"price":"65107","quantity":"0.5","filled":"0.2"}]}]"#
+        );
+
+        let Some(WsEvent::OrderUpdate(updates)) = parse_ws_event(msg) else {
+            panic!("expected order update");
+        };
+        let update = &updates[0];
+        assert_eq!(update.status, OrderStatus::FilledPartial);
+        assert_eq!(update.side, Some(1));
+        assert_eq!(update.price, Some(BigDecimal::from_str("65107").unwrap()));
+        assert_eq!(update.filled, Some(BigDecimal::from_str("0.2").unwrap()));
+        assert!(update.subaccount_id.is_none());
+    }
+
     #[test]
     fn parse_market_price_array_payload() {
         let msg = concat!(
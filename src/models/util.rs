@@ -1,3 +1,5 @@
+use num_traits::ToPrimitive;
+
 pub const ORDER_DECIMALS: u128 = 1_000_000_000;
 pub const ORDER_DECIMAL_PLACES: usize = ORDER_DECIMALS.ilog10() as usize;
 
@@ -10,9 +12,50 @@ pub fn format_order_decimal(raw: u128) -> String {
     )
 }
 
+pub fn format_signed_order_decimal(raw: i128) -> String {
+    let magnitude = format_order_decimal(raw.unsigned_abs());
+    if raw < 0 {
+        format!("-{magnitude}")
+    } else {
+        magnitude
+    }
+}
+
+/// Inverse of [`format_order_decimal`]; rejects negatives and excess precision.
+pub fn parse_order_decimal(value: &str) -> Option<u128> {
+    let (integer, fractional) = value.split_once('.').unwrap_or((value, ""));
+    if (integer.is_empty() && fractional.is_empty())
+        || fractional.len() > ORDER_DECIMAL_PLACES
+        || !integer.bytes().all(|b| b.is_ascii_digit())
+        || !fractional.bytes().all(|b| b.is_ascii_digit())
+    {
+        return None;
+    }
+
+    let integer: u128 = if integer.is_empty() {
+        0
+    } else {
+        integer.parse().ok()?
+    };
+    let fractional: u128 = format!("{fractional:0<width$}", width = ORDER_DECIMAL_PLACES)
+        .parse()
+        .ok()?;
+
+    integer.checked_mul(ORDER_DECIMALS)?.checked_add(fractional)
+}
+
+pub fn decimal_to_raw(value: &bigdecimal::BigDecimal) -> Option<u128> {
+    let scaled = value.with_scale(ORDER_DECIMAL_PLACES as i64);
+    let (digits, _scale) = scaled.into_bigint_and_scale();
+    digits.to_biguint()?.to_u128()
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{ORDER_DECIMAL_PLACES, ORDER_DECIMALS, format_order_decimal};
+    use super::{
+        ORDER_DECIMAL_PLACES, ORDER_DECIMALS, format_order_decimal, format_signed_order_decimal,
+        parse_order_decimal,
+    };
 
     #[test]
     fn decimal_places_are_derived_from_scale() {
@@ -29,4 +72,27 @@ mod tests {
     fn formats_subunit_values() {
         assert_eq!(format_order_decimal(1), "0.000000001");
     }
+
+    #[test]
+    fn formats_signed_values() {
+        assert_eq!(format_signed_order_decimal(-1_500_000_000), "-1.500000000");
+        assert_eq!(format_signed_order_decimal(0), "0.000000000");
+    }
+
+    #[test]
+    fn parses_formatted_values_back_to_raw() {
+        for raw in [0, 1, 1_000_000_000, 1_000_000_001, 65_102_500_000_000] {
+            assert_eq!(parse_order_decimal(&format_order_decimal(raw)), Some(raw));
+        }
+        assert_eq!(parse_order_decimal("100"), Some(100_000_000_000));
+        assert_eq!(parse_order_decimal("0.5"), Some(500_000_000));
+    }
+
+    #[test]
+    fn rejects_invalid_decimals() {
+        assert_eq!(parse_order_decimal(""), None);
+        assert_eq!(parse_order_decimal("-1"), None);
+        assert_eq!(parse_order_decimal("1.0000000001"), None);
+        assert_eq!(parse_order_decimal("1e3"), None);
+    }
 }
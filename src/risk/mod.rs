@@ -0,0 +1,501 @@
+//! Pre-trade checks that every order passes between the runtime and the
+//! order executor, so a bad quote or a fat-fingered setting is refused before
+//! it is signed.
+
+mod settings;
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+pub use settings::RiskConfig;
+use uuid::Uuid;
+
+use crate::models::dto::{OpenOrderData, PositionData};
+use crate::models::util::{ORDER_DECIMALS, decimal_to_raw};
+use crate::runtime::RuntimeEvent;
+use crate::trading::is_terminal_order_status;
+use crate::trading::position::{Position, PositionKey, PositionLedger};
+use crate::trading::state::{Side, TrackedOrder};
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum RiskRejection {
+    #[error("side {0} is neither buy nor sell")]
+    InvalidSide(u8),
+    #[error("quantity {qty_raw} exceeds the per-order maximum {max_raw}")]
+    OrderQuantity { qty_raw: u128, max_raw: u128 },
+    #[error("notional {notional_raw} exceeds the per-order maximum {max_raw}")]
+    Notional { notional_raw: u128, max_raw: u128 },
+    #[error("onchain product {0} is not known to the risk check")]
+    UnknownProduct(u32),
+    #[error("product {product_id} already has {open} working order(s), the maximum is {max}")]
+    OpenOrders {
+        product_id: Uuid,
+        open: usize,
+        max: usize,
+    },
+    #[error("order could take the net position to {projected_raw}, beyond the maximum {max_raw}")]
+    Position { projected_raw: i128, max_raw: u128 },
+    #[error("no oracle price seen yet for product {0}")]
+    NoReferencePrice(Uuid),
+    #[error("price {price_raw} is more than {band_bps} bps from the oracle price {oracle_raw}")]
+    PriceBand {
+        price_raw: u128,
+        oracle_raw: u128,
+        band_bps: u32,
+    },
+}
+
+/// An order about to be submitted, in raw units.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct OrderCheck {
+    pub client_order_id: Uuid,
+    pub onchain_product_id: u32,
+    pub side: u8,
+    pub price_raw: u128,
+    pub qty_raw: u128,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct WorkingOrder {
+    product_id: Uuid,
+    side: Side,
+    qty_raw: u128,
+    /// A cancel is outstanding; the order may still fill but no longer
+    /// takes up an open order slot.
+    cancelling: bool,
+}
+
+#[derive(Debug, Default)]
+struct RiskState {
+    /// Product uuid per onchain id, for the products this runtime may trade.
+    products: HashMap<u32, Uuid>,
+    oracle_prices: HashMap<Uuid, u128>,
+    /// Orders reserved by a submit or adopted at startup and not yet reported
+    /// terminal, by client order id.
+    working: HashMap<Uuid, WorkingOrder>,
+    positions: PositionLedger,
+}
+
+/// Shared by the runtime handle, which checks and reserves each submit, and
+/// the event pipeline, which feeds it oracle prices, fills and terminal updates.
+#[derive(Debug, Clone)]
+pub(crate) struct RiskGuard {
+    config: Arc<RiskConfig>,
+    subaccount_id: Uuid,
+    state: Arc<Mutex<RiskState>>,
+}
+
+impl RiskGuard {
+    pub fn new(config: RiskConfig, subaccount_id: Uuid) -> Self {
+        Self {
+            config: Arc::new(config),
+            subaccount_id,
+            state: Arc::default(),
+        }
+    }
+
+    pub fn register_product(&self, product_id: Uuid, onchain_product_id: u32) {
+        self.lock().products.insert(onchain_product_id, product_id);
+    }
+
+    /// Starts the position check from what the exchange holds rather than
+    /// flat, so limits carry across restarts. Positions of other subaccounts
+    /// are ignored.
+    pub fn seed_positions(&self, subaccount_id: Uuid, positions: &[PositionData]) {
+        if subaccount_id != self.subaccount_id {
+            return;
+        }
+        let mut state = self.lock();
+        for data in positions {
+            if let Some(position) = Position::from_exchange(data) {
+                let key = PositionKey {
+                    subaccount_id: self.subaccount_id,
+                    product_id: data.product_id,
+                };
+                state.positions.seed(key, position);
+            }
+        }
+    }
+
+    /// Counts what `orders` have filled so far as booked, since seeded
+    /// positions already include it.
+    pub fn mark_booked(&self, orders: &[OpenOrderData]) {
+        let mut state = self.lock();
+        for order in orders {
+            if let Some(filled_raw) = decimal_to_raw(&order.filled) {
+                state.positions.mark_booked(order.id, filled_raw);
+            }
+        }
+    }
+
+    /// Counts an order adopted at startup as working, as if reserved, so it
+    /// takes an open order slot and counts toward the position check. Orders
+    /// of other subaccounts are ignored.
+    pub fn adopt(&self, subaccount_id: Uuid, product_id: Uuid, order: &TrackedOrder) {
+        if subaccount_id != self.subaccount_id {
+            return;
+        }
+        self.lock()
+            .working
+            .insert(order.client_order_id, WorkingOrder {
+                product_id,
+                side: order.side,
+                qty_raw: order.qty_raw,
+                cancelling: false,
+            });
+    }
+
+    /// Runs every check against `order` and, when it passes, counts it as
+    /// working until [`Self::release`] or a terminal order update.
+    ///
+    /// Working orders count at their full quantity until they are terminal,
+    /// so partial fills make the position check stricter, never looser.
+    pub fn reserve(&self, order: &OrderCheck) -> Result<(), RiskRejection> {
+        let side = Side::from_raw(order.side).ok_or(RiskRejection::InvalidSide(order.side))?;
+        let config = &self.config;
+
+        if order.qty_raw > config.max_order_qty_raw {
+            return Err(RiskRejection::OrderQuantity {
+                qty_raw: order.qty_raw,
+                max_raw: config.max_order_qty_raw,
+            });
+        }
+
+        let notional_raw = order
+            .price_raw
+            .checked_mul(order.qty_raw)
+            .map_or(u128::MAX, |value| value / ORDER_DECIMALS);
+        if notional_raw > config.max_notional_raw {
+            return Err(RiskRejection::Notional {
+                notional_raw,
+                max_raw: config.max_notional_raw,
+            });
+        }
+
+        let mut state = self.lock();
+        let product_id = *state
+            .products
+            .get(&order.onchain_product_id)
+            .ok_or(RiskRejection::UnknownProduct(order.onchain_product_id))?;
+
+        let open = state
+            .working
+            .values()
+            .filter(|working| working.product_id == product_id && !working.cancelling)
+            .count();
+        if open >= config.max_open_orders {
+            return Err(RiskRejection::OpenOrders {
+                product_id,
+                open,
+                max: config.max_open_orders,
+            });
+        }
+
+        let signed = |qty_raw: u128| match side {
+            Side::Buy => qty_raw as i128,
+            Side::Sell => -(qty_raw as i128),
+        };
+        let pending_raw: u128 = state
+            .working
+            .values()
+            .filter(|working| working.product_id == product_id && working.side == side)
+            .map(|working| working.qty_raw)
+            .sum();
+        let net_qty_raw = state
+            .positions
+            .position(PositionKey {
+                subaccount_id: self.subaccount_id,
+                product_id,
+            })
+            .net_qty_raw;
+        let before_raw = net_qty_raw + signed(pending_raw);
+        let projected_raw = before_raw + signed(order.qty_raw);
+        if projected_raw.unsigned_abs() > config.max_position_raw
+            && projected_raw.unsigned_abs() > before_raw.unsigned_abs()
+        {
+            return Err(RiskRejection::Position {
+                projected_raw,
+                max_raw: config.max_position_raw,
+            });
+        }
+
+        if config.price_band_bps > 0 {
+            let oracle_raw = *state
+                .oracle_prices
+                .get(&product_id)
+                .ok_or(RiskRejection::NoReferencePrice(product_id))?;
+            let deviation_raw = order.price_raw.abs_diff(oracle_raw);
+            if deviation_raw.saturating_mul(10_000)
+                > oracle_raw.saturating_mul(config.price_band_bps.into())
+            {
+                return Err(RiskRejection::PriceBand {
+                    price_raw: order.price_raw,
+                    oracle_raw,
+                    band_bps: config.price_band_bps,
+                });
+            }
+        }
+
+        state.working.insert(order.client_order_id, WorkingOrder {
+            product_id,
+            side,
+            qty_raw: order.qty_raw,
+            cancelling: false,
+        });
+        Ok(())
+    }
+
+    /// Marks whether a cancel of the order is outstanding, so the order that
+    /// replaces it can take its open order slot right away.
+    pub fn set_cancelling(&self, client_order_id: Uuid, cancelling: bool) {
+        if let Some(working) = self.lock().working.get_mut(&client_order_id) {
+            working.cancelling = cancelling;
+        }
+    }
+
+    /// Forgets a reserved order whose submit did not go through.
+    pub fn release(&self, client_order_id: Uuid) {
+        self.lock().working.remove(&client_order_id);
+    }
+
+    /// Keeps oracle prices, positions and working orders current.
+    pub fn observe(&self, event: &RuntimeEvent) {
+        let mut state = self.lock();
+        match event {
+            RuntimeEvent::MarketPrice(tick) => {
+                if let Some(oracle_raw) = decimal_to_raw(&tick.oracle_price) {
+                    state.oracle_prices.insert(tick.product_id, oracle_raw);
+                }
+            }
+            RuntimeEvent::OrderUpdate(update) => {
+                let product_id = state
+                    .working
+                    .get(&update.client_order_id)
+                    .map_or(Uuid::nil(), |working| working.product_id);
+                let fallback = PositionKey {
+                    subaccount_id: self.subaccount_id,
+                    product_id,
+                };
+                state.positions.apply_order_update(update, fallback, 0);
+
+                if is_terminal_order_status(update.status) {
+                    state.working.remove(&update.client_order_id);
+                }
+            }
+            RuntimeEvent::Disconnected | RuntimeEvent::Reconnected => {}
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, RiskState> {
+        self.state.lock().expect("risk state lock poisoned")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use bigdecimal::BigDecimal;
+    use uuid::Uuid;
+
+    use super::{OrderCheck, RiskConfig, RiskGuard, RiskRejection};
+    use crate::models::common::OrderStatus;
+    use crate::models::dto::{MarketPriceData, OrderUpdateData, PositionData};
+    use crate::runtime::RuntimeEvent;
+    use crate::trading::state::{OrderState, Side, TrackedOrder};
+
+    const ONE: u128 = 1_000_000_000;
+    const ONCHAIN_ID: u32 = 7;
+
+    fn guard() -> (RiskGuard, Uuid) {
+        let product_id = Uuid::new_v4();
+        let guard = RiskGuard::new(
+            RiskConfig {
+                max_order_qty_raw: ONE,
+                max_notional_raw: 150 * ONE,
+                max_open_orders: 2,
+                max_position_raw: 2 * ONE,
+                price_band_bps: 100,
+            },
+            Uuid::nil(),
+        );
+        guard.register_product(product_id, ONCHAIN_ID);
+        guard.observe(&RuntimeEvent::MarketPrice(MarketPriceData {
+            product_id,
+            best_bid_price: BigDecimal::from(99),
+            best_ask_price: BigDecimal::from(101),
+            oracle_price: BigDecimal::from(100),
+            price24h_ago: BigDecimal::from(100),
+        }));
+        (guard, product_id)
+    }
+
+    fn order(side: u8, price_raw: u128, qty_raw: u128) -> OrderCheck {
+        OrderCheck {
+            client_order_id: Uuid::new_v4(),
+            onchain_product_id: ONCHAIN_ID,
+            side,
+            price_raw,
+            qty_raw,
+        }
+    }
+
+    fn filled(order: &OrderCheck, product_id: Uuid) -> RuntimeEvent {
+        RuntimeEvent::OrderUpdate(OrderUpdateData {
+            id: Uuid::new_v4(),
+            status: OrderStatus::Filled,
+            created_at: 0,
+            updated_at: 0,
+            client_order_id: order.client_order_id,
+            product_id: Some(product_id),
+            subaccount_id: None,
+            side: Some(order.side),
+            price: Some(BigDecimal::from((order.price_raw / ONE) as u64)),
+            filled: Some(BigDecimal::from_str(&format!("{}e-9", order.qty_raw)).unwrap()),
+        })
+    }
+
+    #[test]
+    fn size_and_notional_limits_refuse_fat_fingers() {
+        let (guard, _) = guard();
+
+        assert_eq!(
+            guard.reserve(&order(0, 100 * ONE, 10 * ONE)),
+            Err(RiskRejection::OrderQuantity {
+                qty_raw: 10 * ONE,
+                max_raw: ONE,
+            })
+        );
+        assert!(matches!(
+            guard.reserve(&order(0, 200 * ONE, ONE)),
+            Err(RiskRejection::Notional { .. })
+        ));
+        assert!(guard.reserve(&order(0, 100 * ONE, ONE)).is_ok());
+    }
+
+    #[test]
+    fn price_band_needs_an_oracle_price() {
+        let (guard, _) = guard();
+
+        assert!(guard.reserve(&order(1, 101 * ONE, ONE / 10)).is_ok());
+        assert!(matches!(
+            guard.reserve(&order(1, 102 * ONE, ONE / 10)),
+            Err(RiskRejection::PriceBand { .. })
+        ));
+
+        let unpriced = Uuid::new_v4();
+        guard.register_product(unpriced, ONCHAIN_ID + 1);
+        let mut unpriced_order = order(0, 100 * ONE, ONE / 10);
+        unpriced_order.onchain_product_id = ONCHAIN_ID + 1;
+        assert_eq!(
+            guard.reserve(&unpriced_order),
+            Err(RiskRejection::NoReferencePrice(unpriced))
+        );
+
+        unpriced_order.onchain_product_id = ONCHAIN_ID + 2;
+        assert_eq!(
+            guard.reserve(&unpriced_order),
+            Err(RiskRejection::UnknownProduct(ONCHAIN_ID + 2))
+        );
+    }
+
+    #[test]
+    fn open_orders_free_up_on_release_and_terminal_updates() {
+        let (guard, product_id) = guard();
+        let first = order(0, 100 * ONE, ONE / 10);
+        let second = order(1, 100 * ONE, ONE / 10);
+
+        guard.reserve(&first).unwrap();
+        guard.reserve(&second).unwrap();
+        assert!(matches!(
+            guard.reserve(&order(0, 100 * ONE, ONE / 10)),
+            Err(RiskRejection::OpenOrders { open: 2, .. })
+        ));
+
+        guard.release(second.client_order_id);
+        guard.observe(&filled(&first, product_id));
+        guard.reserve(&order(0, 100 * ONE, ONE / 10)).unwrap();
+    }
+
+    #[test]
+    fn position_limit_counts_working_orders_and_allows_reducing() {
+        let (guard, product_id) = guard();
+
+        for _ in 0..2 {
+            let buy = order(0, 100 * ONE, ONE);
+            guard.reserve(&buy).unwrap();
+            guard.observe(&filled(&buy, product_id));
+        }
+
+        assert!(matches!(
+            guard.reserve(&order(0, 100 * ONE, ONE / 10)),
+            Err(RiskRejection::Position { .. })
+        ));
+        guard.reserve(&order(1, 100 * ONE, ONE)).unwrap();
+    }
+
+    #[test]
+    fn seeded_positions_count_toward_the_limit() {
+        let (guard, product_id) = guard();
+        let held = PositionData {
+            product_id,
+            side: 0,
+            size: BigDecimal::from(2),
+            cost: Some(BigDecimal::from(200)),
+        };
+
+        guard.seed_positions(Uuid::new_v4(), std::slice::from_ref(&held));
+        guard.reserve(&order(0, 100 * ONE, ONE / 10)).unwrap();
+
+        guard.seed_positions(Uuid::nil(), &[held]);
+        assert!(matches!(
+            guard.reserve(&order(0, 100 * ONE, ONE / 10)),
+            Err(RiskRejection::Position { .. })
+        ));
+    }
+
+    #[test]
+    fn orders_being_cancelled_free_their_open_order_slot() {
+        let (guard, _) = guard();
+        let first = order(0, 100 * ONE, ONE / 10);
+        guard.reserve(&first).unwrap();
+        guard.reserve(&order(1, 100 * ONE, ONE / 10)).unwrap();
+
+        guard.set_cancelling(first.client_order_id, true);
+        let replacement = order(0, 100 * ONE, ONE / 10);
+        guard.reserve(&replacement).unwrap();
+
+        guard.release(replacement.client_order_id);
+        guard.set_cancelling(first.client_order_id, false);
+        assert!(matches!(
+            guard.reserve(&order(0, 100 * ONE, ONE / 10)),
+            Err(RiskRejection::OpenOrders { open: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn adopted_orders_count_toward_the_limits() {
+        let (guard, product_id) = guard();
+        let adopted = |side, qty_raw| TrackedOrder {
+            client_order_id: Uuid::new_v4(),
+            side,
+            level: 0,
+            price_raw: 100 * ONE,
+            qty_raw,
+            state: OrderState::Open,
+            created_at_ms: 0,
+        };
+        guard.adopt(Uuid::new_v4(), product_id, &adopted(Side::Sell, ONE));
+        guard.adopt(Uuid::nil(), product_id, &adopted(Side::Buy, 2 * ONE));
+
+        assert!(matches!(
+            guard.reserve(&order(0, 100 * ONE, ONE / 10)),
+            Err(RiskRejection::Position { .. })
+        ));
+        guard.adopt(Uuid::nil(), product_id, &adopted(Side::Sell, ONE));
+        assert!(matches!(
+            guard.reserve(&order(1, 100 * ONE, ONE / 10)),
+            Err(RiskRejection::OpenOrders { open: 2, .. })
+        ));
+    }
+}
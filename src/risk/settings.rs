@@ -0,0 +1,60 @@
+use crate::trading::settings::deserialize_u128_config;
+
+/// Hard limits every order must pass before it is signed and submitted.
+/// Quantity, notional and position limits left out of the settings are
+/// unlimited; the open order limit defaults to 4 and the price band to 200 bps.
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct RiskConfig {
+    /// Largest quantity a single order may carry.
+    #[serde(
+        default = "unlimited_raw",
+        deserialize_with = "deserialize_u128_config"
+    )]
+    pub max_order_qty_raw: u128,
+    /// Largest `price * quantity` a single order may carry, in raw quote units.
+    #[serde(
+        default = "unlimited_raw",
+        deserialize_with = "deserialize_u128_config"
+    )]
+    pub max_notional_raw: u128,
+    /// Working orders allowed per product, counting submits still in flight
+    /// but not orders with a cancel outstanding. Must cover every ladder
+    /// level on both sides.
+    #[serde(default = "default_max_open_orders")]
+    pub max_open_orders: usize,
+    /// Largest absolute net position the order could leave, assuming every
+    /// working order on the same side fills.
+    #[serde(
+        default = "unlimited_raw",
+        deserialize_with = "deserialize_u128_config"
+    )]
+    pub max_position_raw: u128,
+    /// How far from the last `oracle_price` an order may be priced, in basis
+    /// points; 0 disables the check.
+    #[serde(default = "default_price_band_bps")]
+    pub price_band_bps: u32,
+}
+
+impl Default for RiskConfig {
+    fn default() -> Self {
+        Self {
+            max_order_qty_raw: unlimited_raw(),
+            max_notional_raw: unlimited_raw(),
+            max_open_orders: default_max_open_orders(),
+            max_position_raw: unlimited_raw(),
+            price_band_bps: default_price_band_bps(),
+        }
+    }
+}
+
+fn unlimited_raw() -> u128 {
+    u128::MAX
+}
+
+fn default_max_open_orders() -> usize {
+    4
+}
+
+fn default_price_band_bps() -> u32 {
+    200
+}
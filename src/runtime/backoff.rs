@@ -0,0 +1,106 @@
+use std::time::Duration;
+
+use super::settings::ReconnectConfig;
+
+/// Exponential backoff with equal jitter: each delay is drawn from
+/// `[ceiling / 2, ceiling]`, where the ceiling doubles per attempt up to the cap.
+#[derive(Debug, Clone)]
+pub(crate) struct Backoff {
+    initial_ms: u64,
+    max_ms: u64,
+    max_attempts: Option<u32>,
+    attempt: u32,
+}
+
+impl Backoff {
+    pub fn new(config: &ReconnectConfig) -> Self {
+        Self {
+            initial_ms: config.initial_backoff_ms.max(1),
+            max_ms: config.max_backoff_ms.max(config.initial_backoff_ms.max(1)),
+            max_attempts: config.max_attempts,
+            attempt: 0,
+        }
+    }
+
+    pub fn attempt(&self) -> u32 {
+        self.attempt
+    }
+
+    /// Returns the delay before the next attempt, or `None` once the attempt budget is spent.
+    pub fn next_delay(&mut self) -> Option<Duration> {
+        if self
+            .max_attempts
+            .is_some_and(|max_attempts| self.attempt >= max_attempts)
+        {
+            return None;
+        }
+
+        let ceiling_ms = self.ceiling_ms();
+        self.attempt = self.attempt.saturating_add(1);
+
+        let floor_ms = ceiling_ms / 2;
+        Some(Duration::from_millis(rand::random_range(
+            floor_ms..=ceiling_ms,
+        )))
+    }
+
+    pub fn reset(&mut self) {
+        self.attempt = 0;
+    }
+
+    fn ceiling_ms(&self) -> u64 {
+        let factor = 1u64.checked_shl(self.attempt).unwrap_or(u64::MAX);
+        self.initial_ms.saturating_mul(factor).min(self.max_ms)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::Backoff;
+    use crate::runtime::settings::ReconnectConfig;
+
+    fn config(max_attempts: Option<u32>) -> ReconnectConfig {
+        ReconnectConfig {
+            initial_backoff_ms: 100,
+            max_backoff_ms: 1_000,
+            max_attempts,
+        }
+    }
+
+    #[test]
+    fn delays_grow_within_jitter_bounds_and_cap() {
+        let mut backoff = Backoff::new(&config(None));
+        let expected_ceilings = [100, 200, 400, 800, 1_000, 1_000, 1_000];
+
+        for ceiling in expected_ceilings {
+            let delay = backoff.next_delay().unwrap();
+            assert!(delay >= Duration::from_millis(ceiling / 2), "{delay:?}");
+            assert!(delay <= Duration::from_millis(ceiling), "{delay:?}");
+        }
+    }
+
+    #[test]
+    fn stops_after_max_attempts_until_reset() {
+        let mut backoff = Backoff::new(&config(Some(2)));
+
+        assert!(backoff.next_delay().is_some());
+        assert!(backoff.next_delay().is_some());
+        assert!(backoff.next_delay().is_none());
+        assert_eq!(backoff.attempt(), 2);
+
+        backoff.reset();
+        let delay = backoff.next_delay().unwrap();
+        assert!(delay <= Duration::from_millis(100));
+    }
+
+    #[test]
+    fn large_attempt_counts_do_not_overflow() {
+        let mut backoff = Backoff::new(&config(None));
+        for _ in 0..200 {
+            let delay = backoff.next_delay().unwrap();
+            assert!(delay <= Duration::from_millis(1_000));
+        }
+    }
+}
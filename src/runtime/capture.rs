@@ -0,0 +1,402 @@
+//! Raw websocket capture and replay. Recordings are JSON lines of
+//! `{"received_at_ms": .., "frame": ".."}`, one per inbound text frame, so an
+//! incident can be fed back through `parse_ws_event` without a network.
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use tokio::sync::mpsc;
+
+use super::settings::CaptureConfig;
+use super::{EtherealRuntime, RuntimeEvent};
+use crate::logging::targets;
+use crate::models::dto::{WsEvent, parse_ws_event};
+
+const CAPTURE_EXTENSION: &str = "jsonl";
+
+#[derive(Debug, thiserror::Error)]
+pub enum CaptureError {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("{}:{line}: {reason}", file.display())]
+    Malformed {
+        file: PathBuf,
+        line: usize,
+        reason: String,
+    },
+    #[error("no capture files found at {}", .0.display())]
+    Empty(PathBuf),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct CapturedFrame {
+    pub received_at_ms: u64,
+    pub frame: String,
+}
+
+/// Hands frames to a [`FrameRecorder`] on its own writer thread, so file
+/// writes never block the websocket task.
+pub(crate) struct CaptureSink {
+    sender: std::sync::mpsc::Sender<CapturedFrame>,
+}
+
+impl CaptureSink {
+    /// Starts the writer thread, which writes out what is still queued and
+    /// exits once the sink is dropped.
+    pub fn spawn(
+        mut recorder: FrameRecorder,
+    ) -> Result<(Self, std::thread::JoinHandle<()>), CaptureError> {
+        let (sender, receiver) = std::sync::mpsc::channel::<CapturedFrame>();
+        let writer = std::thread::Builder::new()
+            .name("ws-capture".to_string())
+            .spawn(move || {
+                while let Ok(frame) = receiver.recv() {
+                    // Flushed once the queue is drained, so a crash still
+                    // leaves the frames that led up to it.
+                    let written = std::iter::once(frame)
+                        .chain(receiver.try_iter())
+                        .try_for_each(|frame| recorder.write(&frame))
+                        .and_then(|()| recorder.flush());
+                    if let Err(error) = written {
+                        tracing::error!(
+                            target: targets::RUNTIME_WS,
+                            %error,
+                            "websocket capture failed; recording disabled"
+                        );
+                        return;
+                    }
+                }
+            })?;
+        Ok((Self { sender }, writer))
+    }
+
+    /// Queues a frame; returns `false` once the writer has stopped.
+    pub fn record(&self, frame: &str) -> bool {
+        self.sender
+            .send(CapturedFrame {
+                received_at_ms: now_ms(),
+                frame: frame.to_string(),
+            })
+            .is_ok()
+    }
+}
+
+/// Appends inbound frames to `ws-<start>-<seq>.jsonl`, rolling to the next
+/// sequence number once a file exceeds `max_file_bytes`.
+pub(crate) struct FrameRecorder {
+    directory: PathBuf,
+    prefix: String,
+    max_file_bytes: u64,
+    max_files: Option<usize>,
+    sequence: u32,
+    written: u64,
+    writer: BufWriter<File>,
+}
+
+impl FrameRecorder {
+    pub fn open(config: &CaptureConfig) -> Result<Self, CaptureError> {
+        let directory = PathBuf::from(&config.directory);
+        std::fs::create_dir_all(&directory)?;
+
+        let prefix = format!("ws-{:013}", now_ms());
+        let writer = create_segment(&directory, &prefix, 0)?;
+
+        Ok(Self {
+            directory,
+            prefix,
+            max_file_bytes: config.max_file_bytes,
+            max_files: config.max_files,
+            sequence: 0,
+            written: 0,
+            writer,
+        })
+    }
+
+    fn write(&mut self, captured: &CapturedFrame) -> Result<(), CaptureError> {
+        if self.written >= self.max_file_bytes {
+            self.rotate()?;
+        }
+
+        let mut line = serde_json::to_vec(captured).expect("captured frame serializes");
+        line.push(b'\n');
+
+        self.writer.write_all(&line)?;
+        self.written += line.len() as u64;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), CaptureError> {
+        Ok(self.writer.flush()?)
+    }
+
+    fn rotate(&mut self) -> Result<(), CaptureError> {
+        self.writer.flush()?;
+        self.sequence += 1;
+        self.writer = create_segment(&self.directory, &self.prefix, self.sequence)?;
+        self.written = 0;
+
+        if let Some(max_files) = self.max_files {
+            // Only this recording's segments; earlier runs are left alone.
+            let segment_prefix = format!("{}-", self.prefix);
+            let mut files: Vec<PathBuf> = capture_files(&self.directory)?
+                .into_iter()
+                .filter(|file| {
+                    file.file_name()
+                        .is_some_and(|name| name.to_string_lossy().starts_with(&segment_prefix))
+                })
+                .collect();
+            let excess = files.len().saturating_sub(max_files.max(1));
+            for file in files.drain(..excess) {
+                std::fs::remove_file(&file)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn create_segment(
+    directory: &Path,
+    prefix: &str,
+    sequence: u32,
+) -> std::io::Result<BufWriter<File>> {
+    let path = directory.join(format!("{prefix}-{sequence:05}.{CAPTURE_EXTENSION}"));
+    File::create(path).map(BufWriter::new)
+}
+
+/// Capture files under `directory`, oldest first.
+fn capture_files(directory: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(directory)? {
+        let path = entry?.path();
+        if path.is_file() && path.extension().is_some_and(|ext| ext == CAPTURE_EXTENSION) {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReplaySpeed {
+    /// Sleeps for the recorded gap between frames.
+    Original,
+    /// Sleeps for the recorded gap divided by the factor.
+    Accelerated(f64),
+    /// Dispatches frames back to back.
+    Unthrottled,
+}
+
+impl ReplaySpeed {
+    fn delay(self, gap_ms: u64) -> Option<Duration> {
+        let delay = match self {
+            Self::Original => Duration::from_millis(gap_ms),
+            Self::Accelerated(factor) if factor > 0.0 => {
+                Duration::from_secs_f64(gap_ms as f64 / 1_000.0 / factor)
+            }
+            Self::Accelerated(_) | Self::Unthrottled => return None,
+        };
+        (!delay.is_zero()).then_some(delay)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReplaySummary {
+    pub frames: usize,
+    pub events: usize,
+}
+
+/// Feeds a recording (a single file or a capture directory) through
+/// `parse_ws_event` into `event_sender`, the same path live frames take.
+/// Control frames such as pings are skipped. Stops early once the receiver is dropped.
+pub async fn replay_capture(
+    path: &Path,
+    speed: ReplaySpeed,
+    event_sender: &mpsc::UnboundedSender<RuntimeEvent>,
+) -> Result<ReplaySummary, CaptureError> {
+    let files = if path.is_dir() {
+        capture_files(path)?
+    } else {
+        vec![path.to_path_buf()]
+    };
+    if files.is_empty() {
+        return Err(CaptureError::Empty(path.to_path_buf()));
+    }
+
+    let mut summary = ReplaySummary::default();
+    let mut previous_ms = None;
+
+    for file in files {
+        let reader = BufReader::new(File::open(&file)?);
+        for (index, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let captured: CapturedFrame =
+                serde_json::from_str(&line).map_err(|error| CaptureError::Malformed {
+                    file: file.clone(),
+                    line: index + 1,
+                    reason: error.to_string(),
+                })?;
+
+            if let Some(delay) = previous_ms
+                .map(|previous| captured.received_at_ms.saturating_sub(previous))
+                .and_then(|gap_ms| speed.delay(gap_ms))
+            {
+                tokio::time::sleep(delay).await;
+            }
+            previous_ms = Some(captured.received_at_ms);
+            summary.frames += 1;
+
+            let Some(event) = parse_ws_event(&captured.frame) else {
+                continue;
+            };
+            summary.events += match &event {
+                WsEvent::OrderUpdate(updates) => updates.len(),
+                WsEvent::MarketPrice(prices) => prices.len(),
+                WsEvent::Unknown { .. } => 0,
+            };
+            if !EtherealRuntime::dispatch_ws_event(event_sender, event) {
+                tracing::debug!(
+                    target: targets::RUNTIME_WS,
+                    frames = summary.frames,
+                    "replay receiver dropped"
+                );
+                return Ok(summary);
+            }
+        }
+    }
+
+    Ok(summary)
+}
+
+fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+    use std::time::Duration;
+
+    use tokio::sync::mpsc;
+
+    use super::{
+        CaptureSink, CapturedFrame, FrameRecorder, ReplaySpeed, capture_files, replay_capture,
+    };
+    use crate::runtime::RuntimeEvent;
+    use crate::runtime::settings::CaptureConfig;
+
+    const MARKET_PRICE_FRAME: &str = r#"42/v1/stream,["MarketPrice",{"productId":"dce327cc-4fbb-4d5d-9ede-1c1fca7ef4ba","bestBidPrice":"100","bestAskPrice":"101","oraclePrice":"100.5","price24hAgo":"99"}]"#;
+
+    fn scratch_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("ethereal-capture-{}", uuid::Uuid::new_v4()))
+    }
+
+    fn config(directory: &Path, max_file_bytes: u64, max_files: Option<usize>) -> CaptureConfig {
+        CaptureConfig {
+            enabled: true,
+            directory: directory.to_string_lossy().into_owned(),
+            max_file_bytes,
+            max_files,
+        }
+    }
+
+    fn frame(received_at_ms: u64, frame: &str) -> CapturedFrame {
+        CapturedFrame {
+            received_at_ms,
+            frame: frame.to_string(),
+        }
+    }
+
+    #[test]
+    fn rotates_and_prunes_only_its_own_segments() {
+        let directory = scratch_dir();
+        std::fs::create_dir_all(&directory).unwrap();
+        let earlier_run = directory.join("ws-0000000000001-00000.jsonl");
+        let unrelated = directory.join("notes.jsonl");
+        std::fs::write(&earlier_run, "").unwrap();
+        std::fs::write(&unrelated, "").unwrap();
+        let mut recorder = FrameRecorder::open(&config(&directory, 1, Some(2))).unwrap();
+
+        for at in 0..4 {
+            recorder.write(&frame(at, "2")).unwrap();
+        }
+
+        let files = capture_files(&directory).unwrap();
+        assert_eq!(files.len(), 4);
+        assert!(earlier_run.exists());
+        assert!(unrelated.exists());
+        assert!(files[2].to_string_lossy().ends_with("-00002.jsonl"));
+        assert!(files[3].to_string_lossy().ends_with("-00003.jsonl"));
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[tokio::test]
+    async fn replays_recorded_frames_through_parser() {
+        let directory = scratch_dir();
+        let mut recorder = FrameRecorder::open(&config(&directory, u64::MAX, None)).unwrap();
+        recorder.write(&frame(1_000, "2")).unwrap();
+        recorder.write(&frame(1_010, MARKET_PRICE_FRAME)).unwrap();
+        recorder.write(&frame(1_020, MARKET_PRICE_FRAME)).unwrap();
+        drop(recorder);
+
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        let summary = replay_capture(&directory, ReplaySpeed::Unthrottled, &sender)
+            .await
+            .unwrap();
+
+        assert_eq!(summary.frames, 3);
+        assert_eq!(summary.events, 2);
+        for _ in 0..2 {
+            assert!(matches!(
+                receiver.try_recv(),
+                Ok(RuntimeEvent::MarketPrice(_))
+            ));
+        }
+        assert!(receiver.try_recv().is_err());
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[tokio::test]
+    async fn sink_writes_queued_frames_off_the_caller() {
+        let directory = scratch_dir();
+        let recorder = FrameRecorder::open(&config(&directory, u64::MAX, None)).unwrap();
+        let (sink, writer) = CaptureSink::spawn(recorder).unwrap();
+        assert!(sink.record("2"));
+        assert!(sink.record(MARKET_PRICE_FRAME));
+        drop(sink);
+        writer.join().unwrap();
+
+        let (sender, _receiver) = mpsc::unbounded_channel();
+        let summary = replay_capture(&directory, ReplaySpeed::Unthrottled, &sender)
+            .await
+            .unwrap();
+        assert_eq!(summary.frames, 2);
+
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn replay_speed_scales_recorded_gaps() {
+        assert_eq!(
+            ReplaySpeed::Original.delay(250),
+            Some(Duration::from_millis(250))
+        );
+        assert_eq!(
+            ReplaySpeed::Accelerated(10.0).delay(250),
+            Some(Duration::from_millis(25))
+        );
+        assert_eq!(ReplaySpeed::Unthrottled.delay(250), None);
+        assert_eq!(ReplaySpeed::Original.delay(0), None);
+    }
+}
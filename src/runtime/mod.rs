@@ -1,43 +1,146 @@
+mod backoff;
+mod capture;
+mod products;
+pub(crate) mod protocol;
+mod settings;
+mod signal;
+mod transport;
+
+use std::sync::Arc;
+
 use alloy::primitives::Address;
 use alloy_sol_types::{Eip712Domain, eip712_domain};
-use futures_util::{SinkExt, StreamExt};
-use tokio::sync::mpsc;
+pub use capture::{CaptureError, ReplaySpeed, ReplaySummary, replay_capture};
+use dashmap::DashSet;
+pub use products::{ProductClient, ProductError, ProductInfo, resolve_product_config};
+pub use protocol::ProtocolError;
+pub use settings::{CaptureConfig, ReconnectConfig};
+pub use signal::shutdown_signal;
+use tokio::sync::{mpsc, oneshot};
+pub use transport::{
+    ChannelListener, ChannelPeer, ChannelTransport, TungsteniteTransport, WsTransportRuntime,
+};
 use uuid::Uuid;
 
+use self::backoff::Backoff;
+use self::capture::{CaptureSink, FrameRecorder};
+use self::protocol::{
+    EnginePacket, OpenPacket, PONG_FRAME, STREAM_NAMESPACE, SocketPacket, decode_frame,
+    encode_connect, encode_disconnect, encode_event,
+};
+use self::transport::WsStreamRuntime;
 use crate::error::EtherealRuntimeError;
-use crate::executor::{LiveExecutor, OrderExecutorRuntime, PaperExecutor};
+use crate::executor::{LiveExecutor, OrderExecutorRuntime, PaperExchange, PaperExecutor};
 use crate::logging::targets;
 use crate::models::common::TimeInForce;
 use crate::models::contracts::TradeOrder;
 use crate::models::dto::{
-    CancelOrderData, CancelOrderRequest, MarketPriceData, OrderRequest, OrderUpdateData, Timestamp,
-    TradeOrderData, WsEvent, parse_ws_event,
+    CancelOrderData, CancelOrderRequest, MarketPriceData, OpenOrderData, OrderRequest,
+    OrderUpdateData, PositionData, Timestamp, TradeOrderData, WsEvent, parse_ws_event,
 };
+use crate::risk::{OrderCheck, RiskGuard};
 use crate::settings::{Config, ExecutionMode};
+use crate::trading::state::TrackedOrder;
 
 #[derive(Debug, Clone)]
 pub enum RuntimeEvent {
     OrderUpdate(OrderUpdateData),
     MarketPrice(MarketPriceData),
+    /// The websocket dropped; no market data or order updates arrive until `Reconnected`.
+    Disconnected,
+    /// The websocket is back and every previous subscription has been resent.
+    Reconnected,
+}
+
+const HANDSHAKE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+const CLOSE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+struct WsConnection {
+    stream: WsStreamRuntime,
+    open: OpenPacket,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize)]
+#[serde(tag = "type")]
+enum Subscription {
+    #[serde(rename = "OrderUpdate", rename_all = "camelCase")]
+    OrderUpdates { subaccount_id: Uuid },
+    #[serde(rename_all = "camelCase")]
+    MarketPrice { product_id: Uuid },
+}
+
+impl Subscription {
+    fn frame(&self) -> String {
+        match *self {
+            Self::OrderUpdates { subaccount_id } => {
+                build_subscribe_order_updates_frame(subaccount_id)
+            }
+            Self::MarketPrice { product_id } => build_subscribe_market_price_frame(product_id),
+        }
+    }
+}
+
+/// Instructions from the runtime handle to the connection job.
+enum WsCommand {
+    Send(String),
+    /// Disconnect the namespace, close the socket and stop reconnecting.
+    Close(oneshot::Sender<()>),
 }
 
-type WsWriteType = futures_util::stream::SplitSink<
-    tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>,
-    tokio_tungstenite::tungstenite::Message,
->;
+/// Why a single websocket session ended.
+enum SessionEnd {
+    ConnectionLost,
+    Closed,
+    RuntimeDropped,
+}
 
-type WsReadType = futures_util::stream::SplitStream<
-    tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>,
->;
+enum ReconnectOutcome {
+    Connected(WsConnection),
+    Closed,
+    Exhausted,
+}
 
 fn build_subscribe_order_updates_frame(subaccount_id: Uuid) -> String {
-    format!(
-        r#"42/v1/stream,["subscribe",{{"type":"OrderUpdate","subaccountId":"{subaccount_id}"}}]"#
-    )
+    encode_event(STREAM_NAMESPACE, "subscribe", &Subscription::OrderUpdates {
+        subaccount_id,
+    })
 }
 
 fn build_subscribe_market_price_frame(product_id: Uuid) -> String {
-    format!(r#"42/v1/stream,["subscribe",{{"type":"MarketPrice","productId":"{product_id}"}}]"#)
+    encode_event(STREAM_NAMESPACE, "subscribe", &Subscription::MarketPrice {
+        product_id,
+    })
+}
+
+/// The strategies share one signer, so they must trade one subaccount, and
+/// each product may only be driven by one of them. Returns the subaccount.
+fn check_strategies(config: &Config) -> Result<Uuid, EtherealRuntimeError> {
+    let Some(first) = config.strategies.first() else {
+        return Err(EtherealRuntimeError::InvalidStrategies(
+            "no strategy configured",
+        ));
+    };
+
+    let mut products = std::collections::HashSet::new();
+    for strategy in &config.strategies {
+        if strategy.subaccount != first.subaccount {
+            return Err(EtherealRuntimeError::InvalidStrategies(
+                "strategies trade different subaccounts",
+            ));
+        }
+        if !products.insert(strategy.product_id) {
+            return Err(EtherealRuntimeError::InvalidStrategies(
+                "a product is configured more than once",
+            ));
+        }
+        if 2 * strategy.ladder.levels as usize > config.risk.max_open_orders {
+            return Err(EtherealRuntimeError::InvalidStrategies(
+                "risk.max_open_orders is below the ladder levels on both sides",
+            ));
+        }
+    }
+
+    Ok(first.subaccount)
 }
 
 pub(crate) fn make_domain(chain_id: u64, exchange: Address) -> Eip712Domain {
@@ -53,34 +156,90 @@ pub struct EtherealRuntime {
     signer: crate::signer::Signer,
     domain: Eip712Domain,
     order_executor: OrderExecutorRuntime,
+    risk: RiskGuard,
+    /// Feeds the risk job, as the websocket does.
+    event_sender: mpsc::UnboundedSender<RuntimeEvent>,
 
-    ws_sender: mpsc::Sender<tokio_tungstenite::tungstenite::Message>,
+    ws_sender: mpsc::Sender<WsCommand>,
+    subscriptions: Arc<DashSet<Subscription>>,
 }
 
 impl EtherealRuntime {
     pub async fn new(
         config: &Config,
     ) -> Result<(Self, mpsc::UnboundedReceiver<RuntimeEvent>), EtherealRuntimeError> {
-        let (ws_write, ws_read) = Self::connect_ws(&config.ws_url).await?;
+        let transport =
+            WsTransportRuntime::Tungstenite(TungsteniteTransport::new(config.ws_url.clone()));
+        Self::with_transport(config, transport).await
+    }
+
+    /// Like [`Self::new`], but market data and order updates come over `transport`
+    /// instead of a websocket to `config.ws_url`.
+    pub async fn with_transport(
+        config: &Config,
+        transport: WsTransportRuntime,
+    ) -> Result<(Self, mpsc::UnboundedReceiver<RuntimeEvent>), EtherealRuntimeError> {
+        let subaccount_id = check_strategies(config)?;
+        let recorder = config
+            .capture
+            .enabled
+            .then(|| FrameRecorder::open(&config.capture).and_then(CaptureSink::spawn))
+            .transpose()?
+            .map(|(sink, _writer)| sink);
+        let connection = Self::connect_ws(&transport).await?;
         let (ws_sender, ws_receiver) = tokio::sync::mpsc::channel(32);
-        let (event_sender, event_receiver) = mpsc::unbounded_channel();
+        let (event_sender, risk_events) = mpsc::unbounded_channel();
+        let (strategy_sender, event_receiver) = mpsc::unbounded_channel();
+        let subscriptions = Arc::new(DashSet::new());
+
+        let risk = RiskGuard::new(config.risk.clone(), subaccount_id);
+        for strategy in &config.strategies {
+            risk.register_product(strategy.product_id, strategy.onchain_product_id);
+        }
+        tokio::spawn(Self::spawn_risk_job(
+            risk_events,
+            risk.clone(),
+            strategy_sender,
+        ));
 
         let http_client = reqwest::Client::new();
-        let order_executor = match config.execution_mode {
-            ExecutionMode::Live => {
-                OrderExecutorRuntime::Live(LiveExecutor::new(http_client, config.rest_url.clone()))
+        let (order_executor, ws_event_sender) = match config.execution_mode {
+            ExecutionMode::Live => (
+                OrderExecutorRuntime::Live(LiveExecutor::new(http_client, config.rest_url.clone())),
+                event_sender.clone(),
+            ),
+            ExecutionMode::Paper => {
+                let exchange = PaperExchange::new(event_sender.clone());
+                for strategy in &config.strategies {
+                    exchange.register_product(strategy.product_id, strategy.onchain_product_id);
+                }
+
+                let (ws_event_sender, ws_events) = mpsc::unbounded_channel();
+                tokio::spawn(Self::spawn_paper_market_job(
+                    ws_events,
+                    exchange.clone(),
+                    event_sender.clone(),
+                ));
+
+                (
+                    OrderExecutorRuntime::Paper(PaperExecutor::new(
+                        http_client,
+                        config.rest_url.clone(),
+                        exchange,
+                    )),
+                    ws_event_sender,
+                )
             }
-            ExecutionMode::Paper => OrderExecutorRuntime::Paper(PaperExecutor::new(
-                http_client,
-                config.rest_url.clone(),
-            )),
         };
 
-        tokio::spawn(Self::spawn_write_job(ws_write, ws_receiver));
-        tokio::spawn(Self::spawn_read_job(
-            ws_read,
-            ws_sender.clone(),
-            event_sender,
+        tokio::spawn(Self::spawn_connection_job(
+            transport,
+            config.reconnect.clone(),
+            connection,
+            ws_receiver,
+            ws_event_sender,
+            subscriptions.clone(),
+            recorder,
         ));
 
         Ok((
@@ -88,138 +247,466 @@ impl EtherealRuntime {
                 signer: crate::signer::Signer::new(&config.signer_config),
                 domain: make_domain(config.chain_id, config.exchange),
                 order_executor,
+                risk,
+                event_sender,
                 ws_sender,
+                subscriptions,
             },
             event_receiver,
         ))
     }
 
     async fn connect_ws(
-        ws_url: &url::Url,
-    ) -> Result<(WsWriteType, WsReadType), EtherealRuntimeError> {
-        use tokio_tungstenite::tungstenite::Message;
+        transport: &WsTransportRuntime,
+    ) -> Result<WsConnection, EtherealRuntimeError> {
+        let mut stream = transport.connect().await?;
 
-        tracing::info!(
+        let expected = "engine.io open packet";
+        let frame = Self::next_handshake_frame(&mut stream, expected).await?;
+        let EnginePacket::Open(open) = decode_frame(&frame)? else {
+            return Err(ProtocolError::UnexpectedPacket { expected, frame }.into());
+        };
+
+        tracing::debug!(
             target: targets::RUNTIME_WS,
-            %ws_url,
-            "connecting websocket"
+            sid = %open.sid,
+            ping_interval_ms = open.ping_interval,
+            ping_timeout_ms = open.ping_timeout,
+            "engine.io session opened"
         );
 
-        let url = ws_url.join("socket.io/?EIO=4&transport=websocket")?;
-
-        let (ws_stream, response) = tokio_tungstenite::connect_async(url.to_string()).await?;
+        stream.send_text(encode_connect(STREAM_NAMESPACE)).await?;
 
-        if response.status() != reqwest::StatusCode::SWITCHING_PROTOCOLS {
-            return Err(EtherealRuntimeError::WS(format!(
-                "unexpected status: {}",
-                response.status(),
-            )));
+        let expected = "namespace connect ack";
+        loop {
+            let frame = Self::next_handshake_frame(&mut stream, expected).await?;
+            match decode_frame(&frame)? {
+                EnginePacket::Ping => stream.send_text(PONG_FRAME.to_string()).await?,
+                EnginePacket::Message(SocketPacket::Connect { namespace })
+                    if namespace == STREAM_NAMESPACE =>
+                {
+                    break;
+                }
+                EnginePacket::Message(SocketPacket::ConnectError { namespace, message }) => {
+                    return Err(ProtocolError::ConnectRejected { namespace, message }.into());
+                }
+                _ => return Err(ProtocolError::UnexpectedPacket { expected, frame }.into()),
+            }
         }
 
-        let (mut write, mut read) = ws_stream.split();
-
-        read.next().await;
-
-        write.send(Message::Text("40/v1/stream,".into())).await?;
-        read.next().await;
-
         tracing::info!(
             target: targets::RUNTIME_WS,
             "websocket namespace connected"
         );
 
-        Ok((write, read))
+        Ok(WsConnection { stream, open })
+    }
+
+    async fn next_handshake_frame(
+        stream: &mut WsStreamRuntime,
+        expected: &'static str,
+    ) -> Result<String, EtherealRuntimeError> {
+        let next = tokio::time::timeout(HANDSHAKE_TIMEOUT, stream.next_text())
+            .await
+            .map_err(|_| ProtocolError::HandshakeTimeout(expected))?;
+
+        next.unwrap_or_else(|| {
+            Err(EtherealRuntimeError::WS(format!(
+                "websocket closed while waiting for {expected}"
+            )))
+        })
     }
 
-    async fn spawn_write_job(
-        mut ws_write: WsWriteType,
-        mut ws_receiver: tokio::sync::mpsc::Receiver<tokio_tungstenite::tungstenite::Message>,
+    /// Feeds every market tick through the paper book before the strategy sees
+    /// it, so simulated fills land ahead of the price that caused them.
+    async fn spawn_paper_market_job(
+        mut ws_events: mpsc::UnboundedReceiver<RuntimeEvent>,
+        exchange: PaperExchange,
+        event_sender: mpsc::UnboundedSender<RuntimeEvent>,
     ) {
-        while let Some(msg) = ws_receiver.recv().await {
-            if let Err(error) = ws_write.send(msg).await {
-                tracing::error!(
-                    target: targets::RUNTIME_WS,
-                    %error,
-                    "websocket write failed"
-                );
+        while let Some(event) = ws_events.recv().await {
+            if let RuntimeEvent::MarketPrice(tick) = &event {
+                exchange.on_market_price(tick);
+            }
+            if event_sender.send(event).is_err() {
+                return;
+            }
+        }
+    }
+
+    /// Last stage before the strategy: every event, paper fills included,
+    /// updates the risk state before the strategy can act on it.
+    async fn spawn_risk_job(
+        mut events: mpsc::UnboundedReceiver<RuntimeEvent>,
+        risk: RiskGuard,
+        strategy_sender: mpsc::UnboundedSender<RuntimeEvent>,
+    ) {
+        while let Some(event) = events.recv().await {
+            risk.observe(&event);
+            if strategy_sender.send(event).is_err() {
+                return;
             }
         }
     }
 
-    async fn spawn_read_job(
-        mut ws_read: WsReadType,
-        ws_sender: mpsc::Sender<tokio_tungstenite::tungstenite::Message>,
+    /// Owns the websocket for the lifetime of the runtime: pumps frames in both
+    /// directions and, when the session drops, reconnects and replays subscriptions.
+    async fn spawn_connection_job(
+        transport: WsTransportRuntime,
+        reconnect: ReconnectConfig,
+        mut connection: WsConnection,
+        mut ws_receiver: mpsc::Receiver<WsCommand>,
         event_sender: mpsc::UnboundedSender<RuntimeEvent>,
+        subscriptions: Arc<DashSet<Subscription>>,
+        mut recorder: Option<CaptureSink>,
     ) {
-        use tokio_tungstenite::tungstenite::Message;
+        let mut backoff = Backoff::new(&reconnect);
+
+        loop {
+            match Self::run_session(
+                &mut connection,
+                &mut ws_receiver,
+                &event_sender,
+                &mut recorder,
+            )
+            .await
+            {
+                SessionEnd::Closed | SessionEnd::RuntimeDropped => return,
+                SessionEnd::ConnectionLost => {}
+            }
+
+            tracing::warn!(
+                target: targets::RUNTIME_WS,
+                "websocket connection lost"
+            );
+            if event_sender.send(RuntimeEvent::Disconnected).is_err() {
+                return;
+            }
+
+            match Self::reconnect_ws(&transport, &mut backoff, &mut ws_receiver, &subscriptions)
+                .await
+            {
+                ReconnectOutcome::Connected(reconnected) => connection = reconnected,
+                ReconnectOutcome::Closed => return,
+                ReconnectOutcome::Exhausted => {
+                    tracing::error!(
+                        target: targets::RUNTIME_WS,
+                        attempts = backoff.attempt(),
+                        "websocket reconnect attempts exhausted"
+                    );
+                    return;
+                }
+            }
+
+            backoff.reset();
+
+            if event_sender.send(RuntimeEvent::Reconnected).is_err() {
+                return;
+            }
+        }
+    }
+
+    async fn run_session(
+        connection: &mut WsConnection,
+        ws_receiver: &mut mpsc::Receiver<WsCommand>,
+        event_sender: &mpsc::UnboundedSender<RuntimeEvent>,
+        recorder: &mut Option<CaptureSink>,
+    ) -> SessionEnd {
+        use tokio::time::Instant;
+
+        let heartbeat_timeout = connection.open.heartbeat_timeout();
+        let mut heartbeat_deadline = Instant::now() + heartbeat_timeout;
 
         loop {
-            match ws_read.next().await {
-                Some(Ok(Message::Text(text))) => {
-                    if text == "2" {
-                        if let Err(error) = ws_sender.send(Message::Text("3".into())).await {
-                            tracing::warn!(
-                                target: targets::RUNTIME_WS,
-                                %error,
-                                "failed to enqueue websocket pong"
-                            );
+            tokio::select! {
+                command = ws_receiver.recv() => {
+                    let msg = match command {
+                        Some(WsCommand::Send(msg)) => msg,
+                        Some(WsCommand::Close(ack)) => {
+                            Self::close_session(connection).await;
+                            let _ = ack.send(());
+                            return SessionEnd::Closed;
                         }
-                        continue;
-                    }
+                        None => return SessionEnd::RuntimeDropped,
+                    };
 
-                    if let Some(event) = parse_ws_event(&text) {
-                        match event {
-                            WsEvent::OrderUpdate(updates) => {
-                                for update in updates {
-                                    event_sender
-                                        .send(RuntimeEvent::OrderUpdate(update))
-                                        .expect("runtime event receiver dropped");
-                                }
-                            }
-                            WsEvent::MarketPrice(prices) => {
-                                for price in prices {
-                                    event_sender
-                                        .send(RuntimeEvent::MarketPrice(price))
-                                        .expect("runtime event receiver dropped");
-                                }
-                            }
-                            WsEvent::Unknown { event, payload } => {
-                                tracing::debug!(
-                                    target: targets::RUNTIME_WS,
-                                    event,
-                                    %payload,
-                                    "received unknown websocket event"
-                                );
-                            }
+                    if let Err(error) = connection.stream.send_text(msg).await {
+                        tracing::error!(
+                            target: targets::RUNTIME_WS,
+                            %error,
+                            "websocket write failed"
+                        );
+                        return SessionEnd::ConnectionLost;
+                    }
+                }
+                _ = tokio::time::sleep_until(heartbeat_deadline) => {
+                    tracing::warn!(
+                        target: targets::RUNTIME_WS,
+                        timeout_ms = heartbeat_timeout.as_millis() as u64,
+                        "websocket heartbeat timed out"
+                    );
+                    return SessionEnd::ConnectionLost;
+                }
+                incoming = connection.stream.next_text() => match incoming {
+                    Some(Ok(text)) => {
+                        Self::capture_frame(recorder, &text);
+                        if let Some(end) = Self::handle_text_frame(
+                            &mut connection.stream,
+                            event_sender,
+                            &text,
+                            &mut heartbeat_deadline,
+                            heartbeat_timeout,
+                        )
+                        .await
+                        {
+                            return end;
                         }
                     }
+                    Some(Err(error)) => {
+                        tracing::error!(
+                            target: targets::RUNTIME_WS,
+                            %error,
+                            "websocket read error"
+                        );
+                        return SessionEnd::ConnectionLost;
+                    }
+                    None => return SessionEnd::ConnectionLost,
+                },
+            }
+        }
+    }
+
+    /// Queues a frame when capture is on; once the writer has failed, capture
+    /// stays off so its problems never take the session down.
+    fn capture_frame(recorder: &mut Option<CaptureSink>, frame: &str) {
+        if recorder.as_ref().is_some_and(|sink| !sink.record(frame)) {
+            *recorder = None;
+        }
+    }
+
+    /// Handles one inbound text frame; returns `Some` when the session must end.
+    async fn handle_text_frame(
+        stream: &mut WsStreamRuntime,
+        event_sender: &mpsc::UnboundedSender<RuntimeEvent>,
+        text: &str,
+        heartbeat_deadline: &mut tokio::time::Instant,
+        heartbeat_timeout: std::time::Duration,
+    ) -> Option<SessionEnd> {
+        if let Some(event) = parse_ws_event(text) {
+            return (!Self::dispatch_ws_event(event_sender, event))
+                .then_some(SessionEnd::RuntimeDropped);
+        }
+
+        match decode_frame(text) {
+            Ok(EnginePacket::Ping) => {
+                *heartbeat_deadline = tokio::time::Instant::now() + heartbeat_timeout;
+                if let Err(error) = stream.send_text(PONG_FRAME.to_string()).await {
+                    tracing::warn!(
+                        target: targets::RUNTIME_WS,
+                        %error,
+                        "failed to send websocket pong"
+                    );
+                    return Some(SessionEnd::ConnectionLost);
                 }
-                Some(Ok(_)) => continue,
-                Some(Err(error)) => {
-                    tracing::error!(
+                None
+            }
+            Ok(EnginePacket::Message(SocketPacket::Disconnect { namespace })) => {
+                tracing::warn!(
+                    target: targets::RUNTIME_WS,
+                    namespace,
+                    "server disconnected namespace"
+                );
+                Some(SessionEnd::ConnectionLost)
+            }
+            Ok(EnginePacket::Message(SocketPacket::ConnectError { namespace, message })) => {
+                tracing::error!(
+                    target: targets::RUNTIME_WS,
+                    namespace,
+                    message,
+                    "server rejected namespace"
+                );
+                Some(SessionEnd::ConnectionLost)
+            }
+            Ok(EnginePacket::Close) => {
+                tracing::warn!(
+                    target: targets::RUNTIME_WS,
+                    "server closed engine.io session"
+                );
+                Some(SessionEnd::ConnectionLost)
+            }
+            Ok(packet) => {
+                tracing::debug!(
+                    target: targets::RUNTIME_WS,
+                    ?packet,
+                    "ignoring websocket packet"
+                );
+                None
+            }
+            Err(error) => {
+                tracing::debug!(
+                    target: targets::RUNTIME_WS,
+                    %error,
+                    frame = text,
+                    "failed to decode websocket frame"
+                );
+                None
+            }
+        }
+    }
+
+    /// Forwards a parsed frame to the strategy; returns `false` once nobody is listening.
+    fn dispatch_ws_event(
+        event_sender: &mpsc::UnboundedSender<RuntimeEvent>,
+        event: WsEvent,
+    ) -> bool {
+        match event {
+            WsEvent::OrderUpdate(updates) => updates
+                .into_iter()
+                .all(|update| event_sender.send(RuntimeEvent::OrderUpdate(update)).is_ok()),
+            WsEvent::MarketPrice(prices) => prices
+                .into_iter()
+                .all(|price| event_sender.send(RuntimeEvent::MarketPrice(price)).is_ok()),
+            WsEvent::Unknown { event, payload } => {
+                tracing::debug!(
+                    target: targets::RUNTIME_WS,
+                    event,
+                    %payload,
+                    "received unknown websocket event"
+                );
+                true
+            }
+        }
+    }
+
+    async fn close_session(connection: &mut WsConnection) {
+        let disconnect = encode_disconnect(STREAM_NAMESPACE);
+        if let Err(error) = connection.stream.send_text(disconnect).await {
+            tracing::debug!(
+                target: targets::RUNTIME_WS,
+                %error,
+                "failed to send namespace disconnect"
+            );
+        }
+        if let Err(error) = connection.stream.close().await {
+            tracing::debug!(
+                target: targets::RUNTIME_WS,
+                %error,
+                "failed to close websocket"
+            );
+        }
+
+        tracing::info!(
+            target: targets::RUNTIME_WS,
+            "websocket closed"
+        );
+    }
+
+    async fn reconnect_ws(
+        transport: &WsTransportRuntime,
+        backoff: &mut Backoff,
+        ws_receiver: &mut mpsc::Receiver<WsCommand>,
+        subscriptions: &DashSet<Subscription>,
+    ) -> ReconnectOutcome {
+        while let Some(delay) = backoff.next_delay() {
+            tracing::info!(
+                target: targets::RUNTIME_WS,
+                attempt = backoff.attempt(),
+                delay_ms = delay.as_millis() as u64,
+                "scheduling websocket reconnect"
+            );
+            if !Self::wait_offline(ws_receiver, delay).await {
+                return ReconnectOutcome::Closed;
+            }
+
+            let mut connection = match Self::connect_ws(transport).await {
+                Ok(connection) => connection,
+                Err(error) => {
+                    tracing::warn!(
+                        target: targets::RUNTIME_WS,
+                        attempt = backoff.attempt(),
+                        %error,
+                        "websocket reconnect failed"
+                    );
+                    continue;
+                }
+            };
+
+            // Frames queued while offline belong to the dead session; every
+            // subscription they may carry is replayed below.
+            while let Ok(command) = ws_receiver.try_recv() {
+                if let WsCommand::Close(ack) = command {
+                    Self::close_session(&mut connection).await;
+                    let _ = ack.send(());
+                    return ReconnectOutcome::Closed;
+                }
+            }
+
+            let frames: Vec<String> = subscriptions.iter().map(|sub| sub.frame()).collect();
+            let mut resubscribed = true;
+            for frame in frames {
+                if let Err(error) = connection.stream.send_text(frame).await {
+                    tracing::warn!(
                         target: targets::RUNTIME_WS,
                         %error,
-                        "websocket read error"
+                        "websocket resubscribe failed"
                     );
+                    resubscribed = false;
                     break;
                 }
-                None => break,
+            }
+
+            if resubscribed {
+                tracing::info!(
+                    target: targets::RUNTIME_WS,
+                    subscriptions = subscriptions.len(),
+                    "websocket reconnected"
+                );
+                return ReconnectOutcome::Connected(connection);
             }
         }
+
+        ReconnectOutcome::Exhausted
     }
 
-    pub async fn subscribe_order_updates(
-        &self,
-        subaccount_id: Uuid,
-    ) -> Result<(), EtherealRuntimeError> {
-        use tokio_tungstenite::tungstenite::Message;
+    /// Sleeps out a backoff delay while still honouring close requests;
+    /// returns `false` when the job should stop instead of reconnecting.
+    async fn wait_offline(
+        ws_receiver: &mut mpsc::Receiver<WsCommand>,
+        delay: std::time::Duration,
+    ) -> bool {
+        let sleep = tokio::time::sleep(delay);
+        tokio::pin!(sleep);
+
+        loop {
+            tokio::select! {
+                _ = &mut sleep => return true,
+                command = ws_receiver.recv() => match command {
+                    Some(WsCommand::Send(_)) => continue,
+                    Some(WsCommand::Close(ack)) => {
+                        let _ = ack.send(());
+                        return false;
+                    }
+                    None => return false,
+                },
+            }
+        }
+    }
 
-        let msg = build_subscribe_order_updates_frame(subaccount_id);
+    async fn subscribe(&self, subscription: Subscription) -> Result<(), EtherealRuntimeError> {
+        self.subscriptions.insert(subscription);
 
         self.ws_sender
-            .send(Message::Text(msg.into()))
+            .send(WsCommand::Send(subscription.frame()))
             .await
-            .expect("websocket sender dropped");
+            .map_err(|_| EtherealRuntimeError::WS("websocket connection job stopped".to_string()))
+    }
+
+    pub async fn subscribe_order_updates(
+        &self,
+        subaccount_id: Uuid,
+    ) -> Result<(), EtherealRuntimeError> {
+        self.subscribe(Subscription::OrderUpdates { subaccount_id })
+            .await?;
 
         tracing::info!(
             target: targets::RUNTIME_WS,
@@ -234,14 +721,8 @@ impl EtherealRuntime {
         &self,
         product_id: Uuid,
     ) -> Result<(), EtherealRuntimeError> {
-        use tokio_tungstenite::tungstenite::Message;
-
-        let msg = build_subscribe_market_price_frame(product_id);
-
-        self.ws_sender
-            .send(Message::Text(msg.into()))
-            .await
-            .expect("websocket sender dropped");
+        self.subscribe(Subscription::MarketPrice { product_id })
+            .await?;
 
         tracing::info!(
             target: targets::RUNTIME_WS,
@@ -261,6 +742,28 @@ impl EtherealRuntime {
         post_only: bool,
         time_in_force: TimeInForce,
     ) -> Result<Uuid, EtherealRuntimeError> {
+        let client_order_id = Uuid::new_v4();
+        let check = OrderCheck {
+            client_order_id,
+            onchain_product_id: product_id,
+            side,
+            price_raw,
+            qty_raw,
+        };
+        if let Err(rejection) = self.risk.reserve(&check) {
+            tracing::warn!(
+                target: targets::TRADING_DECISION,
+                %client_order_id,
+                product_id,
+                side,
+                price_raw,
+                qty_raw,
+                %rejection,
+                "order refused by risk check"
+            );
+            return Err(rejection.into());
+        }
+
         let ts = Timestamp::now();
 
         let order = TradeOrder {
@@ -277,9 +780,10 @@ impl EtherealRuntime {
         };
 
         let signature = self.signer.sign_trade_order(&order, &self.domain);
-        let data = TradeOrderData::from_trade_order(order, post_only, time_in_force);
-
-        let client_order_id = data.client_order_id;
+        let data = TradeOrderData {
+            client_order_id,
+            ..TradeOrderData::from_trade_order(order, post_only, time_in_force)
+        };
 
         tracing::info!(
             target: targets::RUNTIME_EXEC,
@@ -294,9 +798,10 @@ impl EtherealRuntime {
             signature: format!("0x{}", hex::encode(signature.as_bytes())),
         };
 
-        let payload = match self.order_executor.submit_order(&order).await {
-            Ok(value) => value,
+        let created = match self.order_executor.submit_order(&order).await {
+            Ok(created) => created,
             Err(error) => {
+                self.risk.release(client_order_id);
                 tracing::warn!(
                     target: targets::RUNTIME_EXEC,
                     %client_order_id,
@@ -310,13 +815,16 @@ impl EtherealRuntime {
         tracing::info!(
             target: targets::RUNTIME_EXEC,
             %client_order_id,
-            %payload,
+            order_id = %created.id,
+            filled = %created.filled,
             "order accepted"
         );
 
         Ok(client_order_id)
     }
 
+    /// While the cancel is outstanding the order no longer counts toward the
+    /// risk check's open order limit, so a replacement can go out alongside.
     pub async fn cancel_order(&self, client_order_id: Uuid) -> Result<(), EtherealRuntimeError> {
         let ts_cancel = Timestamp::now();
         let (cancel_sig, order) = self.signer.sign_cancel_order(ts_cancel.nonce, &self.domain);
@@ -332,7 +840,11 @@ impl EtherealRuntime {
             "submitting cancel"
         );
 
-        self.order_executor.cancel_order(&cancel_req).await?;
+        self.risk.set_cancelling(client_order_id, true);
+        if let Err(error) = self.order_executor.cancel_order(&cancel_req).await {
+            self.risk.set_cancelling(client_order_id, false);
+            return Err(error.into());
+        }
         tracing::info!(
             target: targets::RUNTIME_EXEC,
             %client_order_id,
@@ -341,8 +853,149 @@ impl EtherealRuntime {
         Ok(())
     }
 
-    pub async fn _shutdown(&mut self) -> Result<(), EtherealRuntimeError> {
-        todo!("graceful runtime shutdown is not implemented yet")
+    /// Cancels by exchange order id, for orders this process did not place.
+    pub async fn cancel_orders_by_id(
+        &self,
+        order_ids: Vec<Uuid>,
+    ) -> Result<(), EtherealRuntimeError> {
+        if order_ids.is_empty() {
+            return Ok(());
+        }
+
+        let ts_cancel = Timestamp::now();
+        let (cancel_sig, order) = self.signer.sign_cancel_order(ts_cancel.nonce, &self.domain);
+        let count = order_ids.len();
+        let cancel_req = CancelOrderRequest {
+            data: CancelOrderData::from_cancel_order(order, order_ids, vec![]),
+            signature: format!("0x{}", hex::encode(cancel_sig.as_bytes())),
+        };
+
+        tracing::info!(
+            target: targets::RUNTIME_EXEC,
+            count,
+            "submitting cancel by order id"
+        );
+
+        self.order_executor.cancel_order(&cancel_req).await?;
+        tracing::info!(
+            target: targets::RUNTIME_EXEC,
+            count,
+            "cancel accepted"
+        );
+        Ok(())
+    }
+
+    /// Orders still working on the exchange for `subaccount_id` and `product_id`.
+    /// What they have filled so far is counted as booked by the risk check,
+    /// as it is part of the positions read by [`Self::positions`].
+    pub async fn open_orders(
+        &self,
+        subaccount_id: Uuid,
+        product_id: Uuid,
+    ) -> Result<Vec<OpenOrderData>, EtherealRuntimeError> {
+        let orders = self
+            .order_executor
+            .open_orders(subaccount_id, product_id)
+            .await?;
+        self.risk.mark_booked(&orders);
+
+        tracing::info!(
+            target: targets::RUNTIME_EXEC,
+            %subaccount_id,
+            %product_id,
+            count = orders.len(),
+            "fetched open orders"
+        );
+
+        Ok(orders)
+    }
+
+    /// Looks up an order whose update never arrived and, when the exchange
+    /// still has it, publishes its current state as an order update, so the
+    /// risk check and the strategy book its fills as usual. Returns `false`
+    /// when the exchange has no such order.
+    pub(crate) async fn recover_order_update(
+        &self,
+        subaccount_id: Uuid,
+        client_order_id: Uuid,
+    ) -> Result<bool, EtherealRuntimeError> {
+        let Some(order) = self
+            .order_executor
+            .order_by_client_id(subaccount_id, client_order_id)
+            .await?
+        else {
+            return Ok(false);
+        };
+
+        tracing::info!(
+            target: targets::RUNTIME_EXEC,
+            %client_order_id,
+            order_id = %order.id,
+            status = ?order.status,
+            filled = %order.filled,
+            "recovered order update over REST"
+        );
+        self.publish_order_update(order.to_update(subaccount_id));
+        Ok(true)
+    }
+
+    /// Feeds `update` to the risk check and the strategies as if it had
+    /// arrived over the websocket, for state read over REST.
+    pub(crate) fn publish_order_update(&self, update: OrderUpdateData) {
+        let _ = self.event_sender.send(RuntimeEvent::OrderUpdate(update));
+    }
+
+    /// Open positions of `subaccount_id`, which also become the starting
+    /// positions of the risk check.
+    pub async fn positions(
+        &self,
+        subaccount_id: Uuid,
+    ) -> Result<Vec<PositionData>, EtherealRuntimeError> {
+        let positions = self.order_executor.positions(subaccount_id).await?;
+        self.risk.seed_positions(subaccount_id, &positions);
+
+        tracing::info!(
+            target: targets::RUNTIME_EXEC,
+            %subaccount_id,
+            count = positions.len(),
+            "fetched open positions"
+        );
+
+        Ok(positions)
+    }
+
+    /// Counts an order adopted at startup toward the risk check's limits, as
+    /// if it had been placed through [`Self::place_order`].
+    pub(crate) fn adopt_order(&self, subaccount_id: Uuid, product_id: Uuid, order: &TrackedOrder) {
+        self.risk.adopt(subaccount_id, product_id, order);
+    }
+
+    /// Frees the risk reservation of an order that is gone from the exchange
+    /// without its terminal update ever arriving.
+    pub(crate) fn forget_order(&self, client_order_id: Uuid) {
+        self.risk.release(client_order_id);
+    }
+
+    /// Disconnects the stream namespace and closes the websocket; the event
+    /// channel ends once the connection job has stopped.
+    pub async fn shutdown(&self) -> Result<(), EtherealRuntimeError> {
+        let (ack_sender, ack_receiver) = oneshot::channel();
+
+        if self
+            .ws_sender
+            .send(WsCommand::Close(ack_sender))
+            .await
+            .is_err()
+        {
+            return Ok(());
+        }
+
+        match tokio::time::timeout(CLOSE_TIMEOUT, ack_receiver).await {
+            Ok(_) => Ok(()),
+            Err(_) => Err(EtherealRuntimeError::WS(
+                "timed out closing websocket".to_string(),
+            )),
+        }
     }
 }
 
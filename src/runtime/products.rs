@@ -0,0 +1,324 @@
+//! Typed client for `GET /v1/product`, used to fill in or check the product
+//! fields of [`StrategyConfig`] before anything is quoted.
+
+use uuid::Uuid;
+
+use crate::executor::endpoint;
+use crate::logging::targets;
+use crate::models::dto::{PageData, ProductData};
+use crate::models::util::decimal_to_raw;
+use crate::settings::Config;
+use crate::trading::settings::StrategyConfig;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ProductError {
+    #[error(transparent)]
+    Http(#[from] reqwest::Error),
+    #[error("product listing rejected (status: {status}): {payload}")]
+    Rejected { status: u16, payload: String },
+    #[error(transparent)]
+    Serde(#[from] serde_json::Error),
+    #[error("invalid product listing url: {0}")]
+    Url(#[from] url::ParseError),
+    #[error("product {0} is not listed by the exchange")]
+    Unknown(Uuid),
+    #[error("product {product_id} has an unusable `{field}`")]
+    Malformed {
+        product_id: Uuid,
+        field: &'static str,
+    },
+    #[error("strategy `{field}` is {configured} but the exchange has {exchange}")]
+    Mismatch {
+        field: &'static str,
+        configured: u128,
+        exchange: u128,
+    },
+    #[error("strategy `qty_raw` {qty_raw} is not a multiple of lot size {lot_size_raw}")]
+    OffLot { qty_raw: u128, lot_size_raw: u128 },
+    #[error("strategy `qty_raw` {qty_raw} is below the minimum quantity {min_quantity_raw}")]
+    BelowMinimum {
+        qty_raw: u128,
+        min_quantity_raw: u128,
+    },
+}
+
+/// Exchange metadata for one product, with sizes in raw order units.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProductInfo {
+    pub product_id: Uuid,
+    pub ticker: String,
+    pub onchain_id: u32,
+    pub tick_size_raw: u128,
+    pub lot_size_raw: u128,
+    pub min_quantity_raw: u128,
+    pub max_leverage: u32,
+    /// Fee rates per unit notional (1e9 = 100%); rebates are booked as 0.
+    pub maker_fee_raw: u128,
+    pub taker_fee_raw: u128,
+}
+
+impl TryFrom<ProductData> for ProductInfo {
+    type Error = ProductError;
+
+    fn try_from(product: ProductData) -> Result<Self, Self::Error> {
+        let raw = |field, value| {
+            decimal_to_raw(value).ok_or(ProductError::Malformed {
+                product_id: product.id,
+                field,
+            })
+        };
+
+        Ok(Self {
+            product_id: product.id,
+            onchain_id: product.onchain_id,
+            tick_size_raw: raw("tickSize", &product.tick_size)?,
+            lot_size_raw: raw("lotSize", &product.lot_size)?,
+            min_quantity_raw: raw("minQuantity", &product.min_quantity)?,
+            max_leverage: product.max_leverage,
+            maker_fee_raw: decimal_to_raw(&product.maker_fee).unwrap_or_default(),
+            taker_fee_raw: decimal_to_raw(&product.taker_fee).unwrap_or_default(),
+            ticker: product.ticker,
+        })
+    }
+}
+
+impl ProductInfo {
+    /// Fills the product fields `strategy` leaves at zero and checks the rest,
+    /// along with `qty_raw` against the lot size and minimum quantity.
+    pub fn apply_to(&self, strategy: &mut StrategyConfig) -> Result<(), ProductError> {
+        if strategy.onchain_product_id == 0 {
+            strategy.onchain_product_id = self.onchain_id;
+        } else if strategy.onchain_product_id != self.onchain_id {
+            return Err(ProductError::Mismatch {
+                field: "onchain_product_id",
+                configured: strategy.onchain_product_id.into(),
+                exchange: self.onchain_id.into(),
+            });
+        }
+
+        fill_or_check(
+            "tick_size_raw",
+            &mut strategy.tick_size_raw,
+            self.tick_size_raw,
+        )?;
+        fill_or_check(
+            "lot_size_raw",
+            &mut strategy.lot_size_raw,
+            self.lot_size_raw,
+        )?;
+        fill_or_check(
+            "min_qty_raw",
+            &mut strategy.min_qty_raw,
+            self.min_quantity_raw,
+        )?;
+
+        if strategy.fee_rate_raw == 0 {
+            strategy.fee_rate_raw = self.maker_fee_raw;
+        }
+
+        if self.lot_size_raw > 0 && !strategy.qty_raw.is_multiple_of(self.lot_size_raw) {
+            return Err(ProductError::OffLot {
+                qty_raw: strategy.qty_raw,
+                lot_size_raw: self.lot_size_raw,
+            });
+        }
+        if strategy.qty_raw == 0 || strategy.qty_raw < self.min_quantity_raw {
+            return Err(ProductError::BelowMinimum {
+                qty_raw: strategy.qty_raw,
+                min_quantity_raw: self.min_quantity_raw,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+fn fill_or_check(
+    field: &'static str,
+    configured: &mut u128,
+    exchange: u128,
+) -> Result<(), ProductError> {
+    if *configured == 0 {
+        *configured = exchange;
+        Ok(())
+    } else if *configured == exchange {
+        Ok(())
+    } else {
+        Err(ProductError::Mismatch {
+            field,
+            configured: *configured,
+            exchange,
+        })
+    }
+}
+
+pub struct ProductClient {
+    http_client: reqwest::Client,
+    rest_url: url::Url,
+}
+
+impl ProductClient {
+    pub fn new(http_client: reqwest::Client, rest_url: url::Url) -> Self {
+        Self {
+            http_client,
+            rest_url,
+        }
+    }
+
+    /// Every listed product, following the cursor across pages.
+    pub async fn products(&self) -> Result<Vec<ProductInfo>, ProductError> {
+        let mut products = Vec::new();
+        let mut cursor: Option<String> = None;
+
+        loop {
+            let mut url: url::Url = endpoint(&self.rest_url, "/v1/product").parse()?;
+            if let Some(cursor) = &cursor {
+                url.query_pairs_mut().append_pair("cursor", cursor);
+            }
+
+            let response = self.http_client.get(url).send().await?;
+            let status = response.status();
+            if !status.is_success() {
+                return Err(ProductError::Rejected {
+                    status: status.as_u16(),
+                    payload: response.text().await?,
+                });
+            }
+
+            let page: PageData<ProductData> = serde_json::from_slice(&response.bytes().await?)?;
+            for product in page.data {
+                products.push(product.try_into()?);
+            }
+
+            match page.next_cursor {
+                Some(next) if page.has_next => cursor = Some(next),
+                _ => break,
+            }
+        }
+
+        Ok(products)
+    }
+
+    pub async fn product(&self, product_id: Uuid) -> Result<ProductInfo, ProductError> {
+        self.products()
+            .await?
+            .into_iter()
+            .find(|product| product.product_id == product_id)
+            .ok_or(ProductError::Unknown(product_id))
+    }
+}
+
+/// Returns `config` with each strategy checked against, and completed from,
+/// the exchange's metadata for its `product_id`.
+pub async fn resolve_product_config(config: &Config) -> Result<Config, ProductError> {
+    let client = ProductClient::new(reqwest::Client::new(), config.rest_url.clone());
+    let products = client.products().await?;
+
+    let mut resolved = config.clone();
+    for strategy in &mut resolved.strategies {
+        let product = products
+            .iter()
+            .find(|product| product.product_id == strategy.product_id)
+            .ok_or(ProductError::Unknown(strategy.product_id))?;
+        product.apply_to(strategy)?;
+        log_product(product);
+    }
+
+    Ok(resolved)
+}
+
+fn log_product(product: &ProductInfo) {
+    tracing::info!(
+        target: targets::RUNTIME_EXEC,
+        product_id = %product.product_id,
+        ticker = %product.ticker,
+        onchain_id = product.onchain_id,
+        tick_size_raw = product.tick_size_raw,
+        lot_size_raw = product.lot_size_raw,
+        min_quantity_raw = product.min_quantity_raw,
+        max_leverage = product.max_leverage,
+        maker_fee_raw = product.maker_fee_raw,
+        "product metadata loaded"
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use super::{ProductError, ProductInfo};
+    use crate::settings::Config;
+
+    fn product() -> ProductInfo {
+        ProductInfo {
+            product_id: Uuid::new_v4(),
+            ticker: "BTCUSD".to_string(),
+            onchain_id: 3,
+            tick_size_raw: 1_000_000_000,
+            lot_size_raw: 100_000,
+            min_quantity_raw: 1_000_000,
+            max_leverage: 20,
+            maker_fee_raw: 100_000,
+            taker_fee_raw: 300_000,
+        }
+    }
+
+    #[test]
+    fn unset_fields_are_filled_from_the_exchange() {
+        let mut strategy = Config::testnet("11".repeat(32)).strategies.remove(0);
+        strategy.onchain_product_id = 0;
+        strategy.tick_size_raw = 0;
+
+        product().apply_to(&mut strategy).unwrap();
+
+        assert_eq!(strategy.onchain_product_id, 3);
+        assert_eq!(strategy.tick_size_raw, 1_000_000_000);
+        assert_eq!(strategy.lot_size_raw, 100_000);
+        assert_eq!(strategy.min_qty_raw, 1_000_000);
+        assert_eq!(strategy.fee_rate_raw, 100_000);
+    }
+
+    #[test]
+    fn disagreeing_fields_are_rejected() {
+        let mut strategy = Config::testnet("11".repeat(32)).strategies.remove(0);
+        strategy.onchain_product_id = 4;
+        assert!(matches!(
+            product().apply_to(&mut strategy),
+            Err(ProductError::Mismatch {
+                field: "onchain_product_id",
+                ..
+            })
+        ));
+
+        strategy.onchain_product_id = 3;
+        strategy.tick_size_raw = 1;
+        assert!(matches!(
+            product().apply_to(&mut strategy),
+            Err(ProductError::Mismatch {
+                field: "tick_size_raw",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn quantity_must_respect_lot_and_minimum() {
+        let mut strategy = Config::testnet("11".repeat(32)).strategies.remove(0);
+        strategy.tick_size_raw = 0;
+
+        strategy.qty_raw = 1_100_000;
+        product().apply_to(&mut strategy).unwrap();
+
+        strategy.qty_raw = 1_150_000;
+        assert!(matches!(
+            product().apply_to(&mut strategy),
+            Err(ProductError::OffLot { .. })
+        ));
+
+        strategy.qty_raw = 500_000;
+        assert!(matches!(
+            product().apply_to(&mut strategy),
+            Err(ProductError::BelowMinimum { .. })
+        ));
+    }
+}
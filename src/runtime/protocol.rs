@@ -0,0 +1,333 @@
+//! Minimal Engine.IO v4 / Socket.IO v5 codec for the text-only frames the
+//! Ethereal stream uses. Binary packets are rejected.
+
+use std::time::Duration;
+
+use serde::Serialize;
+
+pub const STREAM_NAMESPACE: &str = "/v1/stream";
+pub const PONG_FRAME: &str = "3";
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum ProtocolError {
+    #[error("empty frame")]
+    EmptyFrame,
+    #[error("unknown {layer} packet type `{kind}`")]
+    UnknownPacketType { layer: &'static str, kind: char },
+    #[error("malformed {packet} packet: {reason}")]
+    Malformed {
+        packet: &'static str,
+        reason: String,
+    },
+    #[error("binary socket.io packets are not supported")]
+    BinaryUnsupported,
+    #[error("expected {expected}, got `{frame}`")]
+    UnexpectedPacket {
+        expected: &'static str,
+        frame: String,
+    },
+    #[error("namespace `{namespace}` rejected connection: {message}")]
+    ConnectRejected { namespace: String, message: String },
+    #[error("timed out waiting for {0}")]
+    HandshakeTimeout(&'static str),
+}
+
+/// Session parameters announced by the server in the Engine.IO open packet.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenPacket {
+    pub sid: String,
+    pub ping_interval: u64,
+    pub ping_timeout: u64,
+    #[serde(default)]
+    pub max_payload: Option<u64>,
+}
+
+impl OpenPacket {
+    /// How long the server may stay silent before the session is considered dead.
+    pub fn heartbeat_timeout(&self) -> Duration {
+        Duration::from_millis(self.ping_interval.saturating_add(self.ping_timeout))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum EnginePacket {
+    Open(OpenPacket),
+    Close,
+    Ping,
+    Pong,
+    Message(SocketPacket),
+    Upgrade,
+    Noop,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SocketPacket {
+    Connect {
+        namespace: String,
+    },
+    Disconnect {
+        namespace: String,
+    },
+    Event {
+        namespace: String,
+        event: String,
+        payload: serde_json::Value,
+    },
+    Ack {
+        namespace: String,
+        ack_id: u64,
+    },
+    ConnectError {
+        namespace: String,
+        message: String,
+    },
+}
+
+pub fn decode_frame(frame: &str) -> Result<EnginePacket, ProtocolError> {
+    let mut chars = frame.chars();
+    let kind = chars.next().ok_or(ProtocolError::EmptyFrame)?;
+    let body = chars.as_str();
+
+    match kind {
+        '0' => serde_json::from_str(body)
+            .map(EnginePacket::Open)
+            .map_err(|error| ProtocolError::Malformed {
+                packet: "open",
+                reason: error.to_string(),
+            }),
+        '1' => Ok(EnginePacket::Close),
+        '2' => Ok(EnginePacket::Ping),
+        '3' => Ok(EnginePacket::Pong),
+        '4' => decode_socket_packet(body).map(EnginePacket::Message),
+        '5' => Ok(EnginePacket::Upgrade),
+        '6' => Ok(EnginePacket::Noop),
+        kind => Err(ProtocolError::UnknownPacketType {
+            layer: "engine.io",
+            kind,
+        }),
+    }
+}
+
+fn decode_socket_packet(body: &str) -> Result<SocketPacket, ProtocolError> {
+    let mut chars = body.chars();
+    let kind = chars.next().ok_or(ProtocolError::Malformed {
+        packet: "message",
+        reason: "missing socket.io packet type".to_string(),
+    })?;
+    let rest = chars.as_str();
+
+    if matches!(kind, '5' | '6') {
+        return Err(ProtocolError::BinaryUnsupported);
+    }
+
+    let (namespace, rest) = match rest.strip_prefix('/') {
+        Some(_) => match rest.split_once(',') {
+            Some((namespace, rest)) => (namespace.to_string(), rest),
+            None => (rest.to_string(), ""),
+        },
+        None => ("/".to_string(), rest),
+    };
+
+    let ack_len = rest.bytes().take_while(u8::is_ascii_digit).count();
+    let (ack_id, data) = rest.split_at(ack_len);
+    let ack_id = ack_id.parse::<u64>().ok();
+
+    match kind {
+        '0' => Ok(SocketPacket::Connect { namespace }),
+        '1' => Ok(SocketPacket::Disconnect { namespace }),
+        '2' => {
+            let (event, payload) = decode_event_data(data)?;
+            Ok(SocketPacket::Event {
+                namespace,
+                event,
+                payload,
+            })
+        }
+        '3' => Ok(SocketPacket::Ack {
+            namespace,
+            ack_id: ack_id.ok_or(ProtocolError::Malformed {
+                packet: "ack",
+                reason: "missing ack id".to_string(),
+            })?,
+        }),
+        '4' => Ok(SocketPacket::ConnectError {
+            namespace,
+            message: decode_connect_error(data),
+        }),
+        kind => Err(ProtocolError::UnknownPacketType {
+            layer: "socket.io",
+            kind,
+        }),
+    }
+}
+
+fn decode_event_data(data: &str) -> Result<(String, serde_json::Value), ProtocolError> {
+    let malformed = |reason: String| ProtocolError::Malformed {
+        packet: "event",
+        reason,
+    };
+
+    let items: Vec<serde_json::Value> =
+        serde_json::from_str(data).map_err(|error| malformed(error.to_string()))?;
+    let mut items = items.into_iter();
+
+    let Some(serde_json::Value::String(event)) = items.next() else {
+        return Err(malformed("missing event name".to_string()));
+    };
+
+    Ok((event, items.next().unwrap_or(serde_json::Value::Null)))
+}
+
+fn decode_connect_error(data: &str) -> String {
+    match serde_json::from_str::<serde_json::Value>(data) {
+        Ok(serde_json::Value::Object(object)) => object
+            .get("message")
+            .and_then(|message| message.as_str())
+            .map(str::to_string)
+            .unwrap_or_else(|| serde_json::Value::Object(object).to_string()),
+        Ok(serde_json::Value::String(message)) => message,
+        Ok(other) => other.to_string(),
+        Err(_) => data.to_string(),
+    }
+}
+
+pub fn encode_connect(namespace: &str) -> String {
+    format!("40{namespace},")
+}
+
+pub fn encode_disconnect(namespace: &str) -> String {
+    format!("41{namespace},")
+}
+
+pub fn encode_event<T: Serialize>(namespace: &str, event: &str, payload: &T) -> String {
+    let data = serde_json::to_string(&(event, payload)).expect("event payload serializes");
+    format!("42{namespace},{data}")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{
+        EnginePacket, OpenPacket, ProtocolError, STREAM_NAMESPACE, SocketPacket, decode_frame,
+        encode_connect, encode_disconnect, encode_event,
+    };
+
+    #[test]
+    fn decodes_open_packet() {
+        let frame = r#"0{"sid":"lv_VI97HAXpY6yYWAAAC","upgrades":[],"pingInterval":25000,"pingTimeout":20000,"maxPayload":1000000}"#;
+
+        let EnginePacket::Open(open) = decode_frame(frame).unwrap() else {
+            panic!("expected open packet");
+        };
+        assert_eq!(open, OpenPacket {
+            sid: "lv_VI97HAXpY6yYWAAAC".to_string(),
+            ping_interval: 25_000,
+            ping_timeout: 20_000,
+            max_payload: Some(1_000_000),
+        });
+        assert_eq!(open.heartbeat_timeout(), Duration::from_secs(45));
+    }
+
+    #[test]
+    fn rejects_open_packet_without_ping_interval() {
+        assert!(matches!(
+            decode_frame(r#"0{"sid":"abc","pingTimeout":20000}"#),
+            Err(ProtocolError::Malformed { packet: "open", .. })
+        ));
+    }
+
+    #[test]
+    fn decodes_heartbeat_and_close_packets() {
+        assert_eq!(decode_frame("2"), Ok(EnginePacket::Ping));
+        assert_eq!(decode_frame("3"), Ok(EnginePacket::Pong));
+        assert_eq!(decode_frame("1"), Ok(EnginePacket::Close));
+        assert_eq!(decode_frame(""), Err(ProtocolError::EmptyFrame));
+    }
+
+    #[test]
+    fn decodes_namespace_connect_ack() {
+        assert_eq!(
+            decode_frame(r#"40/v1/stream,{"sid":"wZX3oN0bSVIhsaknAAAI"}"#),
+            Ok(EnginePacket::Message(SocketPacket::Connect {
+                namespace: STREAM_NAMESPACE.to_string(),
+            }))
+        );
+    }
+
+    #[test]
+    fn decodes_connect_error_message() {
+        assert_eq!(
+            decode_frame(r#"44/v1/stream,{"message":"Not authorized"}"#),
+            Ok(EnginePacket::Message(SocketPacket::ConnectError {
+                namespace: STREAM_NAMESPACE.to_string(),
+                message: "Not authorized".to_string(),
+            }))
+        );
+        assert_eq!(
+            decode_frame(r#"44/v1/stream,"Invalid namespace""#),
+            Ok(EnginePacket::Message(SocketPacket::ConnectError {
+                namespace: STREAM_NAMESPACE.to_string(),
+                message: "Invalid namespace".to_string(),
+            }))
+        );
+    }
+
+    #[test]
+    fn decodes_disconnect_for_namespace_and_root() {
+        assert_eq!(
+            decode_frame("41/v1/stream,"),
+            Ok(EnginePacket::Message(SocketPacket::Disconnect {
+                namespace: STREAM_NAMESPACE.to_string(),
+            }))
+        );
+        assert_eq!(
+            decode_frame("41"),
+            Ok(EnginePacket::Message(SocketPacket::Disconnect {
+                namespace: "/".to_string(),
+            }))
+        );
+    }
+
+    #[test]
+    fn decodes_event_with_namespace_and_ack_id() {
+        assert_eq!(
+            decode_frame(r#"42/v1/stream,7["MarketPrice",{"k":"v"}]"#),
+            Ok(EnginePacket::Message(SocketPacket::Event {
+                namespace: STREAM_NAMESPACE.to_string(),
+                event: "MarketPrice".to_string(),
+                payload: serde_json::json!({ "k": "v" }),
+            }))
+        );
+    }
+
+    #[test]
+    fn rejects_event_without_name_and_binary_packets() {
+        assert!(matches!(
+            decode_frame(r#"42/v1/stream,[1,2]"#),
+            Err(ProtocolError::Malformed {
+                packet: "event",
+                ..
+            })
+        ));
+        assert_eq!(
+            decode_frame(r#"451-/v1/stream,["x",{"_placeholder":true,"num":0}]"#),
+            Err(ProtocolError::BinaryUnsupported)
+        );
+    }
+
+    #[test]
+    fn encodes_connect_and_event_frames() {
+        assert_eq!(encode_connect(STREAM_NAMESPACE), "40/v1/stream,");
+        assert_eq!(encode_disconnect(STREAM_NAMESPACE), "41/v1/stream,");
+        assert_eq!(
+            encode_event(
+                STREAM_NAMESPACE,
+                "subscribe",
+                &serde_json::json!({ "a": 1 })
+            ),
+            r#"42/v1/stream,["subscribe",{"a":1}]"#
+        );
+    }
+}
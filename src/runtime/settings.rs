@@ -0,0 +1,63 @@
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct ReconnectConfig {
+    #[serde(default = "default_initial_backoff_ms")]
+    pub initial_backoff_ms: u64,
+    #[serde(default = "default_max_backoff_ms")]
+    pub max_backoff_ms: u64,
+    /// Gives up after this many consecutive failed attempts; retries forever when unset.
+    #[serde(default)]
+    pub max_attempts: Option<u32>,
+}
+
+impl Default for ReconnectConfig {
+    fn default() -> Self {
+        Self {
+            initial_backoff_ms: default_initial_backoff_ms(),
+            max_backoff_ms: default_max_backoff_ms(),
+            max_attempts: None,
+        }
+    }
+}
+
+fn default_initial_backoff_ms() -> u64 {
+    250
+}
+
+fn default_max_backoff_ms() -> u64 {
+    30_000
+}
+
+/// Raw websocket capture; every inbound text frame is appended to a rotating
+/// JSON-lines file under `directory`.
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct CaptureConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_capture_directory")]
+    pub directory: String,
+    /// A new file is started once the current one grows past this size.
+    #[serde(default = "default_max_file_bytes")]
+    pub max_file_bytes: u64,
+    /// Oldest files are deleted beyond this count; keeps everything when unset.
+    #[serde(default)]
+    pub max_files: Option<usize>,
+}
+
+impl Default for CaptureConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            directory: default_capture_directory(),
+            max_file_bytes: default_max_file_bytes(),
+            max_files: None,
+        }
+    }
+}
+
+fn default_capture_directory() -> String {
+    "captures".to_string()
+}
+
+fn default_max_file_bytes() -> u64 {
+    64 * 1024 * 1024
+}
@@ -0,0 +1,96 @@
+use tokio::sync::mpsc;
+
+use super::{WsStream, WsTransport};
+use crate::error::EtherealRuntimeError;
+
+/// In-process transport: every `connect` hands the server half of a fresh
+/// frame channel pair to the matching [`ChannelListener`].
+pub struct ChannelTransport {
+    sessions: mpsc::UnboundedSender<ChannelPeer>,
+}
+
+impl ChannelTransport {
+    pub fn pair() -> (Self, ChannelListener) {
+        let (sessions, incoming) = mpsc::unbounded_channel();
+        (Self { sessions }, ChannelListener { incoming })
+    }
+}
+
+/// Server side of a [`ChannelTransport`]; yields one peer per client connect.
+pub struct ChannelListener {
+    incoming: mpsc::UnboundedReceiver<ChannelPeer>,
+}
+
+impl ChannelListener {
+    pub async fn accept(&mut self) -> Option<ChannelPeer> {
+        self.incoming.recv().await
+    }
+}
+
+/// Server end of one session. Dropping it looks like a lost connection to the client.
+pub struct ChannelPeer {
+    to_client: mpsc::UnboundedSender<String>,
+    from_client: mpsc::UnboundedReceiver<String>,
+}
+
+impl ChannelPeer {
+    /// Returns `false` once the client side is gone.
+    pub fn send(&self, frame: impl Into<String>) -> bool {
+        self.to_client.send(frame.into()).is_ok()
+    }
+
+    /// Next frame written by the client, `None` after it closed the session.
+    pub async fn recv(&mut self) -> Option<String> {
+        self.from_client.recv().await
+    }
+}
+
+pub(crate) struct ChannelStream {
+    to_server: Option<mpsc::UnboundedSender<String>>,
+    from_server: mpsc::UnboundedReceiver<String>,
+}
+
+fn closed() -> EtherealRuntimeError {
+    EtherealRuntimeError::WS("channel transport closed".to_string())
+}
+
+impl WsTransport for ChannelTransport {
+    type Stream = ChannelStream;
+
+    async fn connect(&self) -> Result<Self::Stream, EtherealRuntimeError> {
+        let (to_server, from_client) = mpsc::unbounded_channel();
+        let (to_client, from_server) = mpsc::unbounded_channel();
+
+        self.sessions
+            .send(ChannelPeer {
+                to_client,
+                from_client,
+            })
+            .map_err(|_| closed())?;
+
+        Ok(ChannelStream {
+            to_server: Some(to_server),
+            from_server,
+        })
+    }
+}
+
+impl WsStream for ChannelStream {
+    async fn send_text(&mut self, frame: String) -> Result<(), EtherealRuntimeError> {
+        self.to_server
+            .as_ref()
+            .ok_or_else(closed)?
+            .send(frame)
+            .map_err(|_| closed())
+    }
+
+    async fn next_text(&mut self) -> Option<Result<String, EtherealRuntimeError>> {
+        self.from_server.recv().await.map(Ok)
+    }
+
+    async fn close(&mut self) -> Result<(), EtherealRuntimeError> {
+        self.to_server = None;
+        self.from_server.close();
+        Ok(())
+    }
+}
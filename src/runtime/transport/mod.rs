@@ -0,0 +1,70 @@
+mod channel;
+mod tungstenite;
+
+use channel::ChannelStream;
+pub use channel::{ChannelListener, ChannelPeer, ChannelTransport};
+use tungstenite::TungsteniteStream;
+pub use tungstenite::TungsteniteTransport;
+
+use crate::error::EtherealRuntimeError;
+
+/// Opens text-framed websocket sessions; the runtime layers Engine.IO on top.
+pub(crate) trait WsTransport {
+    type Stream: WsStream;
+
+    async fn connect(&self) -> Result<Self::Stream, EtherealRuntimeError>;
+}
+
+pub(crate) trait WsStream {
+    async fn send_text(&mut self, frame: String) -> Result<(), EtherealRuntimeError>;
+
+    /// Next inbound text frame, `None` once the peer is gone. Must be cancel
+    /// safe: the session loop races it against commands and the heartbeat.
+    async fn next_text(&mut self) -> Option<Result<String, EtherealRuntimeError>>;
+
+    async fn close(&mut self) -> Result<(), EtherealRuntimeError>;
+}
+
+pub enum WsTransportRuntime {
+    Tungstenite(TungsteniteTransport),
+    Channel(ChannelTransport),
+}
+
+impl WsTransportRuntime {
+    pub(crate) async fn connect(&self) -> Result<WsStreamRuntime, EtherealRuntimeError> {
+        match self {
+            Self::Tungstenite(transport) => {
+                transport.connect().await.map(WsStreamRuntime::Tungstenite)
+            }
+            Self::Channel(transport) => transport.connect().await.map(WsStreamRuntime::Channel),
+        }
+    }
+}
+
+pub(crate) enum WsStreamRuntime {
+    Tungstenite(TungsteniteStream),
+    Channel(ChannelStream),
+}
+
+impl WsStreamRuntime {
+    pub(crate) async fn send_text(&mut self, frame: String) -> Result<(), EtherealRuntimeError> {
+        match self {
+            Self::Tungstenite(stream) => stream.send_text(frame).await,
+            Self::Channel(stream) => stream.send_text(frame).await,
+        }
+    }
+
+    pub(crate) async fn next_text(&mut self) -> Option<Result<String, EtherealRuntimeError>> {
+        match self {
+            Self::Tungstenite(stream) => stream.next_text().await,
+            Self::Channel(stream) => stream.next_text().await,
+        }
+    }
+
+    pub(crate) async fn close(&mut self) -> Result<(), EtherealRuntimeError> {
+        match self {
+            Self::Tungstenite(stream) => stream.close().await,
+            Self::Channel(stream) => stream.close().await,
+        }
+    }
+}
@@ -0,0 +1,72 @@
+use futures_util::{SinkExt, StreamExt};
+use tokio_tungstenite::tungstenite::Message;
+
+use super::{WsStream, WsTransport};
+use crate::error::EtherealRuntimeError;
+use crate::logging::targets;
+
+type WsStreamType =
+    tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>;
+
+/// Connects to the exchange's Socket.IO endpoint over tungstenite.
+pub struct TungsteniteTransport {
+    ws_url: url::Url,
+}
+
+impl TungsteniteTransport {
+    pub fn new(ws_url: url::Url) -> Self {
+        Self { ws_url }
+    }
+}
+
+pub(crate) struct TungsteniteStream {
+    // Boxed to keep `WsStreamRuntime` (and everything holding it) small.
+    inner: Box<WsStreamType>,
+}
+
+impl WsTransport for TungsteniteTransport {
+    type Stream = TungsteniteStream;
+
+    async fn connect(&self) -> Result<Self::Stream, EtherealRuntimeError> {
+        tracing::info!(
+            target: targets::RUNTIME_WS,
+            ws_url = %self.ws_url,
+            "connecting websocket"
+        );
+
+        let url = self.ws_url.join("socket.io/?EIO=4&transport=websocket")?;
+
+        let (inner, response) = tokio_tungstenite::connect_async(url.to_string()).await?;
+
+        if response.status() != reqwest::StatusCode::SWITCHING_PROTOCOLS {
+            return Err(EtherealRuntimeError::WS(format!(
+                "unexpected status: {}",
+                response.status(),
+            )));
+        }
+
+        Ok(TungsteniteStream {
+            inner: Box::new(inner),
+        })
+    }
+}
+
+impl WsStream for TungsteniteStream {
+    async fn send_text(&mut self, frame: String) -> Result<(), EtherealRuntimeError> {
+        Ok(self.inner.send(Message::Text(frame.into())).await?)
+    }
+
+    async fn next_text(&mut self) -> Option<Result<String, EtherealRuntimeError>> {
+        loop {
+            match self.inner.next().await? {
+                Ok(Message::Text(text)) => return Some(Ok(text.to_string())),
+                Ok(_) => continue,
+                Err(error) => return Some(Err(error.into())),
+            }
+        }
+    }
+
+    async fn close(&mut self) -> Result<(), EtherealRuntimeError> {
+        Ok(SinkExt::close(&mut self.inner).await?)
+    }
+}
@@ -1,7 +1,8 @@
+use serde::Deserialize;
 use url::Url;
 use uuid::Uuid;
 
-use crate::{logging, signer, trading};
+use crate::{logging, risk, runtime, signer, trading};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -11,7 +12,7 @@ pub enum ExecutionMode {
     Paper,
 }
 
-#[derive(serde::Deserialize)]
+#[derive(Clone, serde::Deserialize)]
 pub struct Config {
     pub rest_url: Url,
     pub ws_url: Url,
@@ -21,8 +22,17 @@ pub struct Config {
     pub execution_mode: ExecutionMode,
     #[serde(default)]
     pub logging: logging::LoggingConfig,
+    #[serde(default)]
+    pub reconnect: runtime::ReconnectConfig,
+    #[serde(default)]
+    pub capture: runtime::CaptureConfig,
 
-    pub strategy: trading::settings::StrategyConfig,
+    /// One entry per product, all traded through the same runtime and signer.
+    /// A single `[strategy]` table is accepted as a one-product list.
+    #[serde(alias = "strategy", deserialize_with = "deserialize_one_or_many")]
+    pub strategies: Vec<trading::settings::StrategyConfig>,
+    #[serde(default)]
+    pub risk: risk::RiskConfig,
     pub signer_config: signer::Config,
 }
 
@@ -62,15 +72,42 @@ impl Config {
             exchange: "1F0327A80e43FEF1Cd872DC5d38dCe4A165c0643".parse().unwrap(),
             execution_mode: ExecutionMode::Live,
             logging: logging::LoggingConfig::default(),
-            strategy: trading::settings::StrategyConfig {
+            reconnect: runtime::ReconnectConfig::default(),
+            capture: runtime::CaptureConfig::default(),
+            strategies: vec![trading::settings::StrategyConfig {
+                kind: trading::settings::StrategyKind::default(),
                 subaccount: Uuid::parse_str("48119502-2465-45c5-970e-27a28a4e0e3c").unwrap(),
                 product_id: Uuid::nil(),
                 onchain_product_id: 0,
                 qty_raw: 100_000_000,
                 post_only: true,
                 time_in_force: trading::settings::TimeInForce::default(),
-                tick_size_raw: 1,
+                tick_size_raw: 0,
+                lot_size_raw: 0,
+                min_qty_raw: 0,
                 min_spread_ticks: 1,
+                placement: trading::settings::QuotePlacement::default(),
+                offset_ticks: 0,
+                requote_min_ticks: 1,
+                min_quote_lifetime_ms: 0,
+                quote_mode: trading::settings::QuoteMode::default(),
+                inventory: trading::settings::InventoryConfig::default(),
+                oracle: trading::settings::OracleConfig::default(),
+                adaptive_spread: trading::settings::AdaptiveSpreadConfig::default(),
+                ladder: trading::settings::LadderConfig::default(),
+                shutdown_timeout_ms: 5_000,
+                fee_rate_raw: 0,
+                startup_orders: trading::settings::StartupOrderPolicy::default(),
+                halt: trading::settings::HaltConfig::default(),
+                stale_orders: trading::settings::StaleOrderConfig::default(),
+                market_data_timeout_ms: 10_000,
+            }],
+            risk: risk::RiskConfig {
+                max_order_qty_raw: 1_000_000_000,
+                max_notional_raw: 100_000_000_000_000,
+                max_open_orders: 4,
+                max_position_raw: 1_000_000_000,
+                price_band_bps: 200,
             },
             signer_config: signer::Config {
                 subaccount: hex::decode(
@@ -82,3 +119,134 @@ impl Config {
         }
     }
 }
+
+fn deserialize_one_or_many<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: serde::Deserialize<'de>,
+{
+    #[derive(serde::Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany<T> {
+        Many(Vec<T>),
+        One(T),
+    }
+
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::Many(items) => items,
+        OneOrMany::One(item) => vec![item],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Config;
+
+    const BASE: &str = r#"
+rest_url = "https://api.etherealtest.net"
+ws_url = "wss://ws.etherealtest.net"
+chain_id = 13374202
+exchange = "0x1F0327A80e43FEF1Cd872DC5d38dCe4A165c0643"
+
+[signer_config]
+private_key = "1111111111111111111111111111111111111111111111111111111111111111"
+subaccount = "7072696d61727900000000000000000000000000000000000000000000000000"
+"#;
+
+    const RISK: &str = r#"
+[risk]
+max_order_qty_raw = 1000000000
+max_notional_raw = 100000000000000
+max_position_raw = 1000000000
+"#;
+
+    fn parse(strategies: &str) -> Config {
+        load(&format!("{BASE}\n{RISK}\n{strategies}"))
+    }
+
+    fn load(toml: &str) -> Config {
+        config::Config::builder()
+            .add_source(config::File::from_str(toml, config::FileFormat::Toml))
+            .build()
+            .unwrap()
+            .try_deserialize()
+            .unwrap()
+    }
+
+    #[test]
+    fn risk_section_is_optional() {
+        let config = load(&format!(
+            "{BASE}\n{}",
+            r#"
+[strategy]
+subaccount = "48119502-2465-45c5-970e-27a28a4e0e3c"
+product_id = "dce327cc-4fbb-4d5d-9ede-1c1fca7ef4ba"
+qty_raw = 100000000
+"#
+        ));
+
+        assert_eq!(config.risk.max_position_raw, u128::MAX);
+        assert_eq!(config.risk.max_open_orders, 4);
+    }
+
+    #[test]
+    fn single_strategy_table_is_a_one_product_list() {
+        let config = parse(
+            r#"
+[strategy]
+subaccount = "48119502-2465-45c5-970e-27a28a4e0e3c"
+product_id = "dce327cc-4fbb-4d5d-9ede-1c1fca7ef4ba"
+qty_raw = 100000000
+"#,
+        );
+
+        assert_eq!(config.strategies.len(), 1);
+        assert_eq!(config.strategies[0].qty_raw, 100_000_000);
+    }
+
+    #[test]
+    fn strategies_array_configures_several_products() {
+        let config = parse(
+            r#"
+[[strategies]]
+subaccount = "48119502-2465-45c5-970e-27a28a4e0e3c"
+product_id = "dce327cc-4fbb-4d5d-9ede-1c1fca7ef4ba"
+qty_raw = 100000000
+
+[[strategies]]
+subaccount = "48119502-2465-45c5-970e-27a28a4e0e3c"
+product_id = "bc7d5575-3711-4532-a000-312bfacfb767"
+qty_raw = 2000000000
+"#,
+        );
+
+        assert_eq!(config.strategies.len(), 2);
+        assert_eq!(config.strategies[1].qty_raw, 2_000_000_000);
+    }
+
+    #[test]
+    fn ladder_size_profile_is_selected_by_tag() {
+        let config = parse(
+            r#"
+[strategy]
+subaccount = "48119502-2465-45c5-970e-27a28a4e0e3c"
+product_id = "dce327cc-4fbb-4d5d-9ede-1c1fca7ef4ba"
+qty_raw = 100000000
+
+[strategy.ladder]
+levels = 3
+sizes = { profile = "custom", multipliers = [1.0, 0.5, 0.25] }
+"#,
+        );
+
+        let ladder = &config.strategies[0].ladder;
+        assert_eq!(ladder.levels, 3);
+        assert_eq!(ladder.spacing_ticks, 1);
+        assert_eq!(
+            ladder.sizes,
+            crate::trading::settings::SizeProfile::Custom {
+                multipliers: vec![1.0, 0.5, 0.25],
+            }
+        );
+    }
+}
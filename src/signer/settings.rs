@@ -1,4 +1,4 @@
-#[derive(serde::Deserialize)]
+#[derive(Clone, serde::Deserialize)]
 pub struct Config {
     #[serde(with = "hex::serde")]
     pub private_key: Vec<u8>,
@@ -7,7 +7,10 @@ use crate::{EtherealRuntime, RuntimeEvent};
 #[tokio::test]
 async fn measure_post_only_latency_with_ws() {
     let private_key = std::env::var("PRIVATE_KEY").expect("PRIVATE_KEY required");
-    let config = Config::testnet(private_key);
+    let mut config = Config::testnet(private_key);
+    config.strategies[0].onchain_product_id = 1;
+    // Orders rest far from the market on purpose and no prices are subscribed.
+    config.risk.price_band_bps = 0;
     let (bot, mut events) = EtherealRuntime::new(&config).await.unwrap();
 
     bot.subscribe_order_updates("48119502-2465-45c5-970e-27a28a4e0e3c".parse().unwrap())
@@ -30,10 +33,10 @@ async fn measure_post_only_latency_with_ws() {
 
         let update = tokio::time::timeout(Duration::from_secs(10), async {
             while let Some(event) = events.recv().await {
-                if let RuntimeEvent::OrderUpdate(update) = event {
-                    if update.client_order_id == client_order_id {
-                        return update;
-                    }
+                if let RuntimeEvent::OrderUpdate(update) = event
+                    && update.client_order_id == client_order_id
+                {
+                    return update;
                 }
             }
 
@@ -0,0 +1,709 @@
+use std::time::Duration;
+
+use alloy_sol_types::Eip712Domain;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+use crate::executor::{ExecutorError, LiveExecutor, OrderExecutor, PaperExchange, PaperExecutor};
+use crate::mock::{Fault, MockExchange, MockPosition, MockProduct, MockRoute};
+use crate::models::common::{OrderStatus, TimeInForce};
+use crate::models::contracts::TradeOrder;
+use crate::models::dto::{
+    CancelOrderData, CancelOrderRequest, MarketPriceData, OrderRequest, OrderUpdateData, Timestamp,
+    TradeOrderData,
+};
+use crate::models::util::decimal_to_raw;
+use crate::runtime::make_domain;
+use crate::settings::Config;
+use crate::signer::Signer;
+use crate::trading::execution::execute_intents;
+use crate::trading::position::Position;
+use crate::trading::run_strategy_loop;
+use crate::trading::state::{OrderState, Side, StrategyState, TrackedOrder};
+use crate::trading::strategy::{OrderIntent, Strategy, StrategyContext};
+use crate::{
+    ChannelListener, ChannelPeer, ChannelTransport, EtherealRuntime, EtherealRuntimeError,
+    ProductClient, ProductError, RiskRejection, RuntimeEvent, WsTransportRuntime,
+    resolve_product_config,
+};
+
+const ONCHAIN_ID: u32 = 1;
+const PRICE_RAW: u128 = 100_000_000_000;
+const QTY_RAW: u128 = 100_000;
+
+struct Harness {
+    mock: MockExchange,
+    product_id: Uuid,
+    signer: Signer,
+    domain: Eip712Domain,
+}
+
+impl Harness {
+    async fn start() -> Self {
+        let config = Config::testnet("11".repeat(32));
+        let mock = MockExchange::start(config.chain_id, config.exchange)
+            .await
+            .unwrap();
+        let product_id = Uuid::new_v4();
+        mock.add_product(MockProduct::new(product_id, ONCHAIN_ID));
+
+        Self {
+            mock,
+            product_id,
+            signer: Signer::new(&config.signer_config),
+            domain: make_domain(config.chain_id, config.exchange),
+        }
+    }
+
+    /// Live settings for one strategy on the mock's product; the price band
+    /// is off, as the mock publishes no oracle price.
+    fn config(&self) -> Config {
+        let mut config = Config::testnet("11".repeat(32));
+        config.rest_url = self.mock.url().clone();
+        config.strategies[0].product_id = self.product_id;
+        config.strategies[0].onchain_product_id = ONCHAIN_ID;
+        config.risk.price_band_bps = 0;
+        config
+    }
+
+    /// A live runtime against the mock; the websocket peer and listener
+    /// keep its session up for as long as they are held.
+    async fn runtime(
+        &self,
+    ) -> (
+        EtherealRuntime,
+        mpsc::UnboundedReceiver<RuntimeEvent>,
+        ChannelPeer,
+        ChannelListener,
+    ) {
+        super::ws_transport::connect(&self.config()).await
+    }
+
+    fn live(&self) -> LiveExecutor {
+        LiveExecutor::new(reqwest::Client::new(), self.mock.url().clone())
+    }
+
+    fn order(&self, price_raw: u128, domain: &Eip712Domain) -> OrderRequest {
+        let ts = Timestamp::now();
+        let order = TradeOrder {
+            sender: *self.signer.accound_address(),
+            subaccount: *self.signer.subaccount(),
+            quantity: QTY_RAW,
+            price: price_raw,
+            reduceOnly: false,
+            side: 0,
+            engineType: 0,
+            productId: ONCHAIN_ID,
+            nonce: ts.nonce,
+            signedAt: ts.signed_at,
+        };
+        let signature = self.signer.sign_trade_order(&order, domain);
+
+        OrderRequest {
+            data: TradeOrderData::from_trade_order(order, true, TimeInForce::Gtd),
+            signature: format!("0x{}", hex::encode(signature.as_bytes())),
+        }
+    }
+
+    fn cancel(&self, client_order_ids: Vec<Uuid>) -> CancelOrderRequest {
+        let (signature, order) = self
+            .signer
+            .sign_cancel_order(Timestamp::now().nonce, &self.domain);
+
+        CancelOrderRequest {
+            data: CancelOrderData::from_cancel_order(order, vec![], client_order_ids),
+            signature: format!("0x{}", hex::encode(signature.as_bytes())),
+        }
+    }
+}
+
+/// Buys on every tick and reports which of its callbacks ran.
+struct BuyOnEveryTick(mpsc::UnboundedSender<&'static str>);
+
+impl Strategy for BuyOnEveryTick {
+    fn on_market_tick(
+        &mut self,
+        _ctx: &StrategyContext<'_>,
+        _tick: &MarketPriceData,
+    ) -> Vec<OrderIntent> {
+        let _ = self.0.send("tick");
+        vec![OrderIntent::Place {
+            side: Side::Buy,
+            level: 0,
+            price_raw: PRICE_RAW,
+            qty_raw: QTY_RAW,
+        }]
+    }
+
+    fn on_order_update(
+        &mut self,
+        _ctx: &StrategyContext<'_>,
+        _update: &OrderUpdateData,
+    ) -> Vec<OrderIntent> {
+        let _ = self.0.send("update");
+        Vec::new()
+    }
+}
+
+fn rejected_status<T: std::fmt::Debug>(result: Result<T, ExecutorError>) -> (u16, String) {
+    match result {
+        Err(ExecutorError::Rejected { status, payload }) => (status, payload),
+        other => panic!("expected rejection, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn live_submit_and_cancel_round_trip() {
+    let harness = Harness::start().await;
+    let executor = harness.live();
+    let request = harness.order(PRICE_RAW, &harness.domain);
+    let client_order_id = request.data.client_order_id;
+
+    let created = executor.submit_order(&request).await.unwrap();
+    assert_eq!(created.result, "Ok");
+    assert_eq!(created.client_order_id, Some(client_order_id));
+    assert_eq!(
+        harness.mock.order(client_order_id).unwrap().status,
+        OrderStatus::New
+    );
+
+    let cancel = executor
+        .cancel_order(&harness.cancel(vec![client_order_id]))
+        .await
+        .unwrap();
+    assert_eq!(cancel.data[0].result, "Ok");
+    assert_eq!(
+        harness.mock.order(client_order_id).unwrap().status,
+        OrderStatus::Canceled
+    );
+
+    let again = executor
+        .cancel_order(&harness.cancel(vec![client_order_id]))
+        .await
+        .unwrap();
+    assert_eq!(again.data[0].result, "AlreadyCanceled");
+}
+
+#[tokio::test]
+async fn live_submit_rejects_signature_for_another_domain() {
+    let harness = Harness::start().await;
+    let foreign_domain = make_domain(1, alloy::primitives::Address::ZERO);
+    let request = harness.order(PRICE_RAW, &foreign_domain);
+
+    let (status, payload) = rejected_status(harness.live().submit_order(&request).await);
+
+    assert_eq!(status, 400);
+    assert!(payload.contains("Invalid signature"), "{payload}");
+    assert!(harness.mock.orders().is_empty());
+}
+
+#[tokio::test]
+async fn live_submit_rejects_off_tick_price_and_duplicates() {
+    let harness = Harness::start().await;
+    let executor = harness.live();
+
+    let off_tick = harness.order(PRICE_RAW + 1, &harness.domain);
+    let (status, payload) = rejected_status(executor.submit_order(&off_tick).await);
+    assert_eq!(status, 400);
+    assert!(payload.contains("tickSize"), "{payload}");
+
+    let request = harness.order(PRICE_RAW, &harness.domain);
+    executor.submit_order(&request).await.unwrap();
+    let (status, _) = rejected_status(executor.submit_order(&request).await);
+    assert_eq!(status, 400);
+}
+
+#[tokio::test]
+async fn live_executor_surfaces_injected_status_faults() {
+    let harness = Harness::start().await;
+    let executor = harness.live();
+    harness
+        .mock
+        .inject(MockRoute::SubmitOrder, Fault::Status(429));
+    harness
+        .mock
+        .inject(MockRoute::SubmitOrder, Fault::Status(503));
+    harness
+        .mock
+        .inject(MockRoute::CancelOrder, Fault::Status(500));
+
+    let request = harness.order(PRICE_RAW, &harness.domain);
+    assert!(matches!(
+        executor.submit_order(&request).await,
+        Err(ExecutorError::RateLimited)
+    ));
+    assert_eq!(
+        rejected_status(executor.submit_order(&request).await).0,
+        503
+    );
+    executor.submit_order(&request).await.unwrap();
+
+    let cancel = harness.cancel(vec![request.data.client_order_id]);
+    assert_eq!(rejected_status(executor.cancel_order(&cancel).await).0, 500);
+}
+
+#[tokio::test]
+async fn live_open_orders_follow_pagination_and_skip_closed_orders() {
+    let harness = Harness::start().await;
+    let executor = harness.live();
+    harness.mock.set_page_limit(1);
+
+    let mut client_order_ids = Vec::new();
+    for offset in 0..3 {
+        let request = harness.order(PRICE_RAW + offset * 1_000_000_000, &harness.domain);
+        client_order_ids.push(request.data.client_order_id);
+        executor.submit_order(&request).await.unwrap();
+    }
+    harness
+        .mock
+        .set_order_status(client_order_ids[1], OrderStatus::Filled);
+
+    let open = executor
+        .open_orders(Uuid::new_v4(), harness.product_id)
+        .await
+        .unwrap();
+
+    let mut listed: Vec<_> = open
+        .iter()
+        .filter_map(|order| order.client_order_id)
+        .collect();
+    listed.sort();
+    let mut expected = vec![client_order_ids[0], client_order_ids[2]];
+    expected.sort();
+    assert_eq!(listed, expected);
+    assert!(open.iter().all(|order| order.status == OrderStatus::New));
+    assert!(
+        executor
+            .open_orders(Uuid::new_v4(), Uuid::new_v4())
+            .await
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[tokio::test]
+async fn live_positions_follow_pagination() {
+    let harness = Harness::start().await;
+    let executor = harness.live();
+    harness.mock.set_page_limit(1);
+    let other_product = Uuid::new_v4();
+    harness.mock.set_position(MockPosition {
+        product_id: harness.product_id,
+        side: 0,
+        size: "0.5".to_string(),
+        cost: "50".to_string(),
+    });
+    harness.mock.set_position(MockPosition {
+        product_id: other_product,
+        side: 1,
+        size: "2".to_string(),
+        cost: "-3000".to_string(),
+    });
+
+    let positions = executor.positions(Uuid::new_v4()).await.unwrap();
+
+    assert_eq!(positions.len(), 2);
+    let long = Position::from_exchange(&positions[0]).unwrap();
+    assert_eq!(positions[0].product_id, harness.product_id);
+    assert_eq!(long.net_qty_raw, 500_000_000);
+    assert_eq!(long.avg_entry_price_raw, PRICE_RAW);
+    let short = Position::from_exchange(&positions[1]).unwrap();
+    assert_eq!(positions[1].product_id, other_product);
+    assert_eq!(short.net_qty_raw, -2_000_000_000);
+    assert_eq!(short.avg_entry_price_raw, 1_500_000_000_000);
+}
+
+#[tokio::test]
+async fn overdue_order_that_filled_is_recovered_as_an_update() {
+    let harness = Harness::start().await;
+    let (runtime, mut events, _peer, _listener) = harness.runtime().await;
+    let client_order_id = runtime
+        .place_order(PRICE_RAW, QTY_RAW, 0, ONCHAIN_ID, true, TimeInForce::Gtd)
+        .await
+        .unwrap();
+    harness
+        .mock
+        .set_order_status(client_order_id, OrderStatus::Filled);
+    let subaccount = harness.config().strategies[0].subaccount;
+
+    assert!(
+        runtime
+            .recover_order_update(subaccount, client_order_id)
+            .await
+            .unwrap()
+    );
+    assert!(
+        !runtime
+            .recover_order_update(subaccount, Uuid::new_v4())
+            .await
+            .unwrap()
+    );
+
+    let event = tokio::time::timeout(Duration::from_secs(2), events.recv())
+        .await
+        .unwrap();
+    let Some(RuntimeEvent::OrderUpdate(update)) = event else {
+        panic!("expected an order update, got {event:?}");
+    };
+    assert_eq!(update.client_order_id, client_order_id);
+    assert_eq!(update.status, OrderStatus::Filled);
+    assert_eq!(
+        update.filled.as_ref().and_then(decimal_to_raw),
+        Some(QTY_RAW)
+    );
+    assert_eq!(update.product_id, Some(harness.product_id));
+}
+
+#[tokio::test]
+async fn replacement_takes_the_slot_of_the_order_it_cancels() {
+    let harness = Harness::start().await;
+    let mut config = harness.config();
+    config.risk.max_open_orders = 2;
+    let (runtime, _events, _peer, _listener) = super::ws_transport::connect(&config).await;
+    let place =
+        |side| runtime.place_order(PRICE_RAW, QTY_RAW, side, ONCHAIN_ID, true, TimeInForce::Gtd);
+
+    let buy = place(0).await.unwrap();
+    place(1).await.unwrap();
+    assert!(matches!(
+        place(0).await,
+        Err(EtherealRuntimeError::Risk(RiskRejection::OpenOrders {
+            open: 2,
+            ..
+        }))
+    ));
+
+    let (cancelled, replaced) = tokio::join!(biased; runtime.cancel_order(buy), place(0));
+    cancelled.unwrap();
+    replaced.unwrap();
+}
+
+#[tokio::test]
+async fn slow_buy_does_not_hold_up_the_sell() {
+    let harness = Harness::start().await;
+    let (runtime, _events, _peer, _listener) = harness.runtime().await;
+    let config = harness.config().strategies.remove(0);
+    let mut state = StrategyState::default();
+    harness
+        .mock
+        .inject_on_side(0, Fault::Delay(Duration::from_millis(500)));
+    let place = |side| OrderIntent::Place {
+        side,
+        level: 0,
+        price_raw: PRICE_RAW,
+        qty_raw: QTY_RAW,
+    };
+
+    execute_intents(&runtime, &config, &mut state, vec![
+        place(Side::Buy),
+        place(Side::Sell),
+    ])
+    .await
+    .unwrap();
+
+    let orders = harness.mock.orders();
+    let created_at = |side| {
+        orders
+            .iter()
+            .find(|order| order.side == side)
+            .unwrap()
+            .created_at
+    };
+    assert!(created_at(1) + 400 <= created_at(0));
+    assert_eq!(state.orders.iter().count(), 2);
+}
+
+#[tokio::test]
+async fn failed_cancel_of_a_replaced_order_is_sent_again() {
+    let harness = Harness::start().await;
+    let (runtime, _events, _peer, _listener) = harness.runtime().await;
+    let config = harness.config().strategies.remove(0);
+    let mut state = StrategyState::default();
+
+    let track_resting = async |state: &mut StrategyState| {
+        let client_order_id = runtime
+            .place_order(PRICE_RAW, QTY_RAW, 0, ONCHAIN_ID, true, TimeInForce::Gtd)
+            .await
+            .unwrap();
+        state.orders.insert(TrackedOrder {
+            client_order_id,
+            side: Side::Buy,
+            level: 0,
+            price_raw: PRICE_RAW,
+            qty_raw: QTY_RAW,
+            state: OrderState::Open,
+            created_at_ms: 0,
+        });
+        client_order_id
+    };
+    let replace = |client_order_id| OrderIntent::Replace {
+        client_order_id,
+        side: Side::Buy,
+        level: 0,
+        price_raw: PRICE_RAW - 1_000_000_000,
+        qty_raw: QTY_RAW,
+    };
+
+    // One failure: the retry goes through and only the confirmation is awaited.
+    let retried = track_resting(&mut state).await;
+    harness
+        .mock
+        .inject(MockRoute::CancelOrder, Fault::Status(500));
+    let result = execute_intents(&runtime, &config, &mut state, vec![replace(retried)]).await;
+    assert!(result.is_err());
+    assert_eq!(
+        harness.mock.order(retried).unwrap().status,
+        OrderStatus::Canceled
+    );
+    assert_eq!(
+        state.orders.get(retried).unwrap().state,
+        OrderState::PendingCancel { filled_raw: 0 }
+    );
+
+    // Two failures: the order stays pending cancel, out of the ladder.
+    let stuck = track_resting(&mut state).await;
+    for _ in 0..2 {
+        harness
+            .mock
+            .inject(MockRoute::CancelOrder, Fault::Status(500));
+    }
+    let result = execute_intents(&runtime, &config, &mut state, vec![replace(stuck)]).await;
+    assert!(result.is_err());
+    assert_eq!(harness.mock.order(stuck).unwrap().status, OrderStatus::New);
+    assert_eq!(
+        state.orders.get(stuck).unwrap().state,
+        OrderState::PendingCancel { filled_raw: 0 }
+    );
+    assert_eq!(
+        state
+            .orders
+            .iter()
+            .filter(|order| order.state == OrderState::PendingNew)
+            .count(),
+        2
+    );
+}
+
+#[tokio::test]
+async fn ladder_wider_than_the_open_order_limit_is_refused() {
+    let harness = Harness::start().await;
+    let mut config = harness.config();
+    config.strategies[0].ladder.levels = 3;
+    config.risk.max_open_orders = 4;
+    let (transport, _listener) = ChannelTransport::pair();
+
+    let result =
+        EtherealRuntime::with_transport(&config, WsTransportRuntime::Channel(transport)).await;
+
+    assert!(matches!(
+        result,
+        Err(EtherealRuntimeError::InvalidStrategies(_))
+    ));
+}
+
+#[tokio::test]
+async fn slow_response_hits_client_timeout() {
+    let harness = Harness::start().await;
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_millis(50))
+        .build()
+        .unwrap();
+    let executor = LiveExecutor::new(client, harness.mock.url().clone());
+    harness.mock.inject(
+        MockRoute::SubmitOrder,
+        Fault::Delay(Duration::from_millis(500)),
+    );
+
+    let result = executor
+        .submit_order(&harness.order(PRICE_RAW, &harness.domain))
+        .await;
+
+    assert!(matches!(result, Err(ExecutorError::HttpError(error)) if error.is_timeout()));
+}
+
+#[tokio::test]
+async fn cancel_of_unknown_order_is_accepted_as_not_found() {
+    let harness = Harness::start().await;
+
+    let cancelled = harness
+        .live()
+        .cancel_order(&harness.cancel(vec![Uuid::new_v4()]))
+        .await
+        .unwrap();
+
+    assert_eq!(cancelled.data[0].result, "NotFound");
+}
+
+#[tokio::test]
+async fn paper_executor_validates_through_dry_run_only() {
+    let harness = Harness::start().await;
+    let (event_sender, mut events) = mpsc::unbounded_channel();
+    let executor = PaperExecutor::new(
+        reqwest::Client::new(),
+        harness.mock.url().clone(),
+        PaperExchange::new(event_sender),
+    );
+
+    let request = harness.order(PRICE_RAW, &harness.domain);
+    let created = executor.submit_order(&request).await.unwrap();
+
+    assert_eq!(created.result, "Ok");
+    assert_eq!(created.client_order_id, Some(request.data.client_order_id));
+    assert!(events.try_recv().is_ok());
+    assert!(harness.mock.orders().is_empty());
+
+    harness.mock.inject(MockRoute::DryRun, Fault::Status(502));
+    let request = harness.order(PRICE_RAW, &harness.domain);
+    assert_eq!(
+        rejected_status(executor.submit_order(&request).await).0,
+        502
+    );
+}
+
+#[tokio::test]
+async fn products_endpoint_lists_registered_products() {
+    let harness = Harness::start().await;
+
+    let page: serde_json::Value = reqwest::get(format!("{}v1/product", harness.mock.url()))
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+
+    assert_eq!(page["data"][0]["onchainId"], ONCHAIN_ID);
+    assert_eq!(page["data"][0]["tickSize"], "1");
+}
+
+#[tokio::test]
+async fn product_client_reads_typed_metadata() {
+    let harness = Harness::start().await;
+    let client = ProductClient::new(reqwest::Client::new(), harness.mock.url().clone());
+
+    let product = client.product(harness.product_id).await.unwrap();
+
+    assert_eq!(product.onchain_id, ONCHAIN_ID);
+    assert_eq!(product.tick_size_raw, 1_000_000_000);
+    assert_eq!(product.lot_size_raw, QTY_RAW);
+    assert_eq!(product.min_quantity_raw, QTY_RAW);
+    assert_eq!(product.max_leverage, 20);
+    assert!(matches!(
+        client.product(Uuid::new_v4()).await,
+        Err(ProductError::Unknown(_))
+    ));
+}
+
+#[tokio::test]
+async fn product_config_is_filled_or_refused() {
+    let harness = Harness::start().await;
+    let mut config = Config::testnet("11".repeat(32));
+    config.rest_url = harness.mock.url().clone();
+    config.strategies[0].product_id = harness.product_id;
+    config.strategies[0].onchain_product_id = 0;
+    config.strategies[0].tick_size_raw = 0;
+
+    let resolved = resolve_product_config(&config).await.unwrap();
+    assert_eq!(resolved.strategies[0].onchain_product_id, ONCHAIN_ID);
+    assert_eq!(resolved.strategies[0].tick_size_raw, 1_000_000_000);
+
+    config.strategies[0].tick_size_raw = 500_000_000;
+    let refused = crate::run_strategy(&config, std::future::pending()).await;
+    assert!(matches!(
+        refused,
+        Err(EtherealRuntimeError::Product(ProductError::Mismatch {
+            field: "tick_size_raw",
+            ..
+        }))
+    ));
+}
+
+#[tokio::test]
+async fn every_strategy_is_resolved_against_its_own_product() {
+    let harness = Harness::start().await;
+    let second_product_id = Uuid::new_v4();
+    harness
+        .mock
+        .add_product(MockProduct::new(second_product_id, ONCHAIN_ID + 1));
+
+    let mut config = Config::testnet("11".repeat(32));
+    config.rest_url = harness.mock.url().clone();
+    config.strategies[0].product_id = harness.product_id;
+    let mut second = config.strategies[0].clone();
+    second.product_id = second_product_id;
+    config.strategies.push(second);
+
+    let resolved = resolve_product_config(&config).await.unwrap();
+    assert_eq!(resolved.strategies[0].onchain_product_id, ONCHAIN_ID);
+    assert_eq!(resolved.strategies[1].onchain_product_id, ONCHAIN_ID + 1);
+
+    config.strategies[1].product_id = Uuid::new_v4();
+    assert!(matches!(
+        resolve_product_config(&config).await,
+        Err(ProductError::Unknown(_))
+    ));
+}
+
+#[tokio::test]
+async fn order_updates_between_rejections_do_not_reset_the_halt_count() {
+    let harness = Harness::start().await;
+    let (runtime, _events, _peer, _listener) = harness.runtime().await;
+    let mut config = harness.config().strategies.remove(0);
+    config.halt.max_consecutive_rejections = 2;
+    for _ in 0..3 {
+        harness
+            .mock
+            .inject(MockRoute::SubmitOrder, Fault::Status(503));
+    }
+
+    let (calls, mut called) = mpsc::unbounded_channel();
+    let (events, market_events) = mpsc::unbounded_channel();
+    let (_halt, halt_commands) = mpsc::unbounded_channel();
+    let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+    let strategy_loop = run_strategy_loop(
+        &runtime,
+        std::slice::from_ref(&config),
+        |_| BuyOnEveryTick(calls.clone()),
+        market_events,
+        halt_commands,
+        async {
+            let _ = stopped.await;
+        },
+    );
+
+    let tick = RuntimeEvent::MarketPrice(MarketPriceData {
+        product_id: harness.product_id,
+        best_bid_price: 99.into(),
+        best_ask_price: 101.into(),
+        oracle_price: 100.into(),
+        price24h_ago: 100.into(),
+    });
+    let update = RuntimeEvent::OrderUpdate(OrderUpdateData {
+        id: Uuid::new_v4(),
+        status: OrderStatus::Canceled,
+        created_at: 0,
+        updated_at: 0,
+        client_order_id: Uuid::new_v4(),
+        product_id: Some(harness.product_id),
+        subaccount_id: None,
+        side: Some(0),
+        price: None,
+        filled: None,
+    });
+    let script = async {
+        for event in [tick.clone(), update.clone(), tick.clone(), update, tick] {
+            // Lets the loop act on each event in a batch of its own.
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            events.send(event).unwrap();
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        stop.send(()).unwrap();
+    };
+    let (result, ()) = tokio::join!(strategy_loop, script);
+    result.unwrap();
+
+    let mut seen = Vec::new();
+    while let Ok(call) = called.try_recv() {
+        seen.push(call);
+    }
+    assert_eq!(seen, ["tick", "update", "tick", "update"]);
+}
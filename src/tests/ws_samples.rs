@@ -1,22 +1,12 @@
 use std::time::Duration;
 
 use futures_util::{SinkExt, StreamExt};
-use serde::Deserialize;
 use serde_json::Value;
 use tokio::time::timeout;
 use tokio_tungstenite::tungstenite::Message;
 use url::Url;
 
-#[derive(Debug, Deserialize)]
-struct ProductListEnvelope {
-    data: Vec<ProductEntry>,
-}
-
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct ProductEntry {
-    id: String,
-}
+use crate::ProductClient;
 
 #[tokio::test]
 #[ignore = "manual websocket sampling test; run explicitly when capturing WS payloads"]
@@ -134,15 +124,12 @@ async fn sample_market_price_ws_payloads() {
 }
 
 async fn first_product_id(rest_url: &str) -> String {
-    let url = format!("{rest_url}/v1/product?limit=1");
-    let response = reqwest::Client::new().get(url).send().await.unwrap();
-    let response = response.error_for_status().unwrap();
-    let payload: ProductListEnvelope = response.json().await.unwrap();
+    let client = ProductClient::new(reqwest::Client::new(), rest_url.parse().unwrap());
+    let products = client.products().await.unwrap();
 
-    payload
-        .data
+    products
         .first()
         .expect("no products returned by /v1/product")
-        .id
-        .clone()
+        .product_id
+        .to_string()
 }
@@ -0,0 +1,143 @@
+use tokio::sync::mpsc;
+use tokio::time::{Duration, timeout};
+use uuid::Uuid;
+
+use crate::settings::{Config, ExecutionMode};
+use crate::{
+    ChannelListener, ChannelPeer, ChannelTransport, EtherealRuntime, RuntimeEvent,
+    WsTransportRuntime,
+};
+
+const OPEN_FRAME: &str =
+    r#"0{"sid":"test","upgrades":[],"pingInterval":25000,"pingTimeout":20000}"#;
+const CONNECT_FRAME: &str = "40/v1/stream,";
+const CONNECT_ACK_FRAME: &str = r#"40/v1/stream,{"sid":"ns"}"#;
+const STEP_TIMEOUT: Duration = Duration::from_secs(2);
+
+fn config() -> Config {
+    let mut config = Config::testnet("11".repeat(32));
+    config.execution_mode = ExecutionMode::Paper;
+    config.reconnect.initial_backoff_ms = 1;
+    config.reconnect.max_backoff_ms = 5;
+    config
+}
+
+async fn recv(peer: &mut ChannelPeer) -> Option<String> {
+    timeout(STEP_TIMEOUT, peer.recv())
+        .await
+        .expect("timed out waiting for client frame")
+}
+
+async fn next_event(events: &mut mpsc::UnboundedReceiver<RuntimeEvent>) -> RuntimeEvent {
+    timeout(STEP_TIMEOUT, events.recv())
+        .await
+        .expect("timed out waiting for runtime event")
+        .expect("runtime event stream closed")
+}
+
+/// Plays the server side of the Engine.IO open + namespace connect exchange.
+async fn accept_session(listener: &mut ChannelListener) -> ChannelPeer {
+    let mut peer = timeout(STEP_TIMEOUT, listener.accept())
+        .await
+        .expect("timed out waiting for client connect")
+        .expect("transport dropped");
+
+    assert!(peer.send(OPEN_FRAME));
+    assert_eq!(recv(&mut peer).await.as_deref(), Some(CONNECT_FRAME));
+    assert!(peer.send(CONNECT_ACK_FRAME));
+    peer
+}
+
+pub(super) async fn connect(
+    config: &Config,
+) -> (
+    EtherealRuntime,
+    mpsc::UnboundedReceiver<RuntimeEvent>,
+    ChannelPeer,
+    ChannelListener,
+) {
+    let (transport, mut listener) = ChannelTransport::pair();
+    let (runtime, peer) = tokio::join!(
+        EtherealRuntime::with_transport(config, WsTransportRuntime::Channel(transport)),
+        accept_session(&mut listener),
+    );
+    let (runtime, events) = runtime.unwrap();
+    (runtime, events, peer, listener)
+}
+
+#[tokio::test]
+async fn subscriptions_are_written_to_the_transport() {
+    let config = config();
+    let (runtime, _events, mut peer, _listener) = connect(&config).await;
+    let product_id = Uuid::new_v4();
+
+    runtime.subscribe_market_price(product_id).await.unwrap();
+
+    assert_eq!(
+        recv(&mut peer).await.unwrap(),
+        format!(
+            r#"42/v1/stream,["subscribe",{{"type":"MarketPrice","productId":"{product_id}"}}]"#
+        )
+    );
+}
+
+#[tokio::test]
+async fn server_ping_is_answered_with_pong() {
+    let config = config();
+    let (_runtime, _events, mut peer, _listener) = connect(&config).await;
+
+    assert!(peer.send("2"));
+
+    assert_eq!(recv(&mut peer).await.as_deref(), Some("3"));
+}
+
+#[tokio::test]
+async fn stream_events_reach_the_runtime_channel() {
+    let config = config();
+    let (_runtime, mut events, peer, _listener) = connect(&config).await;
+    let product_id = config.strategies[0].product_id;
+
+    assert!(peer.send(format!(
+        r#"42/v1/stream,["MarketPrice",{{"productId":"{product_id}","bestBidPrice":"100","bestAskPrice":"101","oraclePrice":"100.5","price24hAgo":"99"}}]"#
+    )));
+
+    let RuntimeEvent::MarketPrice(tick) = next_event(&mut events).await else {
+        panic!("expected market price");
+    };
+    assert_eq!(tick.product_id, product_id);
+}
+
+#[tokio::test]
+async fn lost_session_reconnects_and_resubscribes() {
+    let config = config();
+    let (runtime, mut events, mut peer, mut listener) = connect(&config).await;
+    let product_id = Uuid::new_v4();
+
+    runtime.subscribe_market_price(product_id).await.unwrap();
+    let subscribe_frame = recv(&mut peer).await.unwrap();
+
+    drop(peer);
+    assert!(matches!(
+        next_event(&mut events).await,
+        RuntimeEvent::Disconnected
+    ));
+
+    let mut peer = accept_session(&mut listener).await;
+    assert_eq!(recv(&mut peer).await, Some(subscribe_frame));
+    assert!(matches!(
+        next_event(&mut events).await,
+        RuntimeEvent::Reconnected
+    ));
+}
+
+#[tokio::test]
+async fn shutdown_disconnects_namespace_and_closes_transport() {
+    let config = config();
+    let (runtime, _events, mut peer, _listener) = connect(&config).await;
+
+    let (shutdown, frame) = tokio::join!(runtime.shutdown(), recv(&mut peer));
+    shutdown.unwrap();
+
+    assert_eq!(frame.as_deref(), Some("41/v1/stream,"));
+    assert_eq!(recv(&mut peer).await, None);
+}
@@ -0,0 +1,484 @@
+//! Sends strategy intents to the exchange, buys and sells as independent lanes.
+
+use std::collections::VecDeque;
+
+use futures_util::StreamExt;
+use futures_util::stream::FuturesUnordered;
+use uuid::Uuid;
+
+use super::now_ms;
+use super::settings::StrategyConfig;
+use super::state::{OrderState, OrderTracker, Side, StrategyState, TrackedOrder};
+use super::strategy::OrderIntent;
+use crate::executor::ExecutorError;
+use crate::logging::targets;
+use crate::{EtherealRuntime, EtherealRuntimeError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct PlaceLeg {
+    side: Side,
+    level: u32,
+    price_raw: u128,
+    qty_raw: u128,
+}
+
+/// A cancel already marked on the tracked order, with the state to restore
+/// if it fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct CancelLeg {
+    client_order_id: Uuid,
+    side: Side,
+    previous: OrderState,
+}
+
+/// The requests one intent turns into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Step {
+    Place(PlaceLeg),
+    Cancel(CancelLeg),
+    /// Both legs are sent together.
+    Replace(CancelLeg, PlaceLeg),
+}
+
+struct StepResult {
+    cancel: Option<(CancelLeg, Result<(), EtherealRuntimeError>)>,
+    place: Option<(PlaceLeg, Result<Uuid, EtherealRuntimeError>)>,
+}
+
+/// Executes `intents` in order per side, both sides at once; see [`keep_error`] for the result.
+pub(crate) async fn execute_intents(
+    runtime: &EtherealRuntime,
+    config: &StrategyConfig,
+    state: &mut StrategyState,
+    intents: Vec<OrderIntent>,
+) -> Result<(), EtherealRuntimeError> {
+    let mut lanes = split_by_side(&state.orders, intents);
+    let mut in_flight = FuturesUnordered::new();
+    for (lane, intents) in lanes.iter_mut().enumerate() {
+        if let Some(step) = next_step(state, intents) {
+            in_flight.push(run_step(runtime, config, lane, step));
+        }
+    }
+
+    let mut kept_error = None;
+    while let Some((lane, result)) = in_flight.next().await {
+        let (applied, retry) = apply_step_result(state, result);
+        if let Err(error) = applied {
+            keep_error(&mut kept_error, error);
+            lanes[lane].clear();
+        }
+        if let Some(step) = retry
+            .map(Step::Cancel)
+            .or_else(|| next_step(state, &mut lanes[lane]))
+        {
+            in_flight.push(run_step(runtime, config, lane, step));
+        }
+    }
+
+    kept_error.map_or(Ok(()), Err)
+}
+
+/// Keeps a rate limit over a halt-counted rejection over anything else; logs the other.
+fn keep_error(kept: &mut Option<EtherealRuntimeError>, error: EtherealRuntimeError) {
+    let dropped = match kept {
+        Some(current) if halt_weight(&error) <= halt_weight(current) => error,
+        _ => match kept.replace(error) {
+            Some(replaced) => replaced,
+            None => return,
+        },
+    };
+    tracing::warn!(
+        target: targets::TRADING_DECISION,
+        error = %dropped,
+        "order request failed"
+    );
+}
+
+fn halt_weight(error: &EtherealRuntimeError) -> u8 {
+    match error {
+        EtherealRuntimeError::Executor(ExecutorError::RateLimited) => 2,
+        EtherealRuntimeError::Executor(error) if error.counts_toward_halt() => 1,
+        _ => 0,
+    }
+}
+
+/// Cancels a tracked order; `false` when it is untracked or already cancelling.
+pub(super) async fn cancel_tracked_order(
+    runtime: &EtherealRuntime,
+    state: &mut StrategyState,
+    client_order_id: Uuid,
+) -> Result<bool, EtherealRuntimeError> {
+    let Some(cancel) = begin_cancel(state, client_order_id) else {
+        return Ok(false);
+    };
+    let result = runtime.cancel_order(client_order_id).await;
+    finish_cancel(state, cancel, result, false)?;
+    Ok(true)
+}
+
+/// Queues each intent on the side it trades; cancels of untracked orders
+/// are dropped.
+fn split_by_side(orders: &OrderTracker, intents: Vec<OrderIntent>) -> [VecDeque<OrderIntent>; 2] {
+    let mut lanes = [VecDeque::new(), VecDeque::new()];
+    for intent in intents {
+        let side = match &intent {
+            OrderIntent::Place { side, .. } | OrderIntent::Replace { side, .. } => *side,
+            OrderIntent::Cancel { client_order_id } => {
+                let Some(order) = orders.get(*client_order_id) else {
+                    skip_cancel(*client_order_id);
+                    continue;
+                };
+                order.side
+            }
+        };
+        lanes[side as usize].push_back(intent);
+    }
+    lanes
+}
+
+/// Takes intents off `lane` until one needs a request, marking any order it
+/// cancels as pending cancel.
+fn next_step(state: &mut StrategyState, lane: &mut VecDeque<OrderIntent>) -> Option<Step> {
+    while let Some(intent) = lane.pop_front() {
+        let step = match intent {
+            OrderIntent::Place {
+                side,
+                level,
+                price_raw,
+                qty_raw,
+            } => Some(Step::Place(PlaceLeg {
+                side,
+                level,
+                price_raw,
+                qty_raw,
+            })),
+            OrderIntent::Cancel { client_order_id } => {
+                begin_cancel(state, client_order_id).map(Step::Cancel)
+            }
+            OrderIntent::Replace {
+                client_order_id,
+                side,
+                level,
+                price_raw,
+                qty_raw,
+            } => begin_cancel(state, client_order_id).map(|cancel| {
+                Step::Replace(cancel, PlaceLeg {
+                    side,
+                    level,
+                    price_raw,
+                    qty_raw,
+                })
+            }),
+        };
+        if step.is_some() {
+            return step;
+        }
+    }
+    None
+}
+
+async fn run_step(
+    runtime: &EtherealRuntime,
+    config: &StrategyConfig,
+    lane: usize,
+    step: Step,
+) -> (usize, StepResult) {
+    let result = match step {
+        Step::Place(place) => StepResult {
+            cancel: None,
+            place: Some((place, submit(runtime, config, place).await)),
+        },
+        Step::Cancel(cancel) => StepResult {
+            cancel: Some((cancel, runtime.cancel_order(cancel.client_order_id).await)),
+            place: None,
+        },
+        Step::Replace(cancel, place) => {
+            // Biased so the cancel frees the old order's open order slot
+            // before the replacement is risk checked.
+            let (cancelled, placed) = tokio::join!(
+                biased;
+                runtime.cancel_order(cancel.client_order_id),
+                submit(runtime, config, place),
+            );
+            StepResult {
+                cancel: Some((cancel, cancelled)),
+                place: Some((place, placed)),
+            }
+        }
+    };
+    (lane, result)
+}
+
+async fn submit(
+    runtime: &EtherealRuntime,
+    config: &StrategyConfig,
+    place: PlaceLeg,
+) -> Result<Uuid, EtherealRuntimeError> {
+    runtime
+        .place_order(
+            place.price_raw,
+            place.qty_raw,
+            place.side as u8,
+            config.onchain_product_id,
+            config.post_only,
+            config.time_in_force,
+        )
+        .await
+}
+
+/// Applies a step's results, returning its error and any replaced order's cancel to retry.
+fn apply_step_result(
+    state: &mut StrategyState,
+    result: StepResult,
+) -> (Result<(), EtherealRuntimeError>, Option<CancelLeg>) {
+    let mut replaced = false;
+    let mut place_error = Ok(());
+    if let Some((place, placed)) = result.place {
+        match placed {
+            Ok(client_order_id) => {
+                record_placed(state, place, client_order_id);
+                replaced = true;
+            }
+            Err(error) => place_error = Err(error),
+        }
+    }
+
+    let Some((cancel, cancelled)) = result.cancel else {
+        return (place_error, None);
+    };
+    match finish_cancel(state, cancel, cancelled, replaced) {
+        Err(error) if replaced => (Err(error), Some(retry_cancel(state, cancel))),
+        Err(error) => (Err(error), None),
+        Ok(()) => (place_error, None),
+    }
+}
+
+/// A second try at a replaced order's cancel. The order is already pending
+/// cancel, which is also what a failed retry leaves it as.
+fn retry_cancel(state: &StrategyState, cancel: CancelLeg) -> CancelLeg {
+    let previous = state
+        .orders
+        .get(cancel.client_order_id)
+        .map_or(cancel.previous, |order| order.state);
+    tracing::warn!(
+        target: targets::TRADING_DECISION,
+        client_order_id = %cancel.client_order_id,
+        "cancel of replaced order failed; retrying"
+    );
+    CancelLeg { previous, ..cancel }
+}
+
+fn record_placed(state: &mut StrategyState, place: PlaceLeg, client_order_id: Uuid) {
+    state.orders.insert(TrackedOrder {
+        client_order_id,
+        side: place.side,
+        level: place.level,
+        price_raw: place.price_raw,
+        qty_raw: place.qty_raw,
+        state: OrderState::PendingNew,
+        created_at_ms: now_ms(),
+    });
+
+    tracing::info!(
+        target: targets::TRADING_DECISION,
+        side = %place.side,
+        level = place.level,
+        %client_order_id,
+        price_raw = place.price_raw,
+        qty_raw = place.qty_raw,
+        "strategy action: place"
+    );
+}
+
+fn begin_cancel(state: &mut StrategyState, client_order_id: Uuid) -> Option<CancelLeg> {
+    let Some(order) = state
+        .orders
+        .get_mut(client_order_id)
+        .filter(|order| order.state.is_cancelable())
+    else {
+        skip_cancel(client_order_id);
+        return None;
+    };
+
+    let previous = order.state;
+    order.state = OrderState::PendingCancel {
+        filled_raw: previous.filled_raw(),
+    };
+    Some(CancelLeg {
+        client_order_id,
+        side: order.side,
+        previous,
+    })
+}
+
+/// Restores a failed cancel's order unless a replacement already took its level.
+fn finish_cancel(
+    state: &mut StrategyState,
+    cancel: CancelLeg,
+    result: Result<(), EtherealRuntimeError>,
+    replaced: bool,
+) -> Result<(), EtherealRuntimeError> {
+    if let Err(error) = result {
+        if !replaced && let Some(order) = state.orders.get_mut(cancel.client_order_id) {
+            order.state = cancel.previous;
+        }
+        return Err(error);
+    }
+
+    tracing::info!(
+        target: targets::TRADING_DECISION,
+        side = %cancel.side,
+        client_order_id = %cancel.client_order_id,
+        "strategy action: cancel"
+    );
+    Ok(())
+}
+
+fn skip_cancel(client_order_id: Uuid) {
+    tracing::debug!(
+        target: targets::TRADING_DECISION,
+        %client_order_id,
+        "cancel skipped; order not tracked or already cancelling"
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use uuid::Uuid;
+
+    use super::{CancelLeg, PlaceLeg, Step, keep_error, next_step, split_by_side};
+    use crate::EtherealRuntimeError;
+    use crate::executor::ExecutorError;
+    use crate::trading::state::{OrderState, Side, StrategyState, TrackedOrder};
+    use crate::trading::strategy::OrderIntent;
+
+    fn tracked(side: Side, state: OrderState) -> TrackedOrder {
+        TrackedOrder {
+            client_order_id: Uuid::new_v4(),
+            side,
+            level: 0,
+            price_raw: 100,
+            qty_raw: 10,
+            state,
+            created_at_ms: 0,
+        }
+    }
+
+    fn place(side: Side, price_raw: u128) -> OrderIntent {
+        OrderIntent::Place {
+            side,
+            level: 0,
+            price_raw,
+            qty_raw: 10,
+        }
+    }
+
+    #[test]
+    fn intents_are_queued_on_their_side_in_order() {
+        let mut state = StrategyState::default();
+        let sell = tracked(Side::Sell, OrderState::Open);
+        let sell_id = sell.client_order_id;
+        state.orders.insert(sell);
+
+        let [buys, sells] = split_by_side(&state.orders, vec![
+            OrderIntent::Cancel {
+                client_order_id: sell_id,
+            },
+            place(Side::Buy, 99),
+            OrderIntent::Cancel {
+                client_order_id: Uuid::new_v4(),
+            },
+            place(Side::Sell, 101),
+            place(Side::Buy, 98),
+        ]);
+
+        assert_eq!(buys, [place(Side::Buy, 99), place(Side::Buy, 98)]);
+        assert_eq!(sells, [
+            OrderIntent::Cancel {
+                client_order_id: sell_id,
+            },
+            place(Side::Sell, 101),
+        ]);
+    }
+
+    #[test]
+    fn replace_marks_the_old_order_and_skips_when_already_cancelling() {
+        let mut state = StrategyState::default();
+        let order = tracked(Side::Buy, OrderState::Open);
+        let client_order_id = order.client_order_id;
+        state.orders.insert(order);
+        let replace = OrderIntent::Replace {
+            client_order_id,
+            side: Side::Buy,
+            level: 0,
+            price_raw: 99,
+            qty_raw: 10,
+        };
+        let mut lane = VecDeque::from([replace.clone(), replace, place(Side::Buy, 98)]);
+
+        assert_eq!(
+            next_step(&mut state, &mut lane),
+            Some(Step::Replace(
+                CancelLeg {
+                    client_order_id,
+                    side: Side::Buy,
+                    previous: OrderState::Open,
+                },
+                PlaceLeg {
+                    side: Side::Buy,
+                    level: 0,
+                    price_raw: 99,
+                    qty_raw: 10,
+                },
+            ))
+        );
+        assert_eq!(
+            state.orders.get(client_order_id).unwrap().state,
+            OrderState::PendingCancel { filled_raw: 0 }
+        );
+        assert!(matches!(
+            next_step(&mut state, &mut lane),
+            Some(Step::Place(PlaceLeg { price_raw: 98, .. }))
+        ));
+        assert!(lane.is_empty());
+    }
+
+    #[test]
+    fn the_error_that_matters_to_the_halt_is_kept() {
+        let kept_after = |errors: Vec<ExecutorError>| {
+            let mut kept = None;
+            for error in errors {
+                keep_error(&mut kept, error.into());
+            }
+            kept
+        };
+
+        assert!(matches!(
+            kept_after(vec![
+                ExecutorError::PostOnlyWouldCross,
+                ExecutorError::InsufficientMargin
+            ]),
+            Some(EtherealRuntimeError::Executor(
+                ExecutorError::InsufficientMargin
+            ))
+        ));
+        assert!(matches!(
+            kept_after(vec![
+                ExecutorError::InsufficientMargin,
+                ExecutorError::RateLimited
+            ]),
+            Some(EtherealRuntimeError::Executor(ExecutorError::RateLimited))
+        ));
+        assert!(matches!(
+            kept_after(vec![
+                ExecutorError::NonceAlreadyUsed,
+                ExecutorError::InsufficientMargin
+            ]),
+            Some(EtherealRuntimeError::Executor(
+                ExecutorError::NonceAlreadyUsed
+            ))
+        ));
+    }
+}
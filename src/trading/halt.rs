@@ -0,0 +1,380 @@
+//! Trading halts: operator-triggered through a signal or a watched file, or
+//! automatic after repeated exchange rejections or a drawdown. A halt lasts
+//! until the operator resumes it, whatever started it. Rate limits only
+//! pause quoting for a short backoff.
+
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use tokio::sync::mpsc;
+
+use super::settings::HaltConfig;
+use crate::EtherealRuntimeError;
+use crate::executor::ExecutorError;
+use crate::logging::targets;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HaltCommand {
+    Halt,
+    Resume,
+}
+
+/// A halt command and the halt file it came from. Commands from signals
+/// carry no file and apply to every product.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OperatorCommand {
+    pub command: HaltCommand,
+    pub halt_file: Option<PathBuf>,
+}
+
+impl OperatorCommand {
+    /// Whether the command applies to a product halted by `config`.
+    pub fn applies_to(&self, config: &HaltConfig) -> bool {
+        self.halt_file.is_none() || self.halt_file == config.halt_file
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HaltReason {
+    Operator,
+    Rejections(u32),
+    Drawdown { drawdown_raw: u128 },
+}
+
+/// Tracks whether trading is halted and the signals that trigger an automatic
+/// halt, along with any rate limit backoff.
+#[derive(Debug)]
+pub(crate) struct HaltMonitor {
+    max_consecutive_rejections: u32,
+    max_drawdown_raw: u128,
+    rate_limit_backoff: Option<Duration>,
+    consecutive_rejections: u32,
+    peak_pnl_raw: i128,
+    reason: Option<HaltReason>,
+    backoff_until: Option<Instant>,
+}
+
+impl HaltMonitor {
+    pub fn new(config: &HaltConfig) -> Self {
+        Self {
+            max_consecutive_rejections: config.max_consecutive_rejections,
+            max_drawdown_raw: config.max_drawdown_raw,
+            rate_limit_backoff: (config.rate_limit_backoff_ms > 0)
+                .then(|| Duration::from_millis(config.rate_limit_backoff_ms)),
+            consecutive_rejections: 0,
+            peak_pnl_raw: 0,
+            reason: None,
+            backoff_until: None,
+        }
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.reason.is_some()
+    }
+
+    /// Whether a rate limit's backoff is still running at `now`.
+    pub fn is_backing_off(&self, now: Instant) -> bool {
+        self.backoff_until.is_some_and(|until| now < until)
+    }
+
+    /// Returns `true` when this starts a halt, `false` when already halted.
+    pub fn halt(&mut self, reason: HaltReason) -> bool {
+        if self.is_halted() {
+            return false;
+        }
+        self.reason = Some(reason);
+        true
+    }
+
+    /// Lifts the halt, restarting the rejection count and the drawdown
+    /// high-water mark from `total_pnl_raw`. Returns `false` when not halted.
+    pub fn resume(&mut self, total_pnl_raw: i128) -> bool {
+        if self.reason.take().is_none() {
+            return false;
+        }
+        self.consecutive_rejections = 0;
+        self.peak_pnl_raw = total_pnl_raw;
+        true
+    }
+
+    /// Counts exchange rejections across ticks; a tick that went through
+    /// resets the count and other failures leave it as is. A rate limit
+    /// starts the backoff from `now` instead of counting.
+    pub fn record_tick(
+        &mut self,
+        result: &Result<(), EtherealRuntimeError>,
+        now: Instant,
+    ) -> Option<HaltReason> {
+        match result {
+            Ok(()) => self.consecutive_rejections = 0,
+            Err(EtherealRuntimeError::Executor(ExecutorError::RateLimited)) => {
+                self.backoff_until = self.rate_limit_backoff.map(|backoff| now + backoff);
+            }
+            Err(EtherealRuntimeError::Executor(error)) if error.counts_toward_halt() => {
+                self.consecutive_rejections += 1;
+            }
+            Err(_) => {}
+        }
+
+        let limit = self.max_consecutive_rejections;
+        if limit > 0 && self.consecutive_rejections >= limit {
+            let reason = HaltReason::Rejections(self.consecutive_rejections);
+            return self.halt(reason).then_some(reason);
+        }
+        None
+    }
+
+    pub fn record_pnl(&mut self, total_pnl_raw: i128) -> Option<HaltReason> {
+        self.peak_pnl_raw = self.peak_pnl_raw.max(total_pnl_raw);
+        let drawdown_raw = (self.peak_pnl_raw - total_pnl_raw).unsigned_abs();
+
+        if self.max_drawdown_raw > 0 && drawdown_raw > self.max_drawdown_raw {
+            let reason = HaltReason::Drawdown { drawdown_raw };
+            return self.halt(reason).then_some(reason);
+        }
+        None
+    }
+}
+
+/// Operator halt and resume commands: `SIGUSR1` halts and `SIGUSR2` resumes
+/// every product on unix, and each configured `halt_file` halts the products
+/// configured with it while it exists.
+pub(crate) fn spawn_operator_controls<'a>(
+    configs: impl IntoIterator<Item = &'a HaltConfig>,
+) -> mpsc::UnboundedReceiver<OperatorCommand> {
+    let (sender, receiver) = mpsc::unbounded_channel();
+
+    #[cfg(unix)]
+    tokio::spawn(watch_signals(sender.clone()));
+
+    let mut watched = std::collections::HashSet::new();
+    for config in configs {
+        if let Some(path) = config.halt_file.clone()
+            && watched.insert(path.clone())
+        {
+            let interval = Duration::from_millis(config.halt_file_poll_ms.max(1));
+            tokio::spawn(watch_halt_file(path, interval, sender.clone()));
+        }
+    }
+
+    receiver
+}
+
+#[cfg(unix)]
+async fn watch_signals(sender: mpsc::UnboundedSender<OperatorCommand>) {
+    use tokio::signal::unix::{SignalKind, signal};
+
+    let (mut halt, mut resume) = match (
+        signal(SignalKind::user_defined1()),
+        signal(SignalKind::user_defined2()),
+    ) {
+        (Ok(halt), Ok(resume)) => (halt, resume),
+        (Err(error), _) | (_, Err(error)) => {
+            tracing::error!(
+                target: targets::TRADING_DECISION,
+                %error,
+                "failed to listen for halt signals"
+            );
+            return;
+        }
+    };
+
+    loop {
+        let command = tokio::select! {
+            Some(()) = halt.recv() => HaltCommand::Halt,
+            Some(()) = resume.recv() => HaltCommand::Resume,
+            else => return,
+        };
+        let command = OperatorCommand {
+            command,
+            halt_file: None,
+        };
+        if sender.send(command).is_err() {
+            return;
+        }
+    }
+}
+
+async fn watch_halt_file(
+    path: PathBuf,
+    interval: Duration,
+    sender: mpsc::UnboundedSender<OperatorCommand>,
+) {
+    let mut present = false;
+    let mut ticker = tokio::time::interval(interval);
+
+    loop {
+        ticker.tick().await;
+        let exists = path.exists();
+        if exists == present {
+            continue;
+        }
+        present = exists;
+
+        let command = if exists {
+            HaltCommand::Halt
+        } else {
+            HaltCommand::Resume
+        };
+        tracing::info!(
+            target: targets::TRADING_DECISION,
+            path = %path.display(),
+            ?command,
+            "halt file changed"
+        );
+        let command = OperatorCommand {
+            command,
+            halt_file: Some(path.clone()),
+        };
+        if sender.send(command).is_err() {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::{HaltCommand, HaltMonitor, HaltReason, spawn_operator_controls};
+    use crate::EtherealRuntimeError;
+    use crate::executor::ExecutorError;
+    use crate::trading::settings::HaltConfig;
+
+    fn rejected() -> Result<(), EtherealRuntimeError> {
+        Err(ExecutorError::Rejected {
+            status: 400,
+            payload: String::new(),
+        }
+        .into())
+    }
+
+    #[test]
+    fn consecutive_rejections_halt_until_resumed() {
+        let mut monitor = HaltMonitor::new(&HaltConfig {
+            max_consecutive_rejections: 2,
+            ..HaltConfig::default()
+        });
+        let now = Instant::now();
+
+        assert_eq!(monitor.record_tick(&rejected(), now), None);
+        assert_eq!(monitor.record_tick(&Ok(()), now), None);
+        assert_eq!(monitor.record_tick(&rejected(), now), None);
+        assert_eq!(
+            monitor.record_tick(
+                &Err(ExecutorError::SendRequestError(String::new()).into()),
+                now
+            ),
+            None
+        );
+        assert_eq!(
+            monitor.record_tick(&Err(ExecutorError::InsufficientMargin.into()), now),
+            Some(HaltReason::Rejections(2))
+        );
+        assert!(monitor.is_halted());
+        assert_eq!(monitor.record_tick(&rejected(), now), None);
+        assert!(monitor.is_halted());
+
+        assert!(monitor.resume(0));
+        assert!(!monitor.is_halted());
+        assert_eq!(monitor.record_tick(&rejected(), now), None);
+    }
+
+    #[test]
+    fn crossing_and_rate_limits_do_not_count_toward_a_halt() {
+        let mut monitor = HaltMonitor::new(&HaltConfig {
+            max_consecutive_rejections: 2,
+            rate_limit_backoff_ms: 1_000,
+            ..HaltConfig::default()
+        });
+        let now = Instant::now();
+
+        assert_eq!(monitor.record_tick(&rejected(), now), None);
+        for error in [
+            ExecutorError::PostOnlyWouldCross,
+            ExecutorError::RateLimited,
+        ] {
+            assert_eq!(monitor.record_tick(&Err(error.into()), now), None);
+        }
+        assert!(!monitor.is_halted());
+        assert!(monitor.record_tick(&rejected(), now).is_some());
+    }
+
+    #[test]
+    fn rate_limit_backs_off_for_the_configured_time() {
+        let mut monitor = HaltMonitor::new(&HaltConfig {
+            rate_limit_backoff_ms: 1_000,
+            ..HaltConfig::default()
+        });
+        let now = Instant::now();
+        assert!(!monitor.is_backing_off(now));
+
+        monitor.record_tick(&Err(ExecutorError::RateLimited.into()), now);
+        assert!(monitor.is_backing_off(now + Duration::from_millis(999)));
+        assert!(!monitor.is_backing_off(now + Duration::from_millis(1_000)));
+
+        let mut disabled = HaltMonitor::new(&HaltConfig {
+            rate_limit_backoff_ms: 0,
+            ..HaltConfig::default()
+        });
+        disabled.record_tick(&Err(ExecutorError::RateLimited.into()), now);
+        assert!(!disabled.is_backing_off(now));
+    }
+
+    #[test]
+    fn drawdown_is_measured_from_the_high_water_mark() {
+        let mut monitor = HaltMonitor::new(&HaltConfig {
+            max_drawdown_raw: 50,
+            ..HaltConfig::default()
+        });
+
+        assert_eq!(monitor.record_pnl(100), None);
+        assert_eq!(monitor.record_pnl(60), None);
+        assert_eq!(
+            monitor.record_pnl(40),
+            Some(HaltReason::Drawdown { drawdown_raw: 60 })
+        );
+
+        assert!(monitor.resume(40));
+        assert_eq!(monitor.record_pnl(0), None);
+        assert!(monitor.record_pnl(-20).is_some());
+    }
+
+    #[test]
+    fn operator_halt_does_not_stack() {
+        let mut monitor = HaltMonitor::new(&HaltConfig::default());
+
+        assert!(!monitor.resume(0));
+        assert!(monitor.halt(HaltReason::Operator));
+        assert!(!monitor.halt(HaltReason::Operator));
+        assert!(monitor.resume(0));
+    }
+
+    #[tokio::test]
+    async fn halt_file_applies_to_its_own_product() {
+        let dir = std::env::temp_dir().join(format!("halt-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir(&dir).unwrap();
+        let config = |name: &str| HaltConfig {
+            halt_file: Some(dir.join(name)),
+            halt_file_poll_ms: 5,
+            ..HaltConfig::default()
+        };
+        let (first, second) = (config("first"), config("second"));
+        let mut commands = spawn_operator_controls([&first, &second]);
+
+        std::fs::write(dir.join("first"), "").unwrap();
+        let halt = commands.recv().await.unwrap();
+        assert_eq!(halt.command, HaltCommand::Halt);
+        assert!(halt.applies_to(&first) && !halt.applies_to(&second));
+
+        std::fs::write(dir.join("second"), "").unwrap();
+        assert!(commands.recv().await.unwrap().applies_to(&second));
+
+        std::fs::remove_file(dir.join("first")).unwrap();
+        let resume = commands.recv().await.unwrap();
+        assert_eq!(resume.command, HaltCommand::Resume);
+        assert!(resume.applies_to(&first) && !resume.applies_to(&second));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
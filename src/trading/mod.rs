@@ -1,218 +1,722 @@
+pub(crate) mod execution;
+pub(crate) mod halt;
 pub(crate) mod policy;
+pub(crate) mod position;
 pub(crate) mod settings;
+mod stale;
+mod startup;
 pub(crate) mod state;
+pub(crate) mod strategy;
+pub(crate) mod volatility;
+
+use std::collections::HashSet;
+use std::future::Future;
+use std::pin::Pin;
+use std::time::{Duration, Instant};
 
 use tokio::sync::mpsc;
+use uuid::Uuid;
 
-use self::policy::StrategyAction;
+use self::execution::{cancel_tracked_order, execute_intents};
+use self::halt::{HaltCommand, HaltMonitor, HaltReason, OperatorCommand};
+use self::position::{Fill, Position, PositionKey};
 use self::settings::StrategyConfig;
-use self::state::{Side, SideState, StrategyState};
+use self::stale::{MarketDataWatch, StaleOrderWatch, drop_unknown_order, resolve_stale_orders};
+use self::state::StrategyState;
+use self::strategy::{OrderIntent, Strategy};
 use crate::logging::targets;
 use crate::models::common::OrderStatus;
 use crate::models::dto::{MarketPriceData, OrderUpdateData};
+use crate::models::util::{decimal_to_raw, format_order_decimal, format_signed_order_decimal};
 use crate::{EtherealRuntime, EtherealRuntimeError, RuntimeEvent};
 
-pub async fn run_strategy_loop(
-    runtime: &EtherealRuntime,
-    config: &StrategyConfig,
-    mut market_events: mpsc::UnboundedReceiver<RuntimeEvent>,
-) -> Result<(), EtherealRuntimeError> {
-    runtime.subscribe_order_updates(config.subaccount).await?;
-    runtime.subscribe_market_price(config.product_id).await?;
-    let mut state = StrategyState::default();
-
-    while let Some(event) = market_events.recv().await {
-        let mut latest_market_tick = None;
-        match event {
-            RuntimeEvent::MarketPrice(tick) => {
-                if tick.product_id == config.product_id {
-                    latest_market_tick = Some(tick);
+/// One product's strategy with the state and halt monitor only it touches.
+struct ProductSlot<S> {
+    config: StrategyConfig,
+    strategy: S,
+    state: StrategyState,
+    halt: HaltMonitor,
+    timer: Option<tokio::time::Interval>,
+    stale_orders: StaleOrderWatch,
+    stale_timer: Option<tokio::time::Interval>,
+    market_data: MarketDataWatch,
+    /// Fires at `market_data`'s deadline; reset by every tick.
+    market_data_timer: Option<Pin<Box<tokio::time::Sleep>>>,
+    batch: EventBatch,
+}
+
+/// Which of a slot's timers fired.
+#[derive(Debug, Clone, Copy)]
+enum SlotTimer {
+    Strategy,
+    StaleOrders,
+    MarketData,
+}
+
+impl<S: Strategy> ProductSlot<S> {
+    fn new(config: StrategyConfig, strategy: S) -> Self {
+        let market_data = MarketDataWatch::new(config.market_data_timeout_ms, Instant::now());
+        Self {
+            market_data_timer: market_data.deadline().map(|deadline| {
+                Box::pin(tokio::time::sleep_until(tokio::time::Instant::from_std(
+                    deadline,
+                )))
+            }),
+            market_data,
+            timer: strategy.timer_interval().map(tokio::time::interval),
+            halt: HaltMonitor::new(&config.halt),
+            stale_orders: StaleOrderWatch::new(&config.stale_orders),
+            stale_timer: (config.stale_orders.check_interval_ms > 0).then(|| {
+                tokio::time::interval(Duration::from_millis(config.stale_orders.check_interval_ms))
+            }),
+            state: StrategyState::default(),
+            batch: EventBatch::default(),
+            config,
+            strategy,
+        }
+    }
+
+    fn total_pnl_raw(&self) -> i128 {
+        self.state
+            .positions
+            .position(PositionKey::for_strategy(&self.config))
+            .total_pnl_raw()
+    }
+
+    fn is_quoting_paused(&self) -> bool {
+        self.halt.is_halted()
+            || self.halt.is_backing_off(Instant::now())
+            || self.market_data.is_stale()
+    }
+
+    async fn on_timer(&mut self, runtime: &EtherealRuntime) {
+        if self.is_quoting_paused() {
+            return;
+        }
+        self.state.now_ms = now_ms();
+        let intents = self.strategy.on_timer(&self.state.context(&self.config));
+        if intents.is_empty() {
+            return;
+        }
+        let result = execute_intents(runtime, &self.config, &mut self.state, intents).await;
+        self.record_strategy_result(runtime, result).await;
+    }
+
+    fn on_market_tick_received(&mut self) {
+        let now = Instant::now();
+        if self.market_data.on_tick(now) {
+            tracing::info!(
+                target: targets::TRADING_DECISION,
+                product_id = %self.config.product_id,
+                "market data fresh again; quoting resumes"
+            );
+        }
+        if let (Some(timer), Some(deadline)) =
+            (&mut self.market_data_timer, self.market_data.deadline())
+        {
+            timer
+                .as_mut()
+                .reset(tokio::time::Instant::from_std(deadline));
+        }
+    }
+
+    /// Pulls both sides once no tick has arrived within `market_data_timeout_ms`.
+    async fn check_market_data(&mut self, runtime: &EtherealRuntime) {
+        if !self.market_data.expire(Instant::now()) {
+            return;
+        }
+
+        tracing::warn!(
+            target: targets::TRADING_DECISION,
+            product_id = %self.config.product_id,
+            timeout_ms = self.config.market_data_timeout_ms,
+            "market data stale; pulling quotes until ticks resume"
+        );
+        self.batch.latest_market_tick = None;
+        pull_quotes(runtime, &mut self.state).await;
+    }
+
+    /// Checks orders stuck waiting for an update against the exchange's
+    /// working orders. Fills found on working orders, and the last update of
+    /// orders it no longer works, are replayed as order updates; orders it has
+    /// no record of are dropped.
+    async fn check_stale_orders(&mut self, runtime: &EtherealRuntime) {
+        let overdue = self
+            .stale_orders
+            .overdue(&self.state.orders, std::time::Instant::now());
+        if overdue.is_empty() {
+            return;
+        }
+
+        let working = match runtime
+            .open_orders(self.config.subaccount, self.config.product_id)
+            .await
+        {
+            Ok(working) => working,
+            Err(error) => {
+                tracing::warn!(
+                    target: targets::TRADING_DECISION,
+                    product_id = %self.config.product_id,
+                    %error,
+                    "failed to check stale orders"
+                );
+                return;
+            }
+        };
+
+        let plan = resolve_stale_orders(&mut self.state, &overdue, &working);
+        for client_order_id in plan.missing {
+            match runtime
+                .recover_order_update(self.config.subaccount, client_order_id)
+                .await
+            {
+                Ok(true) => {}
+                Ok(false) => {
+                    drop_unknown_order(&mut self.state, client_order_id);
+                    runtime.forget_order(client_order_id);
                 }
+                Err(error) => tracing::warn!(
+                    target: targets::TRADING_DECISION,
+                    %client_order_id,
+                    %error,
+                    "failed to look up order missing from the working orders"
+                ),
             }
-            RuntimeEvent::OrderUpdate(update) => {
-                reconcile_order_update(&mut state, &update);
+        }
+        for order in plan.unbooked {
+            runtime.publish_order_update(order.to_update(self.config.subaccount));
+        }
+        for client_order_id in plan.recancel {
+            if let Err(error) =
+                cancel_tracked_order(runtime, &mut self.state, client_order_id).await
+            {
+                tracing::warn!(
+                    target: targets::TRADING_DECISION,
+                    %client_order_id,
+                    %error,
+                    "failed to retry cancel"
+                );
             }
         }
+    }
 
-        while let Ok(next_event) = market_events.try_recv() {
-            match next_event {
-                RuntimeEvent::MarketPrice(tick) => {
-                    if tick.product_id == config.product_id {
-                        latest_market_tick = Some(tick);
-                    }
-                }
-                RuntimeEvent::OrderUpdate(update) => {
-                    reconcile_order_update(&mut state, &update);
-                }
+    /// Acts on what [`absorb_event`] collected since the last iteration.
+    async fn process_batch(&mut self, runtime: &EtherealRuntime, pull: bool) {
+        let batch = std::mem::take(&mut self.batch);
+
+        if pull {
+            pull_quotes(runtime, &mut self.state).await;
+        }
+
+        if let Some(reason) = self.halt.record_pnl(self.total_pnl_raw()) {
+            begin_halt(runtime, &self.config, reason).await;
+        }
+        if self.is_quoting_paused() {
+            if !batch.intents.is_empty() {
+                tracing::debug!(
+                    target: targets::TRADING_DECISION,
+                    product_id = %self.config.product_id,
+                    dropped = batch.intents.len(),
+                    "trading halted, backing off or market data stale; dropping strategy intents"
+                );
             }
+            return;
+        }
+        // Nothing was sent, so there is no outcome for the halt monitor.
+        if batch.intents.is_empty() && batch.latest_market_tick.is_none() {
+            return;
         }
 
-        if let Some(tick) = latest_market_tick
-            && let Err(error) = handle_market_tick(runtime, config, &mut state, &tick).await
+        let mut result =
+            execute_intents(runtime, &self.config, &mut self.state, batch.intents).await;
+        if result.is_ok()
+            && let Some(tick) = batch.latest_market_tick
         {
+            result = handle_market_tick(
+                runtime,
+                &self.config,
+                &mut self.state,
+                &mut self.strategy,
+                &tick,
+            )
+            .await;
+        }
+        self.record_strategy_result(runtime, result).await;
+    }
+
+    async fn record_strategy_result(
+        &mut self,
+        runtime: &EtherealRuntime,
+        result: Result<(), EtherealRuntimeError>,
+    ) {
+        if let Err(error) = &result {
             tracing::warn!(
                 target: targets::TRADING_DECISION,
+                product_id = %self.config.product_id,
                 %error,
                 "strategy tick processing failed"
             );
         }
+        if let Some(reason) = self.halt.record_tick(&result, Instant::now()) {
+            begin_halt(runtime, &self.config, reason).await;
+        }
     }
 
-    Err(EtherealRuntimeError::WS(
-        "runtime event stream closed".to_string(),
-    ))
-}
-
-fn reconcile_order_update(state: &mut StrategyState, update: &OrderUpdateData) {
-    let buy_matched = reconcile_side_order_update(state.side_state_mut(Side::Buy), update);
-    let sell_matched = reconcile_side_order_update(state.side_state_mut(Side::Sell), update);
-    if buy_matched || sell_matched {
-        tracing::info!(
-            target: targets::TRADING_DECISION,
-            client_order_id = %update.client_order_id,
-            status = %update.status,
-            "strategy state reconciled from order update"
-        );
+    async fn apply_halt_command(&mut self, runtime: &EtherealRuntime, command: HaltCommand) {
+        match command {
+            HaltCommand::Halt => {
+                if self.halt.halt(HaltReason::Operator) {
+                    begin_halt(runtime, &self.config, HaltReason::Operator).await;
+                }
+            }
+            HaltCommand::Resume => {
+                if self.halt.resume(self.total_pnl_raw()) {
+                    tracing::warn!(
+                        target: targets::TRADING_DECISION,
+                        product_id = %self.config.product_id,
+                        "trading resumed by operator; quoting resumes on next tick"
+                    );
+                }
+            }
+        }
     }
 }
 
-fn reconcile_side_order_update(side_state: &mut SideState, update: &OrderUpdateData) -> bool {
-    if side_state.active_client_order_id != Some(update.client_order_id) {
-        return false;
+/// Runs one strategy per entry of `configs`, built by `make_strategy`, over
+/// the shared `runtime`. Halt signals apply to every product, a halt file to
+/// the products configured with it, and automatic halts only to the product
+/// that tripped them.
+pub async fn run_strategy_loop<S: Strategy>(
+    runtime: &EtherealRuntime,
+    configs: &[StrategyConfig],
+    mut make_strategy: impl FnMut(&StrategyConfig) -> S,
+    mut market_events: mpsc::UnboundedReceiver<RuntimeEvent>,
+    mut halt_commands: mpsc::UnboundedReceiver<OperatorCommand>,
+    shutdown: impl Future<Output = ()>,
+) -> Result<(), EtherealRuntimeError> {
+    let mut slots: Vec<ProductSlot<S>> = configs
+        .iter()
+        .map(|config| ProductSlot::new(config.clone(), make_strategy(config)))
+        .collect();
+
+    let subaccounts: HashSet<Uuid> = configs.iter().map(|config| config.subaccount).collect();
+    for subaccount in subaccounts {
+        runtime.subscribe_order_updates(subaccount).await?;
+        let positions = runtime.positions(subaccount).await?;
+        for slot in slots
+            .iter_mut()
+            .filter(|slot| slot.config.subaccount == subaccount)
+        {
+            startup::seed_position(&slot.config, &mut slot.state, &positions);
+        }
     }
+    for slot in &mut slots {
+        startup::reconcile_open_orders(runtime, &slot.config, &mut slot.state).await?;
+    }
+    for slot in &slots {
+        runtime
+            .subscribe_market_price(slot.config.product_id)
+            .await?;
+    }
+    tokio::pin!(shutdown);
+
+    loop {
+        let event = tokio::select! {
+            biased;
+            _ = &mut shutdown => break,
+            Some(command) = halt_commands.recv() => {
+                for slot in slots
+                    .iter_mut()
+                    .filter(|slot| command.applies_to(&slot.config.halt))
+                {
+                    slot.apply_halt_command(runtime, command.command).await;
+                }
+                continue;
+            }
+            (index, timer) = next_timer(&mut slots) => {
+                match timer {
+                    SlotTimer::Strategy => slots[index].on_timer(runtime).await,
+                    SlotTimer::StaleOrders => slots[index].check_stale_orders(runtime).await,
+                    SlotTimer::MarketData => slots[index].check_market_data(runtime).await,
+                }
+                continue;
+            }
+            event = market_events.recv() => match event {
+                Some(event) => event,
+                None => {
+                    return Err(EtherealRuntimeError::WS(
+                        "runtime event stream closed".to_string(),
+                    ));
+                }
+            },
+        };
 
-    side_state.inflight = false;
-    if is_terminal_order_status(update.status) {
-        side_state.active_client_order_id = None;
-        side_state.last_quoted_price_raw = None;
+        let mut pull = absorb_event(&mut slots, event);
+        while let Ok(next_event) = market_events.try_recv() {
+            pull |= absorb_event(&mut slots, next_event);
+        }
+
+        for slot in slots.iter_mut().filter(|slot| pull || slot.batch.routed) {
+            slot.process_batch(runtime, pull).await;
+        }
     }
 
-    true
+    wind_down(runtime, &mut slots, &mut market_events).await
 }
 
-fn is_terminal_order_status(status: OrderStatus) -> bool {
-    match status {
-        OrderStatus::Filled
-        | OrderStatus::Rejected
-        | OrderStatus::Canceled
-        | OrderStatus::Expired => true,
-        OrderStatus::New | OrderStatus::Pending | OrderStatus::FilledPartial => false,
+/// Resolves with the first slot timer that is due; never resolves when no
+/// slot has a timer.
+async fn next_timer<S>(slots: &mut [ProductSlot<S>]) -> (usize, SlotTimer) {
+    std::future::poll_fn(|cx| {
+        for (index, slot) in slots.iter_mut().enumerate() {
+            let timers = [
+                (SlotTimer::Strategy, &mut slot.timer),
+                (SlotTimer::StaleOrders, &mut slot.stale_timer),
+            ];
+            for (kind, timer) in timers {
+                if let Some(timer) = timer
+                    && timer.poll_tick(cx).is_ready()
+                {
+                    return std::task::Poll::Ready((index, kind));
+                }
+            }
+            if !slot.market_data.is_stale()
+                && let Some(timer) = &mut slot.market_data_timer
+                && timer.as_mut().poll(cx).is_ready()
+            {
+                return std::task::Poll::Ready((index, SlotTimer::MarketData));
+            }
+        }
+        std::task::Poll::Pending
+    })
+    .await
+}
+
+/// Cancels every working order on the product, including ones the strategy
+/// does not track. Tracked quotes clear as their terminal updates arrive.
+async fn begin_halt(runtime: &EtherealRuntime, config: &StrategyConfig, reason: HaltReason) {
+    tracing::error!(
+        target: targets::TRADING_DECISION,
+        product_id = %config.product_id,
+        ?reason,
+        "trading halted; cancelling all orders until the operator resumes"
+    );
+
+    let cancelled = async {
+        let orders = runtime
+            .open_orders(config.subaccount, config.product_id)
+            .await?;
+        runtime
+            .cancel_orders_by_id(orders.into_iter().map(|order| order.id).collect())
+            .await
+    };
+    if let Err(error) = cancelled.await {
+        tracing::error!(
+            target: targets::TRADING_DECISION,
+            product_id = %config.product_id,
+            %error,
+            "failed to cancel orders on halt"
+        );
     }
 }
 
-pub async fn handle_market_tick(
+/// Cancels every order the strategies still track and waits for each to
+/// reach a terminal status, up to the longest `shutdown_timeout_ms`. Market
+/// ticks are ignored.
+async fn wind_down<S>(
     runtime: &EtherealRuntime,
-    config: &StrategyConfig,
-    state: &mut StrategyState,
-    tick: &MarketPriceData,
+    slots: &mut [ProductSlot<S>],
+    market_events: &mut mpsc::UnboundedReceiver<RuntimeEvent>,
 ) -> Result<(), EtherealRuntimeError> {
-    if tick.product_id != config.product_id {
-        return Ok(());
+    let mut pending: HashSet<Uuid> = slots
+        .iter()
+        .flat_map(|slot| slot.state.orders.iter())
+        .map(|order| order.client_order_id)
+        .collect();
+
+    tracing::info!(
+        target: targets::TRADING_DECISION,
+        open_orders = pending.len(),
+        "shutdown requested; cancelling open orders"
+    );
+
+    for &client_order_id in &pending {
+        if let Err(error) = runtime.cancel_order(client_order_id).await {
+            tracing::warn!(
+                target: targets::TRADING_DECISION,
+                %client_order_id,
+                %error,
+                "shutdown cancel failed"
+            );
+        }
     }
 
-    state.last_market = Some(tick.clone());
-    let (buy_action, sell_action) = policy::decide_actions(config, state, tick);
+    let timeout_ms = slots
+        .iter()
+        .map(|slot| slot.config.shutdown_timeout_ms)
+        .max()
+        .unwrap_or_default();
+    let _ = tokio::time::timeout(Duration::from_millis(timeout_ms), async {
+        while !pending.is_empty() {
+            let Some(event) = market_events.recv().await else {
+                break;
+            };
 
-    execute_action(runtime, config, state, Side::Buy, buy_action).await?;
-    execute_action(runtime, config, state, Side::Sell, sell_action).await?;
+            if let RuntimeEvent::OrderUpdate(update) = event {
+                if let Some(slot) = route_order_update(slots, &update) {
+                    record_fill(&slot.config, &mut slot.state, &update);
+                    reconcile_order_update(&mut slot.state, &update);
+                }
+                if is_terminal_order_status(update.status) {
+                    pending.remove(&update.client_order_id);
+                }
+            }
+        }
+    })
+    .await;
 
-    Ok(())
-}
+    for slot in slots.iter() {
+        let key = PositionKey::for_strategy(&slot.config);
+        log_position("final position", key, &slot.state.positions.position(key));
+    }
 
-async fn execute_action(
-    runtime: &EtherealRuntime,
-    config: &StrategyConfig,
-    state: &mut StrategyState,
-    side: Side,
-    action: Option<StrategyAction>,
-) -> Result<(), EtherealRuntimeError> {
-    let Some(action) = action else {
-        tracing::debug!(
+    if pending.is_empty() {
+        tracing::info!(
             target: targets::TRADING_DECISION,
-            %side,
-            "strategy action: skip"
+            "shutdown cleanup complete"
         );
         return Ok(());
-    };
+    }
+
+    for client_order_id in &pending {
+        tracing::error!(
+            target: targets::TRADING_DECISION,
+            %client_order_id,
+            "order still open after shutdown timeout"
+        );
+    }
+
+    Err(EtherealRuntimeError::ShutdownIncomplete(pending.len()))
+}
+
+/// Everything drained from the event channel for one product in one loop iteration.
+#[derive(Default)]
+struct EventBatch {
+    /// Whether any event was routed to the product.
+    routed: bool,
+    latest_market_tick: Option<MarketPriceData>,
+    /// Returned by the strategy's order update and fill callbacks.
+    intents: Vec<OrderIntent>,
+}
 
-    match action {
-        StrategyAction::Place { price_raw, qty_raw } => {
-            place_side_order(runtime, config, state, side, price_raw, qty_raw).await
+/// The slot an order update belongs to: the one tracking its client order id,
+/// else the one trading its product. With a single product every update is
+/// its own, as updates may omit the product.
+fn route_order_update<'a, S>(
+    slots: &'a mut [ProductSlot<S>],
+    update: &OrderUpdateData,
+) -> Option<&'a mut ProductSlot<S>> {
+    let index = slots
+        .iter()
+        .position(|slot| slot.state.orders.get(update.client_order_id).is_some())
+        .or_else(|| {
+            slots
+                .iter()
+                .position(|slot| Some(slot.config.product_id) == update.product_id)
+        })
+        .or_else(|| (slots.len() == 1).then_some(0))?;
+    slots.get_mut(index)
+}
+
+/// Routes `event` to its product's slot; returns `true` when every product
+/// should pull its quotes.
+fn absorb_event<S: Strategy>(slots: &mut [ProductSlot<S>], event: RuntimeEvent) -> bool {
+    match event {
+        RuntimeEvent::MarketPrice(tick) => {
+            if let Some(slot) = slots
+                .iter_mut()
+                .find(|slot| slot.config.product_id == tick.product_id)
+            {
+                slot.batch.routed = true;
+                slot.on_market_tick_received();
+                if let Some(oracle_raw) = decimal_to_raw(&tick.oracle_price) {
+                    slot.state.positions.mark(tick.product_id, oracle_raw);
+                }
+                slot.batch.latest_market_tick = Some(tick);
+            }
+            false
         }
-        StrategyAction::Cancel { client_order_id } => {
-            cancel_side_order(runtime, state, side, client_order_id).await
+        RuntimeEvent::OrderUpdate(update) => {
+            let Some(slot) = route_order_update(slots, &update) else {
+                tracing::debug!(
+                    target: targets::TRADING_DECISION,
+                    client_order_id = %update.client_order_id,
+                    "order update for no configured product"
+                );
+                return false;
+            };
+
+            slot.batch.routed = true;
+            let fill = record_fill(&slot.config, &mut slot.state, &update);
+            reconcile_order_update(&mut slot.state, &update);
+
+            slot.state.now_ms = now_ms();
+            let ctx = slot.state.context(&slot.config);
+            slot.batch
+                .intents
+                .extend(slot.strategy.on_order_update(&ctx, &update));
+            if let Some(fill) = fill {
+                slot.batch
+                    .intents
+                    .extend(slot.strategy.on_fill(&ctx, &fill));
+            }
+            false
         }
-        StrategyAction::Replace {
-            old_client_order_id,
-            new_price_raw,
-            qty_raw,
-        } => {
-            cancel_side_order(runtime, state, side, old_client_order_id).await?;
-            place_side_order(runtime, config, state, side, new_price_raw, qty_raw).await
+        RuntimeEvent::Disconnected => {
+            tracing::warn!(
+                target: targets::TRADING_DECISION,
+                "market data feed disconnected; pulling quotes"
+            );
+            for slot in slots.iter_mut() {
+                slot.batch.latest_market_tick = None;
+            }
+            true
+        }
+        RuntimeEvent::Reconnected => {
+            tracing::info!(
+                target: targets::TRADING_DECISION,
+                "market data feed reconnected; quoting resumes on next tick"
+            );
+            false
         }
     }
 }
 
-async fn place_side_order(
-    runtime: &EtherealRuntime,
+/// Cancels every resting quote; used while the feed is down and fills go unobserved.
+async fn pull_quotes(runtime: &EtherealRuntime, state: &mut StrategyState) {
+    let client_order_ids: Vec<Uuid> = state
+        .orders
+        .iter()
+        .map(|order| order.client_order_id)
+        .collect();
+
+    for client_order_id in client_order_ids {
+        if let Err(error) = cancel_tracked_order(runtime, state, client_order_id).await {
+            tracing::warn!(
+                target: targets::TRADING_DECISION,
+                %client_order_id,
+                %error,
+                "failed to pull quote"
+            );
+        }
+    }
+}
+
+/// Books any new fill carried by `update` into the position ledger.
+fn record_fill(
     config: &StrategyConfig,
     state: &mut StrategyState,
-    side: Side,
-    price_raw: u128,
-    qty_raw: u128,
-) -> Result<(), EtherealRuntimeError> {
-    state.side_state_mut(side).inflight = true;
+    update: &OrderUpdateData,
+) -> Option<Fill> {
+    let fill = state.positions.apply_order_update(
+        update,
+        PositionKey::for_strategy(config),
+        config.fee_rate_raw,
+    )?;
 
-    let client_order_id = runtime
-        .place_order(
-            price_raw,
-            qty_raw,
-            side as u8,
-            config.onchain_product_id,
-            config.post_only,
-            config.time_in_force,
-        )
-        .await?;
+    tracing::info!(
+        target: targets::TRADING_DECISION,
+        side = %fill.side,
+        client_order_id = %update.client_order_id,
+        qty = %format_order_decimal(fill.qty_raw),
+        price = %format_order_decimal(fill.price_raw),
+        fee = %format_order_decimal(fill.fee_raw),
+        "fill booked"
+    );
+    log_position(
+        "position after fill",
+        fill.key,
+        &state.positions.position(fill.key),
+    );
+    Some(fill)
+}
 
-    state.side_state_mut(side).inflight = false;
+fn log_position(message: &'static str, key: PositionKey, position: &Position) {
+    tracing::info!(
+        target: targets::TRADING_DECISION,
+        subaccount_id = %key.subaccount_id,
+        product_id = %key.product_id,
+        net_qty = %format_signed_order_decimal(position.net_qty_raw),
+        avg_entry_price = %format_order_decimal(position.avg_entry_price_raw),
+        realized_pnl = %format_signed_order_decimal(position.realized_pnl_raw),
+        unrealized_pnl = %format_signed_order_decimal(position.unrealized_pnl_raw()),
+        fees = %format_order_decimal(position.fees_raw),
+        total_pnl = %format_signed_order_decimal(position.total_pnl_raw()),
+        "{message}"
+    );
+}
 
-    let side_state = state.side_state_mut(side);
-    side_state.active_client_order_id = Some(client_order_id);
-    side_state.last_quoted_price_raw = Some(price_raw);
+/// Moves the tracked order through its lifecycle, dropping it once terminal.
+pub(crate) fn reconcile_order_update(state: &mut StrategyState, update: &OrderUpdateData) {
+    let Some(order) = state.orders.get_mut(update.client_order_id) else {
+        return;
+    };
+
+    order.apply_status(
+        update.status,
+        update.filled.as_ref().and_then(decimal_to_raw),
+    );
+    let order_state = order.state;
+    if order_state.is_terminal() {
+        state.orders.remove(update.client_order_id);
+    }
 
     tracing::info!(
         target: targets::TRADING_DECISION,
-        %side,
-        %client_order_id,
-        price_raw,
-        qty_raw,
-        "strategy action: place (optimistic completion)"
+        client_order_id = %update.client_order_id,
+        status = %update.status,
+        ?order_state,
+        "strategy state reconciled from order update"
     );
+}
 
-    Ok(())
+pub(crate) fn is_terminal_order_status(status: OrderStatus) -> bool {
+    match status {
+        OrderStatus::Filled
+        | OrderStatus::Rejected
+        | OrderStatus::Canceled
+        | OrderStatus::Expired => true,
+        OrderStatus::New | OrderStatus::Pending | OrderStatus::FilledPartial => false,
+    }
 }
 
-async fn cancel_side_order(
+pub async fn handle_market_tick<S: Strategy>(
     runtime: &EtherealRuntime,
+    config: &StrategyConfig,
     state: &mut StrategyState,
-    side: Side,
-    client_order_id: uuid::Uuid,
+    strategy: &mut S,
+    tick: &MarketPriceData,
 ) -> Result<(), EtherealRuntimeError> {
-    state.side_state_mut(side).inflight = true;
-    let cancel_result = runtime.cancel_order(client_order_id).await;
-    state.side_state_mut(side).inflight = false;
-    cancel_result?;
+    if tick.product_id != config.product_id {
+        return Ok(());
+    }
 
-    let side_state = state.side_state_mut(side);
-    side_state.active_client_order_id = None;
-    side_state.last_quoted_price_raw = None;
+    state.last_market = Some(tick.clone());
+    state.now_ms = now_ms();
+    let intents = strategy.on_market_tick(&state.context(config), tick);
+    if intents.is_empty() {
+        tracing::debug!(
+            target: targets::TRADING_DECISION,
+            "strategy action: skip"
+        );
+    }
 
-    tracing::info!(
-        target: targets::TRADING_DECISION,
-        %side,
-        %client_order_id,
-        "strategy action: cancel (optimistic completion)"
-    );
+    execute_intents(runtime, config, state, intents).await
+}
 
-    Ok(())
+fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as u64
 }
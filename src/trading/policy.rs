@@ -1,14 +1,20 @@
-use num_traits::ToPrimitive;
+use std::collections::BTreeMap;
 
-use super::settings::StrategyConfig;
-use super::state::{Side, SideState, StrategyState};
+use super::position::PositionKey;
+use super::settings::{InventoryConfig, QuoteMode, QuotePlacement, StrategyConfig};
+use super::state::{OrderState, Side, TrackedOrder};
+use super::strategy::{OrderIntent, Strategy, StrategyContext};
+use super::volatility::VolatilityEstimator;
 use crate::models::dto::MarketPriceData;
+use crate::models::util::{ORDER_DECIMALS, decimal_to_raw};
 
-pub type SideActions = (Option<StrategyAction>, Option<StrategyAction>);
+/// The buy and sell side's actions, each in ladder level order.
+pub type SideActions = (Vec<StrategyAction>, Vec<StrategyAction>);
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum StrategyAction {
     Place {
+        level: u32,
         price_raw: u128,
         qty_raw: u128,
     },
@@ -17,89 +23,435 @@ pub enum StrategyAction {
     },
     Replace {
         old_client_order_id: uuid::Uuid,
+        level: u32,
         new_price_raw: u128,
         qty_raw: u128,
     },
 }
 
+impl StrategyAction {
+    pub fn into_intent(self, side: Side) -> OrderIntent {
+        match self {
+            Self::Place {
+                level,
+                price_raw,
+                qty_raw,
+            } => OrderIntent::Place {
+                side,
+                level,
+                price_raw,
+                qty_raw,
+            },
+            Self::Cancel { client_order_id } => OrderIntent::Cancel { client_order_id },
+            Self::Replace {
+                old_client_order_id,
+                level,
+                new_price_raw,
+                qty_raw,
+            } => OrderIntent::Replace {
+                client_order_id: old_client_order_id,
+                side,
+                level,
+                price_raw: new_price_raw,
+                qty_raw,
+            },
+        }
+    }
+}
+
+/// The built-in quoter: a ladder of orders per side starting at the touch,
+/// or skewed by inventory under [`QuoteMode::InventorySkew`].
+#[derive(Debug, Clone)]
+pub struct MarketMaker {
+    /// Present when `adaptive_spread` is enabled.
+    volatility: Option<VolatilityEstimator>,
+}
+
+impl MarketMaker {
+    pub fn new(config: &StrategyConfig) -> Self {
+        let half_life_ms = config.adaptive_spread.half_life_ms;
+        Self {
+            volatility: (half_life_ms > 0).then(|| VolatilityEstimator::new(half_life_ms)),
+        }
+    }
+}
+
+impl Strategy for MarketMaker {
+    fn on_market_tick(
+        &mut self,
+        ctx: &StrategyContext<'_>,
+        tick: &MarketPriceData,
+    ) -> Vec<OrderIntent> {
+        if let Some(estimator) = &mut self.volatility
+            && tick.product_id == ctx.config.product_id
+            && let (Some(bid_raw), Some(ask_raw)) = (
+                decimal_to_raw(&tick.best_bid_price),
+                decimal_to_raw(&tick.best_ask_price),
+            )
+        {
+            estimator.update(bid_raw / 2 + ask_raw / 2, ctx.now_ms);
+        }
+
+        let volatility = self
+            .volatility
+            .as_ref()
+            .and_then(VolatilityEstimator::volatility);
+        let (buy, sell) = decide_actions(ctx, tick, volatility);
+        let buy = buy.into_iter().map(|action| action.into_intent(Side::Buy));
+        let sell = sell
+            .into_iter()
+            .map(|action| action.into_intent(Side::Sell));
+        buy.chain(sell).collect()
+    }
+}
+
+/// `volatility` is the mid's realized volatility per square root of a
+/// second, used by `adaptive_spread`.
 pub fn decide_actions(
-    config: &StrategyConfig,
-    state: &StrategyState,
+    ctx: &StrategyContext<'_>,
     tick: &MarketPriceData,
+    volatility: Option<f64>,
 ) -> SideActions {
+    let config = ctx.config;
+    let buy_ladder = ctx.orders.ladder(Side::Buy);
+    let sell_ladder = ctx.orders.ladder(Side::Sell);
+
     if tick.product_id != config.product_id {
-        return (None, None);
+        return (Vec::new(), Vec::new());
     }
 
     let Some(best_bid_raw) = decimal_to_raw(&tick.best_bid_price) else {
-        return (None, None);
+        return (Vec::new(), Vec::new());
     };
     let Some(best_ask_raw) = decimal_to_raw(&tick.best_ask_price) else {
-        return (None, None);
+        return (Vec::new(), Vec::new());
     };
 
+    let widening_ticks =
+        volatility_widening_ticks(config, best_bid_raw / 2 + best_ask_raw / 2, volatility);
+    let min_spread_ticks = config.min_spread_ticks.saturating_add(widening_ticks);
     let spread_raw = best_ask_raw.saturating_sub(best_bid_raw);
     let min_spread_raw = config
         .tick_size_raw
-        .saturating_mul(config.min_spread_ticks as u128);
+        .saturating_mul(min_spread_ticks as u128);
 
-    if best_ask_raw <= best_bid_raw || (config.min_spread_ticks > 0 && spread_raw < min_spread_raw)
+    let oracle_raw = decimal_to_raw(&tick.oracle_price).filter(|oracle_raw| *oracle_raw > 0);
+    let crossed_or_narrow =
+        best_ask_raw <= best_bid_raw || (min_spread_ticks > 0 && spread_raw < min_spread_raw);
+    let oracle_missing = oracle_raw.is_none()
+        && (config.quote_mode == QuoteMode::Oracle || config.oracle.max_deviation_bps > 0);
+    if crossed_or_narrow
+        || oracle_missing
+        || oracle_raw
+            .is_some_and(|oracle_raw| mid_deviates(config, best_bid_raw, best_ask_raw, oracle_raw))
     {
         return (
-            cancel_if_active(state.side_state(Side::Buy)),
-            cancel_if_active(state.side_state(Side::Sell)),
+            decide_ladder_actions(ctx, &buy_ladder, &[]),
+            decide_ladder_actions(ctx, &sell_ladder, &[]),
         );
     }
 
-    let desired_buy_raw = quantize_to_tick(best_bid_raw, config.tick_size_raw);
-    let desired_sell_raw = quantize_to_tick(best_ask_raw, config.tick_size_raw);
+    let position_raw = ctx
+        .positions
+        .position(PositionKey::for_strategy(config))
+        .net_qty_raw;
+    let offset_ticks = config.offset_ticks.saturating_add(widening_ticks);
+    let anchors = anchor_prices(config, best_bid_raw, best_ask_raw, oracle_raw, offset_ticks);
+    let (mut buy, mut sell) = match config.quote_mode {
+        QuoteMode::Oracle => oracle_quotes(config, anchors, best_bid_raw, best_ask_raw),
+        QuoteMode::Touch => {
+            let quote = |price_raw: u128| {
+                (price_raw > 0).then_some(Quote {
+                    price_raw: quantize_to_tick(price_raw, config.tick_size_raw),
+                    qty_raw: config.qty_raw,
+                })
+            };
+            (quote(anchors.0), quote(anchors.1))
+        }
+        QuoteMode::InventorySkew => {
+            skewed_quotes(config, anchors, best_bid_raw, best_ask_raw, position_raw)
+        }
+    };
+
+    let max_position_raw = config.inventory.max_position_raw as i128;
+    if max_position_raw > 0 {
+        if position_raw >= max_position_raw {
+            buy = None;
+        }
+        if position_raw <= -max_position_raw {
+            sell = None;
+        }
+    }
+
+    (
+        decide_ladder_actions(ctx, &buy_ladder, &ladder_quotes(config, Side::Buy, buy)),
+        decide_ladder_actions(ctx, &sell_ladder, &ladder_quotes(config, Side::Sell, sell)),
+    )
+}
+
+/// Expands the side's top quote into contiguous levels `spacing_ticks` apart, sized by multiplier.
+fn ladder_quotes(config: &StrategyConfig, side: Side, top: Option<Quote>) -> Vec<Quote> {
+    let Some(top) = top else {
+        return Vec::new();
+    };
+    let ladder = &config.ladder;
+    let step_raw = config
+        .tick_size_raw
+        .max(1)
+        .saturating_mul(ladder.spacing_ticks.max(1) as u128);
+    let min_qty_raw = config.min_qty_raw.max(config.lot_size_raw).max(1);
+
+    let mut quotes = Vec::new();
+    for level in 0..ladder.levels.max(1) {
+        let Some(multiplier) = ladder.sizes.multiplier(level) else {
+            break;
+        };
+        let distance_raw = step_raw.saturating_mul(level as u128);
+        let price_raw = match side {
+            Side::Buy => top.price_raw.saturating_sub(distance_raw),
+            Side::Sell => top.price_raw.saturating_add(distance_raw),
+        };
+        let qty_raw = match config.lot_size_raw {
+            0 => (top.qty_raw as f64 * multiplier) as u128,
+            lot_raw => (top.qty_raw as f64 * multiplier) as u128 / lot_raw * lot_raw,
+        };
+        if price_raw == 0 || qty_raw < min_qty_raw {
+            break;
+        }
+        quotes.push(Quote { price_raw, qty_raw });
+    }
+    quotes
+}
+
+/// Diffs the desired `quotes` against the side's resting ladder, level by level.
+fn decide_ladder_actions(
+    ctx: &StrategyContext<'_>,
+    ladder: &BTreeMap<u32, &TrackedOrder>,
+    quotes: &[Quote],
+) -> Vec<StrategyAction> {
+    let resting_levels = ladder.keys().next_back().map_or(0, |level| level + 1);
+    let levels = resting_levels.max(quotes.len() as u32);
+
+    (0..levels)
+        .filter_map(|level| {
+            decide_level_action(
+                ctx,
+                level,
+                ladder.get(&level).copied(),
+                quotes.get(level as usize).copied(),
+            )
+        })
+        .collect()
+}
+
+/// Extra ticks of spread and offset from the mid's expected move, clamped to the floor and cap.
+fn volatility_widening_ticks(
+    config: &StrategyConfig,
+    mid_raw: u128,
+    volatility: Option<f64>,
+) -> u32 {
+    let adaptive = &config.adaptive_spread;
+    if adaptive.half_life_ms == 0 {
+        return 0;
+    }
+    let cap_ticks = adaptive.cap_ticks.max(adaptive.floor_ticks);
+    let Some(volatility) = volatility else {
+        return adaptive.floor_ticks;
+    };
+
+    let expected_move_raw = volatility * mid_raw as f64 * adaptive.horizon_secs.max(0.0).sqrt();
+    let ticks = (expected_move_raw / config.tick_size_raw.max(1) as f64).ceil();
+    (ticks.min(cap_ticks as f64) as u32).clamp(adaptive.floor_ticks, cap_ticks)
+}
+
+/// Whether the book mid is further from the oracle price than
+/// `oracle.max_deviation_bps`.
+fn mid_deviates(
+    config: &StrategyConfig,
+    best_bid_raw: u128,
+    best_ask_raw: u128,
+    oracle_raw: u128,
+) -> bool {
+    let max_deviation_bps = config.oracle.max_deviation_bps as u128;
+    if max_deviation_bps == 0 {
+        return false;
+    }
+    let mid_raw = best_bid_raw / 2 + best_ask_raw / 2;
+    mid_raw.abs_diff(oracle_raw).saturating_mul(10_000)
+        > oracle_raw.saturating_mul(max_deviation_bps)
+}
+
+/// The touch, improved or oracle-anchored bid and ask, moved `offset_ticks` from the mid.
+fn anchor_prices(
+    config: &StrategyConfig,
+    best_bid_raw: u128,
+    best_ask_raw: u128,
+    oracle_raw: Option<u128>,
+    offset_ticks: u32,
+) -> (u128, u128) {
+    let tick_raw = config.tick_size_raw.max(1);
+    let (mut bid_raw, mut ask_raw) = (best_bid_raw, best_ask_raw);
+
+    if config.quote_mode == QuoteMode::Oracle
+        && let Some(oracle_raw) = oracle_raw
+    {
+        let half_spread_raw = tick_raw.saturating_mul(config.oracle.half_spread_ticks as u128);
+        bid_raw = oracle_raw.saturating_sub(half_spread_raw);
+        ask_raw = oracle_raw.saturating_add(half_spread_raw);
+    } else if config.placement == QuotePlacement::Improve
+        && best_ask_raw.saturating_sub(best_bid_raw) >= 3 * tick_raw
+    {
+        bid_raw += tick_raw;
+        ask_raw -= tick_raw;
+    }
 
+    let offset_raw = tick_raw.saturating_mul(offset_ticks as u128);
     (
-        decide_side_action(state.side_state(Side::Buy), desired_buy_raw, config.qty_raw),
-        decide_side_action(
-            state.side_state(Side::Sell),
-            desired_sell_raw,
-            config.qty_raw,
-        ),
+        bid_raw.saturating_sub(offset_raw),
+        ask_raw.saturating_add(offset_raw),
     )
 }
 
-fn decide_side_action(
-    side_state: &SideState,
-    desired_price_raw: u128,
+/// Quotes at the oracle anchors rounded away from the oracle, pulled back
+/// to a tick off the far touch when they would cross it.
+fn oracle_quotes(
+    config: &StrategyConfig,
+    (anchor_bid_raw, anchor_ask_raw): (u128, u128),
+    best_bid_raw: u128,
+    best_ask_raw: u128,
+) -> (Option<Quote>, Option<Quote>) {
+    let tick_raw = config.tick_size_raw.max(1);
+    let bid_raw = quantize_to_tick(anchor_bid_raw, config.tick_size_raw)
+        .min(best_ask_raw.saturating_sub(tick_raw));
+    let ask_raw =
+        quantize_up_to_tick(anchor_ask_raw, config.tick_size_raw).max(best_bid_raw + tick_raw);
+    let quote = |price_raw: u128| {
+        (price_raw > 0).then_some(Quote {
+            price_raw,
+            qty_raw: config.qty_raw,
+        })
+    };
+
+    (quote(bid_raw), quote(ask_raw))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Quote {
+    price_raw: u128,
     qty_raw: u128,
-) -> Option<StrategyAction> {
-    if side_state.inflight {
-        return None;
+}
+
+/// Quotes shifted by the reservation price offset, shrinking the side that would grow the position.
+fn skewed_quotes(
+    config: &StrategyConfig,
+    (anchor_bid_raw, anchor_ask_raw): (u128, u128),
+    best_bid_raw: u128,
+    best_ask_raw: u128,
+    position_raw: i128,
+) -> (Option<Quote>, Option<Quote>) {
+    let shift_raw = reservation_shift_raw(&config.inventory, position_raw);
+    let tick_raw = config.tick_size_raw.max(1);
+
+    let bid_raw = quantize_to_tick(
+        anchor_bid_raw.saturating_add_signed(shift_raw),
+        config.tick_size_raw,
+    )
+    .min(best_ask_raw.saturating_sub(tick_raw));
+    let ask_raw = quantize_up_to_tick(
+        anchor_ask_raw.saturating_add_signed(shift_raw),
+        config.tick_size_raw,
+    )
+    .max(best_bid_raw + tick_raw);
+
+    let (buy_qty_raw, sell_qty_raw) = if position_raw > 0 {
+        (shrunk_qty_raw(config, position_raw), config.qty_raw)
+    } else {
+        (config.qty_raw, shrunk_qty_raw(config, position_raw))
+    };
+    let min_qty_raw = config.min_qty_raw.max(config.lot_size_raw).max(1);
+    let quote = |price_raw: u128, qty_raw: u128| {
+        (price_raw > 0 && qty_raw >= min_qty_raw).then_some(Quote { price_raw, qty_raw })
+    };
+
+    (quote(bid_raw, buy_qty_raw), quote(ask_raw, sell_qty_raw))
+}
+
+/// `-q * gamma * sigma^2 * tau` in raw price units, `q` being the position in
+/// whole units.
+fn reservation_shift_raw(inventory: &InventoryConfig, position_raw: i128) -> i128 {
+    let position = position_raw as f64 / ORDER_DECIMALS as f64;
+    let shift =
+        -position * inventory.risk_aversion * inventory.volatility.powi(2) * inventory.horizon_secs;
+    (shift * ORDER_DECIMALS as f64).round() as i128
+}
+
+fn shrunk_qty_raw(config: &StrategyConfig, position_raw: i128) -> u128 {
+    let max_position_raw = config.inventory.max_position_raw;
+    if max_position_raw == 0 {
+        return config.qty_raw;
     }
 
-    match side_state.active_client_order_id {
-        None => Some(StrategyAction::Place {
-            price_raw: desired_price_raw,
-            qty_raw,
-        }),
-        Some(active_id) => {
-            if side_state.last_quoted_price_raw == Some(desired_price_raw) {
-                None
-            } else {
-                Some(StrategyAction::Replace {
-                    old_client_order_id: active_id,
-                    new_price_raw: desired_price_raw,
-                    qty_raw,
-                })
-            }
-        }
+    let remaining = 1.0 - position_raw.unsigned_abs() as f64 / max_position_raw as f64;
+    let qty_raw = (config.qty_raw as f64 * remaining.clamp(0.0, 1.0)) as u128;
+    match config.lot_size_raw {
+        0 => qty_raw,
+        lot_raw => qty_raw / lot_raw * lot_raw,
     }
 }
 
-fn cancel_if_active(side_state: &SideState) -> Option<StrategyAction> {
-    if side_state.inflight {
+/// Places, replaces or cancels a ladder level's order to match `quote`.
+fn decide_level_action(
+    ctx: &StrategyContext<'_>,
+    level: u32,
+    order: Option<&TrackedOrder>,
+    quote: Option<Quote>,
+) -> Option<StrategyAction> {
+    let Some(Quote { price_raw, qty_raw }) = quote else {
+        return cancel_if_working(order);
+    };
+
+    let Some(order) = order else {
+        return Some(StrategyAction::Place {
+            level,
+            price_raw,
+            qty_raw,
+        });
+    };
+    if !order.state.is_working() {
+        return None;
+    }
+
+    let quoted_qty_raw = match order.state {
+        OrderState::PartiallyFilled { remaining_raw, .. } => remaining_raw,
+        _ => order.qty_raw,
+    };
+    let config = ctx.config;
+    let moved_raw = order.price_raw.abs_diff(price_raw);
+    let requote_raw = config
+        .tick_size_raw
+        .max(1)
+        .saturating_mul(config.requote_min_ticks.max(1) as u128);
+    if quoted_qty_raw == qty_raw && moved_raw < requote_raw {
+        return None;
+    }
+    if ctx.now_ms.saturating_sub(order.created_at_ms) < config.min_quote_lifetime_ms {
         return None;
     }
 
-    side_state
-        .active_client_order_id
-        .map(|client_order_id| StrategyAction::Cancel { client_order_id })
+    Some(StrategyAction::Replace {
+        old_client_order_id: order.client_order_id,
+        level,
+        new_price_raw: price_raw,
+        qty_raw,
+    })
+}
+
+fn cancel_if_working(order: Option<&TrackedOrder>) -> Option<StrategyAction> {
+    order
+        .filter(|order| order.state.is_working())
+        .map(|order| StrategyAction::Cancel {
+            client_order_id: order.client_order_id,
+        })
 }
 
 fn quantize_to_tick(price_raw: u128, tick_size_raw: u128) -> u128 {
@@ -109,10 +461,11 @@ fn quantize_to_tick(price_raw: u128, tick_size_raw: u128) -> u128 {
     (price_raw / tick_size_raw) * tick_size_raw
 }
 
-fn decimal_to_raw(value: &bigdecimal::BigDecimal) -> Option<u128> {
-    let scaled = value.with_scale(crate::models::util::ORDER_DECIMAL_PLACES as i64);
-    let (digits, _scale) = scaled.into_bigint_and_scale();
-    digits.to_biguint()?.to_u128()
+fn quantize_up_to_tick(price_raw: u128, tick_size_raw: u128) -> u128 {
+    if tick_size_raw == 0 {
+        return price_raw;
+    }
+    price_raw.div_ceil(tick_size_raw) * tick_size_raw
 }
 
 #[cfg(test)]
@@ -123,9 +476,15 @@ mod tests {
     use uuid::Uuid;
 
     use super::{StrategyAction, decide_actions, decimal_to_raw};
-    use crate::models::dto::MarketPriceData;
-    use crate::trading::settings::{StrategyConfig, TimeInForce};
-    use crate::trading::state::StrategyState;
+    use crate::models::common::OrderStatus;
+    use crate::models::dto::{MarketPriceData, OrderUpdateData};
+    use crate::trading::position::PositionKey;
+    use crate::trading::settings::{
+        AdaptiveSpreadConfig, HaltConfig, InventoryConfig, LadderConfig, OracleConfig, QuoteMode,
+        QuotePlacement, SizeProfile, StaleOrderConfig, StartupOrderPolicy, StrategyConfig,
+        StrategyKind, TimeInForce,
+    };
+    use crate::trading::state::{OrderState, Side, StrategyState, TrackedOrder};
 
     #[test]
     fn converts_decimal_to_raw_using_scale() {
@@ -140,18 +499,20 @@ mod tests {
         let state = StrategyState::default();
         let tick = market(product_id, "100", "101");
 
-        let actions = decide_actions(&config, &state, &tick);
+        let actions = decide_actions(&state.context(&config), &tick, None);
         assert_eq!(
             actions,
             (
-                Some(StrategyAction::Place {
+                vec![StrategyAction::Place {
+                    level: 0,
                     price_raw: 100_000_000_000,
                     qty_raw: config.qty_raw,
-                }),
-                Some(StrategyAction::Place {
+                }],
+                vec![StrategyAction::Place {
+                    level: 0,
                     price_raw: 101_000_000_000,
                     qty_raw: config.qty_raw,
-                })
+                }]
             )
         );
     }
@@ -161,13 +522,14 @@ mod tests {
         let product_id = Uuid::new_v4();
         let config = base_config(product_id);
         let mut state = StrategyState::default();
-        state.buy.active_client_order_id = Some(Uuid::new_v4());
-        state.sell.active_client_order_id = Some(Uuid::new_v4());
-        state.buy.last_quoted_price_raw = Some(100_000_000_000);
-        state.sell.last_quoted_price_raw = Some(101_000_000_000);
+        track(&mut state, Side::Buy, 100_000_000_000);
+        track(&mut state, Side::Sell, 101_000_000_000);
         let tick = market(product_id, "100", "101");
 
-        assert_eq!(decide_actions(&config, &state, &tick), (None, None));
+        assert_eq!(
+            decide_actions(&state.context(&config), &tick, None),
+            (vec![], vec![])
+        );
     }
 
     #[test]
@@ -175,24 +537,24 @@ mod tests {
         let product_id = Uuid::new_v4();
         let config = base_config(product_id);
         let mut state = StrategyState::default();
-        let buy_order_id = Uuid::new_v4();
-        state.buy.active_client_order_id = Some(buy_order_id);
-        state.buy.last_quoted_price_raw = Some(99_000_000_000);
+        let buy_order_id = track(&mut state, Side::Buy, 99_000_000_000);
         let tick = market(product_id, "100", "101");
 
-        let actions = decide_actions(&config, &state, &tick);
+        let actions = decide_actions(&state.context(&config), &tick, None);
         assert_eq!(
             actions,
             (
-                Some(StrategyAction::Replace {
+                vec![StrategyAction::Replace {
                     old_client_order_id: buy_order_id,
+                    level: 0,
                     new_price_raw: 100_000_000_000,
                     qty_raw: config.qty_raw,
-                }),
-                Some(StrategyAction::Place {
+                }],
+                vec![StrategyAction::Place {
+                    level: 0,
                     price_raw: 101_000_000_000,
                     qty_raw: config.qty_raw,
-                })
+                }]
             )
         );
     }
@@ -203,43 +565,527 @@ mod tests {
         let mut config = base_config(product_id);
         config.min_spread_ticks = 2;
         let mut state = StrategyState::default();
-        let buy_order_id = Uuid::new_v4();
-        state.buy.active_client_order_id = Some(buy_order_id);
+        let buy_order_id = track(&mut state, Side::Buy, 100_000_000_000);
         let tick = market(product_id, "100", "101");
 
         assert_eq!(
-            decide_actions(&config, &state, &tick),
+            decide_actions(&state.context(&config), &tick, None),
             (
-                Some(StrategyAction::Cancel {
+                vec![StrategyAction::Cancel {
                     client_order_id: buy_order_id,
-                }),
-                None
+                }],
+                vec![]
             )
         );
     }
 
     #[test]
-    fn inflight_side_is_blocked() {
+    fn unconfirmed_order_is_left_alone() {
+        let product_id = Uuid::new_v4();
+        let mut config = base_config(product_id);
+        let mut state = StrategyState::default();
+        let buy_order_id = track(&mut state, Side::Buy, 99_000_000_000);
+        state.orders.get_mut(buy_order_id).unwrap().state = OrderState::PendingNew;
+        let tick = market(product_id, "100", "101");
+
+        assert_eq!(
+            decide_actions(&state.context(&config), &tick, None),
+            (vec![], vec![StrategyAction::Place {
+                level: 0,
+                price_raw: 101_000_000_000,
+                qty_raw: config.qty_raw,
+            }])
+        );
+
+        config.min_spread_ticks = 2;
+        assert_eq!(
+            decide_actions(&state.context(&config), &tick, None),
+            (vec![], vec![])
+        );
+    }
+
+    #[test]
+    fn pending_cancel_is_never_cancelled_again() {
+        let product_id = Uuid::new_v4();
+        let mut config = base_config(product_id);
+        let mut state = StrategyState::default();
+        let buy_order_id = track(&mut state, Side::Buy, 99_000_000_000);
+        state.orders.get_mut(buy_order_id).unwrap().state =
+            OrderState::PendingCancel { filled_raw: 0 };
+        let tick = market(product_id, "100", "101");
+
+        assert_eq!(
+            decide_actions(&state.context(&config), &tick, None).0,
+            vec![StrategyAction::Place {
+                level: 0,
+                price_raw: 100_000_000_000,
+                qty_raw: config.qty_raw,
+            }]
+        );
+
+        config.min_spread_ticks = 2;
+        assert_eq!(
+            decide_actions(&state.context(&config), &tick, None),
+            (vec![], vec![])
+        );
+    }
+
+    #[test]
+    fn partial_fill_resizes_the_quote() {
         let product_id = Uuid::new_v4();
         let config = base_config(product_id);
         let mut state = StrategyState::default();
-        state.buy.inflight = true;
+        let buy_order_id = track(&mut state, Side::Buy, 100_000_000_000);
+        track(&mut state, Side::Sell, 101_000_000_000);
+        state
+            .orders
+            .get_mut(buy_order_id)
+            .unwrap()
+            .apply_status(OrderStatus::FilledPartial, Some(40_000_000));
+        let tick = market(product_id, "100", "101");
+
+        assert_eq!(
+            state.orders.get(buy_order_id).unwrap().state,
+            OrderState::PartiallyFilled {
+                filled_raw: 40_000_000,
+                remaining_raw: 60_000_000,
+            }
+        );
+        assert_eq!(
+            decide_actions(&state.context(&config), &tick, None),
+            (
+                vec![StrategyAction::Replace {
+                    old_client_order_id: buy_order_id,
+                    level: 0,
+                    new_price_raw: 100_000_000_000,
+                    qty_raw: config.qty_raw,
+                }],
+                vec![]
+            )
+        );
+    }
+
+    #[test]
+    fn flat_inventory_skew_matches_the_touch() {
+        let product_id = Uuid::new_v4();
+        let mut config = skew_config(product_id);
+        let state = StrategyState::default();
+        let tick = market(product_id, "100", "104");
+
+        let skewed = decide_actions(&state.context(&config), &tick, None);
+        config.quote_mode = QuoteMode::Touch;
+
+        assert_eq!(skewed, decide_actions(&state.context(&config), &tick, None));
+    }
+
+    #[test]
+    fn long_inventory_lowers_quotes_and_shrinks_bids() {
+        let product_id = Uuid::new_v4();
+        let config = skew_config(product_id);
+        let state = with_position(&config, Side::Buy, "1");
+        let tick = market(product_id, "100", "104");
+
+        assert_eq!(
+            decide_actions(&state.context(&config), &tick, None),
+            (
+                vec![StrategyAction::Place {
+                    level: 0,
+                    price_raw: 99_000_000_000,
+                    qty_raw: 50_000_000,
+                }],
+                vec![StrategyAction::Place {
+                    level: 0,
+                    price_raw: 103_000_000_000,
+                    qty_raw: config.qty_raw,
+                }]
+            )
+        );
+    }
+
+    #[test]
+    fn short_inventory_raises_quotes_but_never_crosses() {
+        let product_id = Uuid::new_v4();
+        let config = skew_config(product_id);
+        let state = with_position(&config, Side::Sell, "1");
+        let tick = market(product_id, "100", "101");
+
+        assert_eq!(
+            decide_actions(&state.context(&config), &tick, None),
+            (
+                vec![StrategyAction::Place {
+                    level: 0,
+                    price_raw: 100_000_000_000,
+                    qty_raw: config.qty_raw,
+                }],
+                vec![StrategyAction::Place {
+                    level: 0,
+                    price_raw: 102_000_000_000,
+                    qty_raw: 50_000_000,
+                }]
+            )
+        );
+    }
+
+    #[test]
+    fn max_position_stops_the_side_that_grows_it() {
+        let product_id = Uuid::new_v4();
+        let mut config = base_config(product_id);
+        config.inventory.max_position_raw = 2_000_000_000;
+        let mut state = with_position(&config, Side::Buy, "2");
+        let buy_order_id = track(&mut state, Side::Buy, 100_000_000_000);
         let tick = market(product_id, "100", "101");
 
         assert_eq!(
-            decide_actions(&config, &state, &tick),
+            decide_actions(&state.context(&config), &tick, None),
             (
-                None,
-                Some(StrategyAction::Place {
+                vec![StrategyAction::Cancel {
+                    client_order_id: buy_order_id,
+                }],
+                vec![StrategyAction::Place {
+                    level: 0,
                     price_raw: 101_000_000_000,
                     qty_raw: config.qty_raw,
-                })
+                }]
+            )
+        );
+    }
+
+    #[test]
+    fn improve_steps_inside_wide_spreads_and_offset_backs_off() {
+        let product_id = Uuid::new_v4();
+        let mut config = base_config(product_id);
+        config.placement = QuotePlacement::Improve;
+        let state = StrategyState::default();
+        let place = |price_raw| {
+            vec![StrategyAction::Place {
+                level: 0,
+                price_raw,
+                qty_raw: config.qty_raw,
+            }]
+        };
+
+        assert_eq!(
+            decide_actions(
+                &state.context(&config),
+                &market(product_id, "100", "103"),
+                None
+            ),
+            (place(101_000_000_000), place(102_000_000_000))
+        );
+        assert_eq!(
+            decide_actions(
+                &state.context(&config),
+                &market(product_id, "100", "102"),
+                None
+            ),
+            (place(100_000_000_000), place(102_000_000_000))
+        );
+
+        config.placement = QuotePlacement::Join;
+        config.offset_ticks = 2;
+        assert_eq!(
+            decide_actions(
+                &state.context(&config),
+                &market(product_id, "100", "101"),
+                None
+            ),
+            (place(98_000_000_000), place(103_000_000_000))
+        );
+    }
+
+    #[test]
+    fn small_moves_and_young_quotes_are_not_requoted() {
+        let product_id = Uuid::new_v4();
+        let mut config = base_config(product_id);
+        config.requote_min_ticks = 2;
+        config.min_quote_lifetime_ms = 500;
+        let mut state = StrategyState::default();
+        let buy_order_id = track(&mut state, Side::Buy, 99_000_000_000);
+        track(&mut state, Side::Sell, 101_000_000_000);
+        state.now_ms = 1_000;
+
+        let one_tick = market(product_id, "100", "101");
+        assert_eq!(
+            decide_actions(&state.context(&config), &one_tick, None),
+            (vec![], vec![])
+        );
+
+        let two_ticks = market(product_id, "101", "102");
+        state.orders.get_mut(buy_order_id).unwrap().created_at_ms = 600;
+        assert_eq!(
+            decide_actions(&state.context(&config), &two_ticks, None).0,
+            vec![]
+        );
+
+        state.orders.get_mut(buy_order_id).unwrap().created_at_ms = 500;
+        assert_eq!(
+            decide_actions(&state.context(&config), &two_ticks, None).0,
+            vec![StrategyAction::Replace {
+                old_client_order_id: buy_order_id,
+                level: 0,
+                new_price_raw: 101_000_000_000,
+                qty_raw: config.qty_raw,
+            }]
+        );
+    }
+
+    #[test]
+    fn ladder_levels_step_away_from_the_touch_with_sized_profile() {
+        let product_id = Uuid::new_v4();
+        let mut config = base_config(product_id);
+        config.ladder = LadderConfig {
+            levels: 3,
+            spacing_ticks: 2,
+            sizes: SizeProfile::Geometric { ratio: 2.0 },
+        };
+        let state = StrategyState::default();
+        let tick = market(product_id, "100", "101");
+
+        let (buy, sell) = decide_actions(&state.context(&config), &tick, None);
+
+        let place = |level, price_raw, qty_raw| StrategyAction::Place {
+            level,
+            price_raw,
+            qty_raw,
+        };
+        assert_eq!(buy, vec![
+            place(0, 100_000_000_000, 100_000_000),
+            place(1, 98_000_000_000, 200_000_000),
+            place(2, 96_000_000_000, 400_000_000),
+        ]);
+        assert_eq!(sell, vec![
+            place(0, 101_000_000_000, 100_000_000),
+            place(1, 103_000_000_000, 200_000_000),
+            place(2, 105_000_000_000, 400_000_000),
+        ]);
+    }
+
+    #[test]
+    fn custom_sizes_scale_level_zero_too() {
+        let product_id = Uuid::new_v4();
+        let mut config = base_config(product_id);
+        config.ladder = LadderConfig {
+            levels: 3,
+            spacing_ticks: 1,
+            sizes: SizeProfile::Custom {
+                multipliers: vec![0.5, 2.0],
+            },
+        };
+        let state = StrategyState::default();
+        let tick = market(product_id, "100", "101");
+
+        let (buy, _) = decide_actions(&state.context(&config), &tick, None);
+
+        let place = |level, price_raw, qty_raw| StrategyAction::Place {
+            level,
+            price_raw,
+            qty_raw,
+        };
+        assert_eq!(buy, vec![
+            place(0, 100_000_000_000, 50_000_000),
+            place(1, 99_000_000_000, 200_000_000),
+        ]);
+    }
+
+    #[test]
+    fn ladder_diff_only_touches_changed_levels() {
+        let product_id = Uuid::new_v4();
+        let mut config = base_config(product_id);
+        config.ladder = LadderConfig {
+            levels: 3,
+            spacing_ticks: 1,
+            sizes: SizeProfile::Custom {
+                multipliers: vec![1.0, 1.0],
+            },
+        };
+        let mut state = StrategyState::default();
+        track(&mut state, Side::Buy, 100_000_000_000);
+        let stray_id = track(&mut state, Side::Buy, 97_000_000_000);
+        state.orders.get_mut(stray_id).unwrap().level = 3;
+        track(&mut state, Side::Sell, 101_000_000_000);
+        let sell_level_1 = track(&mut state, Side::Sell, 103_000_000_000);
+        state.orders.get_mut(sell_level_1).unwrap().level = 1;
+        let tick = market(product_id, "100", "101");
+
+        assert_eq!(
+            decide_actions(&state.context(&config), &tick, None),
+            (
+                vec![
+                    StrategyAction::Place {
+                        level: 1,
+                        price_raw: 99_000_000_000,
+                        qty_raw: config.qty_raw,
+                    },
+                    StrategyAction::Cancel {
+                        client_order_id: stray_id,
+                    },
+                ],
+                vec![StrategyAction::Replace {
+                    old_client_order_id: sell_level_1,
+                    level: 1,
+                    new_price_raw: 102_000_000_000,
+                    qty_raw: config.qty_raw,
+                }]
+            )
+        );
+    }
+
+    #[test]
+    fn oracle_mode_centres_quotes_on_the_oracle_but_stays_passive() {
+        let product_id = Uuid::new_v4();
+        let mut config = base_config(product_id);
+        config.quote_mode = QuoteMode::Oracle;
+        config.oracle.half_spread_ticks = 2;
+        let state = StrategyState::default();
+        let place = |price_raw| {
+            vec![StrategyAction::Place {
+                level: 0,
+                price_raw,
+                qty_raw: config.qty_raw,
+            }]
+        };
+
+        let mut tick = market(product_id, "95", "110");
+        tick.oracle_price = BigDecimal::from_str("102.5").unwrap();
+        assert_eq!(
+            decide_actions(&state.context(&config), &tick, None),
+            (place(100_000_000_000), place(105_000_000_000))
+        );
+
+        let tick = market(product_id, "97", "101");
+        assert_eq!(
+            decide_actions(&state.context(&config), &tick, None),
+            (place(98_000_000_000), place(102_000_000_000))
+        );
+
+        let mut tick = market(product_id, "103", "104");
+        tick.oracle_price = BigDecimal::from_str("100").unwrap();
+        assert_eq!(
+            decide_actions(&state.context(&config), &tick, None),
+            (place(98_000_000_000), place(104_000_000_000))
+        );
+    }
+
+    #[test]
+    fn mid_far_from_the_oracle_cancels_both_sides() {
+        let product_id = Uuid::new_v4();
+        let mut config = base_config(product_id);
+        config.oracle.max_deviation_bps = 100;
+        let mut state = StrategyState::default();
+        let buy_order_id = track(&mut state, Side::Buy, 100_000_000_000);
+        let sell_order_id = track(&mut state, Side::Sell, 101_000_000_000);
+
+        let near = market(product_id, "100", "101");
+        assert_eq!(
+            decide_actions(&state.context(&config), &near, None),
+            (vec![], vec![])
+        );
+
+        let far = market(product_id, "101", "102");
+        assert_eq!(
+            decide_actions(&state.context(&config), &far, None),
+            (
+                vec![StrategyAction::Cancel {
+                    client_order_id: buy_order_id,
+                }],
+                vec![StrategyAction::Cancel {
+                    client_order_id: sell_order_id,
+                }]
             )
         );
     }
 
+    #[test]
+    fn volatility_widens_the_minimum_spread_and_offset() {
+        let product_id = Uuid::new_v4();
+        let mut config = base_config(product_id);
+        config.adaptive_spread = AdaptiveSpreadConfig {
+            half_life_ms: 1_000,
+            horizon_secs: 1.0,
+            floor_ticks: 0,
+            cap_ticks: 3,
+        };
+        let state = StrategyState::default();
+        let place = |price_raw| {
+            vec![StrategyAction::Place {
+                level: 0,
+                price_raw,
+                qty_raw: config.qty_raw,
+            }]
+        };
+
+        let wide = market(product_id, "100", "104");
+        assert_eq!(
+            decide_actions(&state.context(&config), &wide, None),
+            (place(100_000_000_000), place(104_000_000_000))
+        );
+        assert_eq!(
+            decide_actions(&state.context(&config), &wide, Some(0.01)),
+            (place(98_000_000_000), place(106_000_000_000))
+        );
+        assert_eq!(
+            decide_actions(&state.context(&config), &wide, Some(1.0)),
+            (place(97_000_000_000), place(107_000_000_000))
+        );
+
+        let narrow = market(product_id, "100", "102");
+        assert_eq!(
+            decide_actions(&state.context(&config), &narrow, Some(0.01)),
+            (vec![], vec![])
+        );
+    }
+
+    fn track(state: &mut StrategyState, side: Side, price_raw: u128) -> Uuid {
+        let client_order_id = Uuid::new_v4();
+        state.orders.insert(TrackedOrder {
+            client_order_id,
+            side,
+            level: 0,
+            price_raw,
+            qty_raw: 100_000_000,
+            state: OrderState::Open,
+            created_at_ms: 0,
+        });
+        client_order_id
+    }
+
+    fn skew_config(product_id: Uuid) -> StrategyConfig {
+        let mut config = base_config(product_id);
+        config.quote_mode = QuoteMode::InventorySkew;
+        config.inventory = InventoryConfig {
+            max_position_raw: 2_000_000_000,
+            risk_aversion: 0.1,
+            volatility: 1.0,
+            horizon_secs: 10.0,
+        };
+        config
+    }
+
+    fn with_position(config: &StrategyConfig, side: Side, qty: &str) -> StrategyState {
+        let mut state = StrategyState::default();
+        let id = Uuid::new_v4();
+        state.positions.apply_order_update(
+            &OrderUpdateData {
+                id,
+                status: OrderStatus::Filled,
+                created_at: 0,
+                updated_at: 0,
+                client_order_id: id,
+                product_id: Some(config.product_id),
+                subaccount_id: Some(config.subaccount),
+                side: Some(side as u8),
+                price: Some(BigDecimal::from_str("100").unwrap()),
+                filled: Some(BigDecimal::from_str(qty).unwrap()),
+            },
+            PositionKey::for_strategy(config),
+            0,
+        );
+        state
+    }
+
     fn base_config(product_id: Uuid) -> StrategyConfig {
         StrategyConfig {
+            kind: StrategyKind::default(),
             subaccount: Uuid::new_v4(),
             product_id,
             onchain_product_id: 42,
@@ -247,7 +1093,24 @@ mod tests {
             post_only: true,
             time_in_force: TimeInForce::Gtd,
             tick_size_raw: 1_000_000_000,
+            lot_size_raw: 1_000_000,
+            min_qty_raw: 1_000_000,
             min_spread_ticks: 1,
+            placement: QuotePlacement::default(),
+            offset_ticks: 0,
+            requote_min_ticks: 1,
+            min_quote_lifetime_ms: 0,
+            quote_mode: QuoteMode::default(),
+            inventory: InventoryConfig::default(),
+            oracle: OracleConfig::default(),
+            adaptive_spread: AdaptiveSpreadConfig::default(),
+            ladder: LadderConfig::default(),
+            shutdown_timeout_ms: 5_000,
+            fee_rate_raw: 0,
+            startup_orders: StartupOrderPolicy::default(),
+            halt: HaltConfig::default(),
+            stale_orders: StaleOrderConfig::default(),
+            market_data_timeout_ms: 10_000,
         }
     }
 
@@ -0,0 +1,431 @@
+use std::collections::{HashMap, VecDeque};
+
+use uuid::Uuid;
+
+use super::is_terminal_order_status;
+use super::settings::StrategyConfig;
+use super::state::Side;
+use crate::models::dto::{OrderUpdateData, PositionData};
+use crate::models::util::{ORDER_DECIMALS, decimal_to_raw};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PositionKey {
+    pub subaccount_id: Uuid,
+    pub product_id: Uuid,
+}
+
+impl PositionKey {
+    pub fn for_strategy(config: &StrategyConfig) -> Self {
+        Self {
+            subaccount_id: config.subaccount,
+            product_id: config.product_id,
+        }
+    }
+}
+
+/// Inventory and PnL of one subaccount on one product, in raw order units.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Position {
+    /// Signed net quantity; positive is long.
+    pub net_qty_raw: i128,
+    /// Volume-weighted entry price of the open quantity; 0 when flat.
+    pub avg_entry_price_raw: u128,
+    pub realized_pnl_raw: i128,
+    pub fees_raw: u128,
+    /// Last `oracle_price` seen for the product.
+    pub mark_price_raw: Option<u128>,
+}
+
+impl Position {
+    /// The open quantity and entry price the exchange reports; PnL and fees
+    /// start from zero. `None` when the side or size does not parse.
+    pub fn from_exchange(data: &PositionData) -> Option<Self> {
+        let size_raw = decimal_to_raw(&data.size)?;
+        let net_qty_raw = match Side::from_raw(data.side)? {
+            Side::Buy => size_raw as i128,
+            Side::Sell => -(size_raw as i128),
+        };
+        let avg_entry_price_raw = match &data.cost {
+            Some(cost) if size_raw > 0 => decimal_to_raw(&cost.abs())? * ORDER_DECIMALS / size_raw,
+            _ => 0,
+        };
+        Some(Self {
+            net_qty_raw,
+            avg_entry_price_raw,
+            ..Self::default()
+        })
+    }
+
+    /// Open quantity marked to `mark_price_raw`; 0 until a mark is known.
+    pub fn unrealized_pnl_raw(&self) -> i128 {
+        self.mark_price_raw.map_or(0, |mark| {
+            (mark as i128 - self.avg_entry_price_raw as i128) * self.net_qty_raw
+                / ORDER_DECIMALS as i128
+        })
+    }
+
+    /// Realized plus unrealized PnL, net of fees.
+    pub fn total_pnl_raw(&self) -> i128 {
+        self.realized_pnl_raw + self.unrealized_pnl_raw() - self.fees_raw as i128
+    }
+
+    fn apply_fill(&mut self, side: Side, qty_raw: u128, price_raw: u128, fee_raw: u128) {
+        let signed_qty = match side {
+            Side::Buy => qty_raw as i128,
+            Side::Sell => -(qty_raw as i128),
+        };
+        self.fees_raw += fee_raw;
+
+        let open_qty = self.net_qty_raw.unsigned_abs();
+        if self.net_qty_raw == 0 || self.net_qty_raw.signum() == signed_qty.signum() {
+            let cost = open_qty * self.avg_entry_price_raw + qty_raw * price_raw;
+            self.avg_entry_price_raw = cost / (open_qty + qty_raw);
+            self.net_qty_raw += signed_qty;
+            return;
+        }
+
+        let closed_qty = qty_raw.min(open_qty);
+        let per_unit = price_raw as i128 - self.avg_entry_price_raw as i128;
+        self.realized_pnl_raw +=
+            per_unit * closed_qty as i128 * self.net_qty_raw.signum() / ORDER_DECIMALS as i128;
+        self.net_qty_raw += signed_qty;
+
+        if self.net_qty_raw == 0 {
+            self.avg_entry_price_raw = 0;
+        } else if qty_raw > closed_qty {
+            self.avg_entry_price_raw = price_raw;
+        }
+    }
+}
+
+/// One fill booked into the ledger.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fill {
+    pub key: PositionKey,
+    pub side: Side,
+    pub qty_raw: u128,
+    pub price_raw: u128,
+    pub fee_raw: u128,
+}
+
+/// Terminal orders whose booked quantity is remembered, so a replayed
+/// terminal update or a late partial is not booked again.
+const SETTLED_ORDERS_KEPT: usize = 1_024;
+
+/// Positions per subaccount and product, built from the cumulative `filled`
+/// quantity of order updates. Fills are booked at the order's limit price.
+#[derive(Debug, Clone, Default)]
+pub struct PositionLedger {
+    positions: HashMap<PositionKey, Position>,
+    /// Filled quantity already booked per exchange order id.
+    booked: HashMap<Uuid, u128>,
+    /// Terminal orders still in `booked`, oldest first.
+    settled: VecDeque<Uuid>,
+}
+
+impl PositionLedger {
+    /// Books whatever `update` filled since the previous update of the same
+    /// order. `fallback` keys updates that omit their subaccount or product;
+    /// `fee_rate_raw` is the fee per unit notional (1e9 = 100%).
+    pub fn apply_order_update(
+        &mut self,
+        update: &OrderUpdateData,
+        fallback: PositionKey,
+        fee_rate_raw: u128,
+    ) -> Option<Fill> {
+        let already_booked = self.booked.get(&update.id).copied().unwrap_or(0);
+        let fill = update
+            .filled
+            .as_ref()
+            .and_then(decimal_to_raw)
+            .filter(|filled_raw| *filled_raw > already_booked)
+            .and_then(|filled_raw| {
+                let side = update.side.and_then(Side::from_raw)?;
+                let price_raw = update.price.as_ref().and_then(decimal_to_raw)?;
+                Some((filled_raw, side, price_raw))
+            });
+        if let Some((filled_raw, ..)) = fill {
+            self.booked.insert(update.id, filled_raw);
+        }
+        if is_terminal_order_status(update.status) {
+            self.settle(update.id);
+        }
+
+        let (filled_raw, side, price_raw) = fill?;
+        let qty_raw = filled_raw - already_booked;
+        let fee_raw = qty_raw * price_raw / ORDER_DECIMALS * fee_rate_raw / ORDER_DECIMALS;
+        let key = PositionKey {
+            subaccount_id: update.subaccount_id.unwrap_or(fallback.subaccount_id),
+            product_id: update.product_id.unwrap_or(fallback.product_id),
+        };
+
+        self.positions
+            .entry(key)
+            .or_default()
+            .apply_fill(side, qty_raw, price_raw, fee_raw);
+
+        Some(Fill {
+            key,
+            side,
+            qty_raw,
+            price_raw,
+            fee_raw,
+        })
+    }
+
+    /// Keeps a terminal order's booked quantity, dropping the oldest settled
+    /// order's once more than [`SETTLED_ORDERS_KEPT`] are kept.
+    fn settle(&mut self, order_id: Uuid) {
+        if !self.booked.contains_key(&order_id) || self.settled.contains(&order_id) {
+            return;
+        }
+        self.settled.push_back(order_id);
+        if self.settled.len() > SETTLED_ORDERS_KEPT
+            && let Some(oldest) = self.settled.pop_front()
+        {
+            self.booked.remove(&oldest);
+        }
+    }
+
+    /// Replaces the position under `key`, e.g. with the one the exchange
+    /// reports at startup.
+    pub fn seed(&mut self, key: PositionKey, position: Position) {
+        self.positions.insert(key, position);
+    }
+
+    /// Counts `filled_raw` of `order_id` as booked, for an order whose fills
+    /// so far are already part of a seeded position.
+    pub fn mark_booked(&mut self, order_id: Uuid, filled_raw: u128) {
+        if filled_raw > 0 {
+            self.booked.insert(order_id, filled_raw);
+        }
+    }
+
+    /// Marks every subaccount's position on `product_id`.
+    pub fn mark(&mut self, product_id: Uuid, mark_price_raw: u128) {
+        for (key, position) in &mut self.positions {
+            if key.product_id == product_id {
+                position.mark_price_raw = Some(mark_price_raw);
+            }
+        }
+    }
+
+    pub fn position(&self, key: PositionKey) -> Position {
+        self.positions.get(&key).cloned().unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use bigdecimal::BigDecimal;
+    use uuid::Uuid;
+
+    use super::{Position, PositionKey, PositionLedger};
+    use crate::models::common::OrderStatus;
+    use crate::models::dto::{OrderUpdateData, PositionData};
+    use crate::trading::state::Side;
+
+    const ONE: u128 = 1_000_000_000;
+
+    fn key() -> PositionKey {
+        PositionKey {
+            subaccount_id: Uuid::nil(),
+            product_id: Uuid::nil(),
+        }
+    }
+
+    fn update(
+        id: Uuid,
+        side: Side,
+        price: &str,
+        filled: &str,
+        status: OrderStatus,
+    ) -> OrderUpdateData {
+        OrderUpdateData {
+            id,
+            status,
+            created_at: 0,
+            updated_at: 0,
+            client_order_id: id,
+            product_id: None,
+            subaccount_id: None,
+            side: Some(side as u8),
+            price: Some(BigDecimal::from_str(price).unwrap()),
+            filled: Some(BigDecimal::from_str(filled).unwrap()),
+        }
+    }
+
+    #[test]
+    fn partial_fills_are_booked_once() {
+        let mut ledger = PositionLedger::default();
+        let id = Uuid::new_v4();
+
+        let first = ledger
+            .apply_order_update(
+                &update(id, Side::Buy, "100", "0.4", OrderStatus::FilledPartial),
+                key(),
+                0,
+            )
+            .unwrap();
+        assert_eq!(first.qty_raw, 400_000_000);
+        assert!(
+            ledger
+                .apply_order_update(
+                    &update(id, Side::Buy, "100", "0.4", OrderStatus::FilledPartial),
+                    key(),
+                    0
+                )
+                .is_none()
+        );
+        let last = ledger
+            .apply_order_update(
+                &update(id, Side::Buy, "100", "1", OrderStatus::Filled),
+                key(),
+                0,
+            )
+            .unwrap();
+        assert_eq!(last.qty_raw, 600_000_000);
+
+        let position = ledger.position(key());
+        assert_eq!(position.net_qty_raw, ONE as i128);
+        assert_eq!(position.avg_entry_price_raw, 100 * ONE);
+    }
+
+    #[test]
+    fn closing_realizes_pnl_and_flipping_resets_entry() {
+        let mut ledger = PositionLedger::default();
+        let fee_rate = ONE / 1_000;
+
+        ledger.apply_order_update(
+            &update(Uuid::new_v4(), Side::Buy, "100", "1", OrderStatus::Filled),
+            key(),
+            fee_rate,
+        );
+        ledger.apply_order_update(
+            &update(Uuid::new_v4(), Side::Buy, "110", "1", OrderStatus::Filled),
+            key(),
+            fee_rate,
+        );
+        assert_eq!(ledger.position(key()).avg_entry_price_raw, 105 * ONE);
+
+        ledger.apply_order_update(
+            &update(Uuid::new_v4(), Side::Sell, "120", "3", OrderStatus::Filled),
+            key(),
+            fee_rate,
+        );
+        let position = ledger.position(key());
+        assert_eq!(position.net_qty_raw, -(ONE as i128));
+        assert_eq!(position.avg_entry_price_raw, 120 * ONE);
+        assert_eq!(position.realized_pnl_raw, 30 * ONE as i128);
+        assert_eq!(position.fees_raw, 570_000_000);
+    }
+
+    #[test]
+    fn unrealized_pnl_follows_the_mark() {
+        let mut ledger = PositionLedger::default();
+        ledger.apply_order_update(
+            &update(Uuid::new_v4(), Side::Sell, "100", "2", OrderStatus::Filled),
+            key(),
+            0,
+        );
+        assert_eq!(ledger.position(key()).unrealized_pnl_raw(), 0);
+
+        ledger.mark(Uuid::nil(), 97 * ONE);
+
+        let position = ledger.position(key());
+        assert_eq!(position.unrealized_pnl_raw(), 6 * ONE as i128);
+        assert_eq!(position.total_pnl_raw(), 6 * ONE as i128);
+    }
+
+    #[test]
+    fn updates_without_fills_are_ignored() {
+        let mut ledger = PositionLedger::default();
+        let id = Uuid::new_v4();
+
+        assert!(
+            ledger
+                .apply_order_update(
+                    &update(id, Side::Buy, "100", "0", OrderStatus::New),
+                    key(),
+                    0
+                )
+                .is_none()
+        );
+        assert!(
+            ledger
+                .apply_order_update(
+                    &update(id, Side::Buy, "100", "0", OrderStatus::Canceled),
+                    key(),
+                    0
+                )
+                .is_none()
+        );
+        assert_eq!(ledger.position(key()), Default::default());
+    }
+
+    #[test]
+    fn replayed_terminal_updates_are_not_booked_again() {
+        let mut ledger = PositionLedger::default();
+        let id = Uuid::new_v4();
+        let filled = update(id, Side::Buy, "100", "1", OrderStatus::Filled);
+
+        assert!(ledger.apply_order_update(&filled, key(), 0).is_some());
+        assert!(ledger.apply_order_update(&filled, key(), 0).is_none());
+        assert!(
+            ledger
+                .apply_order_update(
+                    &update(id, Side::Buy, "100", "0.5", OrderStatus::FilledPartial),
+                    key(),
+                    0
+                )
+                .is_none()
+        );
+        assert_eq!(ledger.position(key()).net_qty_raw, ONE as i128);
+    }
+
+    #[test]
+    fn fills_missing_side_or_price_are_booked_once_complete() {
+        let mut ledger = PositionLedger::default();
+        let id = Uuid::new_v4();
+        let mut partial = update(id, Side::Sell, "100", "0.5", OrderStatus::FilledPartial);
+        partial.side = None;
+
+        assert!(ledger.apply_order_update(&partial, key(), 0).is_none());
+        let fill = ledger
+            .apply_order_update(
+                &update(id, Side::Sell, "100", "0.5", OrderStatus::FilledPartial),
+                key(),
+                0,
+            )
+            .unwrap();
+        assert_eq!(fill.qty_raw, 500_000_000);
+    }
+
+    #[test]
+    fn seeded_position_books_only_fills_after_the_seed() {
+        let mut ledger = PositionLedger::default();
+        let held = PositionData {
+            product_id: Uuid::nil(),
+            side: 1,
+            size: BigDecimal::from(2),
+            cost: Some(BigDecimal::from(-210)),
+        };
+        ledger.seed(key(), Position::from_exchange(&held).unwrap());
+        assert_eq!(ledger.position(key()).net_qty_raw, -2 * ONE as i128);
+        assert_eq!(ledger.position(key()).avg_entry_price_raw, 105 * ONE);
+
+        let id = Uuid::new_v4();
+        ledger.mark_booked(id, ONE / 2);
+        let fill = ledger
+            .apply_order_update(
+                &update(id, Side::Sell, "105", "1", OrderStatus::Filled),
+                key(),
+                0,
+            )
+            .unwrap();
+        assert_eq!(fill.qty_raw, ONE / 2);
+        assert_eq!(ledger.position(key()).net_qty_raw, -5 * ONE as i128 / 2);
+    }
+}
@@ -1,11 +1,18 @@
+use std::path::PathBuf;
+
 use uuid::Uuid;
 
 pub use crate::models::common::TimeInForce;
 
 #[derive(Debug, Clone, serde::Deserialize)]
 pub struct StrategyConfig {
+    /// Which built-in strategy drives the loop.
+    #[serde(default)]
+    pub kind: StrategyKind,
     pub subaccount: Uuid,
     pub product_id: Uuid,
+    /// Left at 0, it is loaded from `/v1/product` at startup; otherwise it must match.
+    #[serde(default)]
     pub onchain_product_id: u32,
     #[serde(deserialize_with = "deserialize_u128_config")]
     pub qty_raw: u128,
@@ -13,16 +20,283 @@ pub struct StrategyConfig {
     pub post_only: bool,
     #[serde(default)]
     pub time_in_force: TimeInForce,
-    #[serde(
-        default = "default_tick_size_raw",
-        deserialize_with = "deserialize_u128_config"
-    )]
+    /// Left at 0, it is loaded from `/v1/product` at startup; otherwise it must match.
+    #[serde(default, deserialize_with = "deserialize_u128_config")]
     pub tick_size_raw: u128,
+    /// Left at 0, it is loaded from `/v1/product` at startup; otherwise it must match.
+    #[serde(default, deserialize_with = "deserialize_u128_config")]
+    pub lot_size_raw: u128,
+    /// Left at 0, it is loaded from `/v1/product` at startup; otherwise it must match.
+    #[serde(default, deserialize_with = "deserialize_u128_config")]
+    pub min_qty_raw: u128,
     #[serde(default = "default_min_spread_ticks")]
     pub min_spread_ticks: u32,
+    /// Whether quotes join the touch or step one tick inside it.
+    #[serde(default)]
+    pub placement: QuotePlacement,
+    /// Ticks to quote behind the placed price, away from the mid.
+    #[serde(default)]
+    pub offset_ticks: u32,
+    /// Smallest price move, in ticks, that replaces a resting quote.
+    #[serde(default = "default_requote_min_ticks")]
+    pub requote_min_ticks: u32,
+    /// How long a quote rests before it may be replaced; cancels are not held back.
+    #[serde(default)]
+    pub min_quote_lifetime_ms: u64,
+    #[serde(default)]
+    pub quote_mode: QuoteMode,
+    #[serde(default)]
+    pub inventory: InventoryConfig,
+    #[serde(default)]
+    pub oracle: OracleConfig,
+    #[serde(default)]
+    pub adaptive_spread: AdaptiveSpreadConfig,
+    #[serde(default)]
+    pub ladder: LadderConfig,
+    /// How long shutdown waits for cancelled orders to report a terminal status.
+    #[serde(default = "default_shutdown_timeout_ms")]
+    pub shutdown_timeout_ms: u64,
+    /// Fee per unit of filled notional in raw units (1_000_000_000 = 100%).
+    /// Left at 0, it is loaded from the product's maker fee at startup.
+    #[serde(default, deserialize_with = "deserialize_u128_config")]
+    pub fee_rate_raw: u128,
+    /// What to do with orders already resting on the product when the strategy starts.
+    #[serde(default)]
+    pub startup_orders: StartupOrderPolicy,
+    #[serde(default)]
+    pub halt: HaltConfig,
+    #[serde(default)]
+    pub stale_orders: StaleOrderConfig,
+    /// Both sides are pulled when no market tick arrives for the product
+    /// within this long, until ticks resume; 0 disables. Not applied by the
+    /// backtest.
+    #[serde(default = "default_market_data_timeout_ms")]
+    pub market_data_timeout_ms: u64,
 }
 
-fn deserialize_u128_config<'de, D>(deserializer: D) -> Result<u128, D::Error>
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StrategyKind {
+    /// Two-sided quoting around the touch, see [`QuoteMode`].
+    #[default]
+    MarketMaker,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StartupOrderPolicy {
+    /// Track the newest order per side as the live quote and cancel the rest.
+    Adopt,
+    /// Cancel everything before quoting.
+    #[default]
+    Cancel,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuoteMode {
+    /// Join the best bid and best ask with `qty_raw`.
+    #[default]
+    Touch,
+    /// Shift both quotes by the Avellaneda-Stoikov reservation price offset
+    /// `-q * risk_aversion * volatility^2 * horizon_secs` and shrink the side
+    /// that would grow the position.
+    InventorySkew,
+    /// Centre both quotes on `oracle_price`, `oracle.half_spread_ticks` away
+    /// on each side, kept passive against the book.
+    Oracle,
+}
+
+/// Widens `min_spread_ticks` and `offset_ticks` by the mid's expected move
+/// over `horizon_secs`, measured in ticks from an EWMA of log returns and
+/// clamped to `[floor_ticks, cap_ticks]`.
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct AdaptiveSpreadConfig {
+    /// Half-life of the volatility estimate; 0 disables the widening.
+    #[serde(default)]
+    pub half_life_ms: u64,
+    #[serde(default = "default_adaptive_horizon_secs")]
+    pub horizon_secs: f64,
+    #[serde(default)]
+    pub floor_ticks: u32,
+    #[serde(default = "default_adaptive_cap_ticks")]
+    pub cap_ticks: u32,
+}
+
+impl Default for AdaptiveSpreadConfig {
+    fn default() -> Self {
+        Self {
+            half_life_ms: 0,
+            horizon_secs: default_adaptive_horizon_secs(),
+            floor_ticks: 0,
+            cap_ticks: default_adaptive_cap_ticks(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct OracleConfig {
+    /// Distance of each quote from the oracle price under [`QuoteMode::Oracle`].
+    #[serde(default = "default_half_spread_ticks")]
+    pub half_spread_ticks: u32,
+    /// Both sides are cancelled, in every quote mode, while the book mid is
+    /// further than this from the oracle price, in basis points; 0 disables.
+    #[serde(default)]
+    pub max_deviation_bps: u32,
+}
+
+impl Default for OracleConfig {
+    fn default() -> Self {
+        Self {
+            half_spread_ticks: default_half_spread_ticks(),
+            max_deviation_bps: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuotePlacement {
+    /// Quote at the best bid and best ask.
+    #[default]
+    Join,
+    /// Quote one tick inside the touch on both sides when the spread is at
+    /// least three ticks, so the quotes stay apart; join otherwise.
+    Improve,
+}
+
+/// Quotes stacked behind the first on each side. Level 0 is the quote the
+/// quote mode prices; each further level sits `spacing_ticks` deeper.
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct LadderConfig {
+    #[serde(default = "default_ladder_levels")]
+    pub levels: u32,
+    #[serde(default = "default_ladder_spacing_ticks")]
+    pub spacing_ticks: u32,
+    #[serde(default)]
+    pub sizes: SizeProfile,
+}
+
+impl Default for LadderConfig {
+    fn default() -> Self {
+        Self {
+            levels: default_ladder_levels(),
+            spacing_ticks: default_ladder_spacing_ticks(),
+            sizes: SizeProfile::default(),
+        }
+    }
+}
+
+/// Size of each ladder level as a multiple of the quote size, rounded down
+/// to the lot; levels below the minimum quantity are not quoted.
+#[derive(Debug, Clone, PartialEq, Default, serde::Deserialize)]
+#[serde(tag = "profile", rename_all = "snake_case")]
+pub enum SizeProfile {
+    /// Every level the same size.
+    #[default]
+    Flat,
+    /// Level `i` is `ratio^i` times the quote size, so level 0 is unscaled.
+    Geometric { ratio: f64 },
+    /// Level `i` is `multipliers[i]` times the quote size, level 0
+    /// included; levels past the end of the list are not quoted.
+    Custom { multipliers: Vec<f64> },
+}
+
+impl SizeProfile {
+    pub fn multiplier(&self, level: u32) -> Option<f64> {
+        match self {
+            Self::Flat => Some(1.0),
+            Self::Geometric { ratio } => Some(ratio.powi(level as i32)),
+            Self::Custom { multipliers } => multipliers.get(level as usize).copied(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct InventoryConfig {
+    /// Net position at which the side that would grow it stops quoting, in
+    /// every quote mode; 0 disables the limit.
+    #[serde(default, deserialize_with = "deserialize_u128_config")]
+    pub max_position_raw: u128,
+    /// Risk aversion `gamma`.
+    #[serde(default = "default_risk_aversion")]
+    pub risk_aversion: f64,
+    /// Price volatility `sigma`, in quote currency per square root of a second.
+    #[serde(default)]
+    pub volatility: f64,
+    /// Remaining horizon `T - t`; held constant since the strategy has no session end.
+    #[serde(default = "default_horizon_secs")]
+    pub horizon_secs: f64,
+}
+
+impl Default for InventoryConfig {
+    fn default() -> Self {
+        Self {
+            max_position_raw: 0,
+            risk_aversion: default_risk_aversion(),
+            volatility: 0.0,
+            horizon_secs: default_horizon_secs(),
+        }
+    }
+}
+
+/// When trading stops without stopping the process. Every halt cancels all
+/// working orders on the product and lasts until the operator resumes it.
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct HaltConfig {
+    /// Trading on this product, and any other configured with the same file,
+    /// halts while this file exists and resumes once it is removed.
+    #[serde(default)]
+    pub halt_file: Option<PathBuf>,
+    #[serde(default = "default_halt_file_poll_ms")]
+    pub halt_file_poll_ms: u64,
+    /// Consecutive ticks whose order was rejected by the exchange; 0 disables.
+    /// Post-only orders that would cross and rate limits are not counted.
+    #[serde(default = "default_max_consecutive_rejections")]
+    pub max_consecutive_rejections: u32,
+    /// Quoting pauses this long after the exchange rate limits a request,
+    /// without cancelling anything; 0 disables.
+    #[serde(default = "default_rate_limit_backoff_ms")]
+    pub rate_limit_backoff_ms: u64,
+    /// Drop of total PnL below its high-water mark; 0 disables.
+    #[serde(default, deserialize_with = "deserialize_u128_config")]
+    pub max_drawdown_raw: u128,
+}
+
+impl Default for HaltConfig {
+    fn default() -> Self {
+        Self {
+            halt_file: None,
+            halt_file_poll_ms: default_halt_file_poll_ms(),
+            max_consecutive_rejections: default_max_consecutive_rejections(),
+            rate_limit_backoff_ms: default_rate_limit_backoff_ms(),
+            max_drawdown_raw: 0,
+        }
+    }
+}
+
+/// Recovery for orders whose exchange updates never arrive.
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct StaleOrderConfig {
+    /// How often tracked orders are checked; 0 disables the check.
+    #[serde(default = "default_stale_check_interval_ms")]
+    pub check_interval_ms: u64,
+    /// How long an order may wait for its confirmation or its cancel before
+    /// it is checked against the exchange's working orders over REST.
+    #[serde(default = "default_stale_order_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+impl Default for StaleOrderConfig {
+    fn default() -> Self {
+        Self {
+            check_interval_ms: default_stale_check_interval_ms(),
+            timeout_ms: default_stale_order_timeout_ms(),
+        }
+    }
+}
+
+pub(crate) fn deserialize_u128_config<'de, D>(deserializer: D) -> Result<u128, D::Error>
 where
     D: serde::Deserializer<'de>,
 {
@@ -93,10 +367,66 @@ fn default_post_only() -> bool {
     true
 }
 
-fn default_tick_size_raw() -> u128 {
+fn default_min_spread_ticks() -> u32 {
     1
 }
 
-fn default_min_spread_ticks() -> u32 {
+fn default_requote_min_ticks() -> u32 {
+    1
+}
+
+fn default_adaptive_horizon_secs() -> f64 {
+    1.0
+}
+
+fn default_adaptive_cap_ticks() -> u32 {
+    10
+}
+
+fn default_half_spread_ticks() -> u32 {
+    1
+}
+
+fn default_ladder_levels() -> u32 {
+    1
+}
+
+fn default_ladder_spacing_ticks() -> u32 {
     1
 }
+
+fn default_risk_aversion() -> f64 {
+    0.1
+}
+
+fn default_horizon_secs() -> f64 {
+    60.0
+}
+
+fn default_halt_file_poll_ms() -> u64 {
+    1_000
+}
+
+fn default_max_consecutive_rejections() -> u32 {
+    5
+}
+
+fn default_rate_limit_backoff_ms() -> u64 {
+    1_000
+}
+
+fn default_stale_check_interval_ms() -> u64 {
+    1_000
+}
+
+fn default_stale_order_timeout_ms() -> u64 {
+    5_000
+}
+
+fn default_market_data_timeout_ms() -> u64 {
+    10_000
+}
+
+fn default_shutdown_timeout_ms() -> u64 {
+    5_000
+}
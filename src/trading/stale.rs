@@ -0,0 +1,395 @@
+//! Recovery from updates that never arrive. Orders left waiting for their
+//! confirmation or their cancel past the timeout are checked against the
+//! exchange's working orders and brought in line with them; orders it no
+//! longer lists are looked up one by one and their final update replayed. A
+//! product whose market data goes quiet is marked stale until ticks resume.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use uuid::Uuid;
+
+use super::settings::StaleOrderConfig;
+use super::state::{OrderState, OrderTracker, StrategyState};
+use crate::logging::targets;
+use crate::models::dto::OpenOrderData;
+use crate::models::util::decimal_to_raw;
+
+/// Remembers when each tracked order entered its current state.
+#[derive(Debug)]
+pub(crate) struct StaleOrderWatch {
+    timeout: Duration,
+    observed: HashMap<Uuid, (OrderState, Instant)>,
+}
+
+impl StaleOrderWatch {
+    pub fn new(config: &StaleOrderConfig) -> Self {
+        Self {
+            timeout: Duration::from_millis(config.timeout_ms),
+            observed: HashMap::new(),
+        }
+    }
+
+    /// Every order that has not changed state for longer than the timeout,
+    /// but only once a pending one is among them; empty otherwise.
+    pub fn overdue(&mut self, orders: &OrderTracker, now: Instant) -> Vec<Uuid> {
+        self.observed
+            .retain(|client_order_id, _| orders.get(*client_order_id).is_some());
+        for order in orders.iter() {
+            let entry = self
+                .observed
+                .entry(order.client_order_id)
+                .or_insert((order.state, now));
+            if entry.0 != order.state {
+                *entry = (order.state, now);
+            }
+        }
+
+        let overdue: Vec<(Uuid, OrderState)> = self
+            .observed
+            .iter()
+            .filter(|(_, (_, since))| now.saturating_duration_since(*since) > self.timeout)
+            .map(|(client_order_id, (state, _))| (*client_order_id, *state))
+            .collect();
+        let pending = overdue.iter().any(|(_, state)| {
+            matches!(
+                state,
+                OrderState::PendingNew | OrderState::PendingCancel { .. }
+            )
+        });
+
+        if !pending {
+            return Vec::new();
+        }
+        overdue
+            .into_iter()
+            .map(|(client_order_id, _)| client_order_id)
+            .collect()
+    }
+}
+
+/// Tracks whether a product's market data is older than the timeout.
+#[derive(Debug)]
+pub(crate) struct MarketDataWatch {
+    timeout: Option<Duration>,
+    last_tick: Instant,
+    stale: bool,
+}
+
+impl MarketDataWatch {
+    /// A zero `timeout_ms` disables the watch; the clock starts at `now`.
+    pub fn new(timeout_ms: u64, now: Instant) -> Self {
+        Self {
+            timeout: (timeout_ms > 0).then(|| Duration::from_millis(timeout_ms)),
+            last_tick: now,
+            stale: false,
+        }
+    }
+
+    pub fn is_stale(&self) -> bool {
+        self.stale
+    }
+
+    /// When the data goes stale without another tick; `None` when disabled
+    /// or already stale.
+    pub fn deadline(&self) -> Option<Instant> {
+        if self.stale {
+            return None;
+        }
+        self.timeout.map(|timeout| self.last_tick + timeout)
+    }
+
+    /// Returns `true` when this tick ends a stale period.
+    pub fn on_tick(&mut self, now: Instant) -> bool {
+        self.last_tick = now;
+        std::mem::take(&mut self.stale)
+    }
+
+    /// Returns `true` when the data has just gone stale.
+    pub fn expire(&mut self, now: Instant) -> bool {
+        match self.deadline() {
+            Some(deadline) if now >= deadline => {
+                self.stale = true;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// What [`resolve_stale_orders`] changed.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct StaleOrderPlan {
+    /// No longer working on the exchange; to be looked up for how they ended.
+    pub missing: Vec<Uuid>,
+    /// Still working despite an accepted cancel; to be cancelled again.
+    pub recancel: Vec<Uuid>,
+    /// Working with fills the strategy has not seen; to be replayed as order
+    /// updates so the fills are booked like any other.
+    pub unbooked: Vec<OpenOrderData>,
+}
+
+/// Brings each `overdue` order in line with `working`, the exchange's
+/// working orders for the product. Unconfirmed orders that are working
+/// become open, or partially filled, and cancels that did not take are reset
+/// for a retry. Orders not working are left tracked until their update is
+/// recovered.
+pub(crate) fn resolve_stale_orders(
+    state: &mut StrategyState,
+    overdue: &[Uuid],
+    working: &[OpenOrderData],
+) -> StaleOrderPlan {
+    let mut plan = StaleOrderPlan::default();
+
+    for &client_order_id in overdue {
+        let Some(order) = state.orders.get_mut(client_order_id) else {
+            continue;
+        };
+        let Some(exchange_order) = working
+            .iter()
+            .find(|working| working.client_order_id == Some(client_order_id))
+        else {
+            plan.missing.push(client_order_id);
+            continue;
+        };
+        let exchange_filled_raw = decimal_to_raw(&exchange_order.filled).unwrap_or_default();
+        if exchange_filled_raw > order.state.filled_raw() {
+            plan.unbooked.push(exchange_order.clone());
+        }
+
+        match order.state {
+            OrderState::PendingNew => {
+                order.apply_status(exchange_order.status, Some(exchange_filled_raw));
+                tracing::info!(
+                    target: targets::TRADING_DECISION,
+                    %client_order_id,
+                    order_state = ?order.state,
+                    "order confirmed over REST"
+                );
+            }
+            OrderState::PendingCancel { filled_raw } => {
+                order.state = match filled_raw.max(exchange_filled_raw).min(order.qty_raw) {
+                    0 => OrderState::Open,
+                    filled_raw => OrderState::PartiallyFilled {
+                        filled_raw,
+                        remaining_raw: order.qty_raw.saturating_sub(filled_raw),
+                    },
+                };
+                tracing::warn!(
+                    target: targets::TRADING_DECISION,
+                    %client_order_id,
+                    "cancel never confirmed and order still working; retrying"
+                );
+                plan.recancel.push(client_order_id);
+            }
+            _ => {}
+        }
+    }
+
+    plan
+}
+
+/// Stops tracking an order the exchange has no record of.
+pub(crate) fn drop_unknown_order(state: &mut StrategyState, client_order_id: Uuid) {
+    let Some(order) = state.orders.remove(client_order_id) else {
+        return;
+    };
+    tracing::warn!(
+        target: targets::TRADING_DECISION,
+        %client_order_id,
+        order_state = ?order.state,
+        "order unknown to the exchange; dropping it"
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use std::time::{Duration, Instant};
+
+    use bigdecimal::BigDecimal;
+    use uuid::Uuid;
+
+    use super::{MarketDataWatch, StaleOrderPlan, StaleOrderWatch, resolve_stale_orders};
+    use crate::models::common::OrderStatus;
+    use crate::models::dto::OpenOrderData;
+    use crate::trading::position::PositionKey;
+    use crate::trading::reconcile_order_update;
+    use crate::trading::settings::StaleOrderConfig;
+    use crate::trading::state::{OrderState, Side, StrategyState, TrackedOrder};
+
+    fn track(state: &mut StrategyState, order_state: OrderState) -> Uuid {
+        let client_order_id = Uuid::new_v4();
+        state.orders.insert(TrackedOrder {
+            client_order_id,
+            side: Side::Buy,
+            level: 0,
+            price_raw: 100_000_000_000,
+            qty_raw: 100_000_000,
+            state: order_state,
+            created_at_ms: 0,
+        });
+        client_order_id
+    }
+
+    fn working(client_order_id: Uuid, status: OrderStatus) -> OpenOrderData {
+        OpenOrderData {
+            id: Uuid::new_v4(),
+            client_order_id: Some(client_order_id),
+            product_id: Uuid::new_v4(),
+            side: 0,
+            price: BigDecimal::from_str("100").unwrap(),
+            quantity: BigDecimal::from_str("0.1").unwrap(),
+            filled: BigDecimal::default(),
+            status,
+            created_at: 0,
+        }
+    }
+
+    #[test]
+    fn only_pending_orders_past_the_timeout_trigger_a_check() {
+        let mut watch = StaleOrderWatch::new(&StaleOrderConfig {
+            check_interval_ms: 1_000,
+            timeout_ms: 5_000,
+        });
+        let mut state = StrategyState::default();
+        let open_id = track(&mut state, OrderState::Open);
+        let start = Instant::now();
+
+        assert!(watch.overdue(&state.orders, start).is_empty());
+        assert!(
+            watch
+                .overdue(&state.orders, start + Duration::from_secs(6))
+                .is_empty()
+        );
+
+        let pending_id = track(&mut state, OrderState::PendingNew);
+        assert!(
+            watch
+                .overdue(&state.orders, start + Duration::from_secs(7))
+                .is_empty()
+        );
+        let mut overdue = watch.overdue(&state.orders, start + Duration::from_secs(13));
+        overdue.sort();
+        let mut expected = vec![open_id, pending_id];
+        expected.sort();
+        assert_eq!(overdue, expected);
+
+        state.orders.get_mut(pending_id).unwrap().state = OrderState::Open;
+        assert!(
+            watch
+                .overdue(&state.orders, start + Duration::from_secs(14))
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn stale_orders_follow_the_exchange_working_orders() {
+        let mut state = StrategyState::default();
+        let missing_id = track(&mut state, OrderState::PendingNew);
+        let confirmed_id = track(&mut state, OrderState::PendingNew);
+        let stuck_cancel_id = track(&mut state, OrderState::PendingCancel {
+            filled_raw: 40_000_000,
+        });
+        let working = [
+            working(confirmed_id, OrderStatus::New),
+            working(stuck_cancel_id, OrderStatus::FilledPartial),
+        ];
+
+        let plan = resolve_stale_orders(
+            &mut state,
+            &[missing_id, confirmed_id, stuck_cancel_id],
+            &working,
+        );
+
+        assert_eq!(plan, StaleOrderPlan {
+            missing: vec![missing_id],
+            recancel: vec![stuck_cancel_id],
+            unbooked: vec![],
+        });
+        assert!(state.orders.get(missing_id).is_some());
+        assert_eq!(
+            state.orders.get(confirmed_id).unwrap().state,
+            OrderState::Open
+        );
+        assert_eq!(
+            state.orders.get(stuck_cancel_id).unwrap().state,
+            OrderState::PartiallyFilled {
+                filled_raw: 40_000_000,
+                remaining_raw: 60_000_000,
+            }
+        );
+    }
+
+    #[test]
+    fn missing_order_that_filled_is_booked_from_its_recovered_update() {
+        let mut state = StrategyState::default();
+        let client_order_id = track(&mut state, OrderState::PendingCancel { filled_raw: 0 });
+
+        let plan = resolve_stale_orders(&mut state, &[client_order_id], &[]);
+        assert_eq!(plan.missing, [client_order_id]);
+
+        let mut listed = working(client_order_id, OrderStatus::Filled);
+        listed.filled = listed.quantity.clone();
+        let update = listed.to_update(Uuid::nil());
+        let key = PositionKey {
+            subaccount_id: Uuid::nil(),
+            product_id: listed.product_id,
+        };
+        let fill = state.positions.apply_order_update(&update, key, 0).unwrap();
+        reconcile_order_update(&mut state, &update);
+
+        assert_eq!(fill.qty_raw, 100_000_000);
+        assert_eq!(state.positions.position(key).net_qty_raw, 100_000_000);
+        assert!(state.orders.get(client_order_id).is_none());
+    }
+
+    #[test]
+    fn fill_found_on_a_working_order_is_booked_from_its_update() {
+        let mut state = StrategyState::default();
+        let client_order_id = track(&mut state, OrderState::PendingNew);
+        let mut listed = working(client_order_id, OrderStatus::FilledPartial);
+        listed.filled = BigDecimal::from_str("0.04").unwrap();
+
+        let plan = resolve_stale_orders(&mut state, &[client_order_id], &[listed.clone()]);
+        assert_eq!(
+            state.orders.get(client_order_id).unwrap().state,
+            OrderState::PartiallyFilled {
+                filled_raw: 40_000_000,
+                remaining_raw: 60_000_000,
+            }
+        );
+        assert_eq!(plan.unbooked, [listed.clone()]);
+
+        let update = plan.unbooked[0].to_update(Uuid::nil());
+        let key = PositionKey {
+            subaccount_id: Uuid::nil(),
+            product_id: listed.product_id,
+        };
+        state.positions.apply_order_update(&update, key, 0).unwrap();
+        reconcile_order_update(&mut state, &update);
+        assert_eq!(state.positions.position(key).net_qty_raw, 40_000_000);
+    }
+
+    #[test]
+    fn market_data_goes_stale_without_ticks_and_recovers_on_the_next() {
+        let start = Instant::now();
+        let mut watch = MarketDataWatch::new(1_000, start);
+
+        assert!(!watch.expire(start + Duration::from_millis(999)));
+        assert!(!watch.on_tick(start + Duration::from_millis(999)));
+        assert!(!watch.expire(start + Duration::from_millis(1_500)));
+        assert!(watch.expire(start + Duration::from_millis(2_000)));
+        assert!(watch.is_stale());
+        assert_eq!(watch.deadline(), None);
+        assert!(!watch.expire(start + Duration::from_millis(3_000)));
+
+        assert!(watch.on_tick(start + Duration::from_millis(3_000)));
+        assert!(!watch.is_stale());
+        assert_eq!(watch.deadline(), Some(start + Duration::from_millis(4_000)));
+
+        let mut disabled = MarketDataWatch::new(0, start);
+        assert_eq!(disabled.deadline(), None);
+        assert!(!disabled.expire(start + Duration::from_secs(3_600)));
+    }
+}
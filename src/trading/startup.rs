@@ -0,0 +1,240 @@
+use uuid::Uuid;
+
+use super::log_position;
+use super::position::{Position, PositionKey};
+use super::settings::{StartupOrderPolicy, StrategyConfig};
+use super::state::{OrderState, Side, StrategyState, TrackedOrder};
+use crate::logging::targets;
+use crate::models::dto::{OpenOrderData, PositionData};
+use crate::models::util::decimal_to_raw;
+use crate::{EtherealRuntime, EtherealRuntimeError};
+
+/// What to do with the orders found resting when the strategy starts.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct StartupPlan {
+    /// Orders to track as the live quotes.
+    pub adopt: Vec<TrackedOrder>,
+    /// Exchange order ids to cancel.
+    pub cancel: Vec<Uuid>,
+}
+
+/// Under [`StartupOrderPolicy::Adopt`] keeps the newest order per side, in
+/// the state and with the fill the exchange reports, and cancels the rest. Orders without a client order id, or with a price or
+/// quantity that does not fit raw units, cannot be tracked and are always
+/// cancelled.
+pub(crate) fn plan_startup(policy: StartupOrderPolicy, orders: &[OpenOrderData]) -> StartupPlan {
+    let mut plan = StartupPlan::default();
+    let mut newest: [Option<&OpenOrderData>; 2] = [None, None];
+
+    for order in orders {
+        let adoptable = policy == StartupOrderPolicy::Adopt
+            && order.client_order_id.is_some()
+            && order.side <= Side::Sell as u8
+            && decimal_to_raw(&order.price).is_some()
+            && decimal_to_raw(&order.quantity).is_some();
+        if !adoptable {
+            plan.cancel.push(order.id);
+            continue;
+        }
+
+        let slot = &mut newest[order.side as usize];
+        match slot {
+            Some(current) if current.created_at >= order.created_at => plan.cancel.push(order.id),
+            _ => {
+                if let Some(replaced) = slot.replace(order) {
+                    plan.cancel.push(replaced.id);
+                }
+            }
+        }
+    }
+
+    for (side, order) in [Side::Buy, Side::Sell].into_iter().zip(newest) {
+        if let Some(order) = order
+            && let (Some(client_order_id), Some(price_raw), Some(qty_raw)) = (
+                order.client_order_id,
+                decimal_to_raw(&order.price),
+                decimal_to_raw(&order.quantity),
+            )
+        {
+            let mut tracked = TrackedOrder {
+                client_order_id,
+                side,
+                level: 0,
+                price_raw,
+                qty_raw,
+                state: OrderState::PendingNew,
+                created_at_ms: order.created_at,
+            };
+            tracked.apply_status(order.status, decimal_to_raw(&order.filled));
+            if !tracked.state.is_terminal() {
+                plan.adopt.push(tracked);
+            }
+        }
+    }
+
+    plan
+}
+
+/// Starts the strategy's position from the one the exchange reports for its
+/// product, so inventory skew and position limits hold across restarts.
+pub(crate) fn seed_position(
+    config: &StrategyConfig,
+    state: &mut StrategyState,
+    positions: &[PositionData],
+) {
+    let key = PositionKey::for_strategy(config);
+    let position = positions
+        .iter()
+        .filter(|data| data.product_id == config.product_id)
+        .find_map(Position::from_exchange)
+        .unwrap_or_default();
+    state.positions.seed(key, position);
+    log_position("starting position", key, &state.positions.position(key));
+}
+
+/// Brings `state` in line with the orders the exchange still has working, so
+/// quotes left behind by a previous run are neither orphaned nor doubled up.
+/// What those orders filled before the restart is already in the seeded
+/// position, so it is counted as booked.
+pub(crate) async fn reconcile_open_orders(
+    runtime: &EtherealRuntime,
+    config: &StrategyConfig,
+    state: &mut StrategyState,
+) -> Result<(), EtherealRuntimeError> {
+    let orders = runtime
+        .open_orders(config.subaccount, config.product_id)
+        .await?;
+    for order in &orders {
+        if let Some(filled_raw) = decimal_to_raw(&order.filled) {
+            state.positions.mark_booked(order.id, filled_raw);
+        }
+    }
+    let plan = plan_startup(config.startup_orders, &orders);
+
+    tracing::info!(
+        target: targets::TRADING_DECISION,
+        policy = ?config.startup_orders,
+        open_orders = orders.len(),
+        adopted = plan.adopt.len(),
+        cancelled = plan.cancel.len(),
+        "reconciled open orders at startup"
+    );
+
+    runtime.cancel_orders_by_id(plan.cancel).await?;
+
+    for order in plan.adopt {
+        tracing::info!(
+            target: targets::TRADING_DECISION,
+            side = %order.side,
+            client_order_id = %order.client_order_id,
+            price_raw = order.price_raw,
+            qty_raw = order.qty_raw,
+            "adopted resting order"
+        );
+        runtime.adopt_order(config.subaccount, config.product_id, &order);
+        state.orders.insert(order);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use bigdecimal::BigDecimal;
+    use uuid::Uuid;
+
+    use super::{StartupPlan, plan_startup};
+    use crate::models::common::OrderStatus;
+    use crate::models::dto::OpenOrderData;
+    use crate::trading::settings::StartupOrderPolicy;
+    use crate::trading::state::{OrderState, Side, TrackedOrder};
+
+    fn open_order(side: u8, price: &str, created_at: u64) -> OpenOrderData {
+        OpenOrderData {
+            id: Uuid::new_v4(),
+            client_order_id: Some(Uuid::new_v4()),
+            product_id: Uuid::nil(),
+            side,
+            price: BigDecimal::from_str(price).unwrap(),
+            quantity: BigDecimal::from_str("0.1").unwrap(),
+            filled: BigDecimal::default(),
+            status: OrderStatus::New,
+            created_at,
+        }
+    }
+
+    #[test]
+    fn cancel_policy_cancels_everything() {
+        let orders = [open_order(0, "100", 1), open_order(1, "101", 2)];
+
+        let plan = plan_startup(StartupOrderPolicy::Cancel, &orders);
+
+        assert_eq!(plan, StartupPlan {
+            adopt: vec![],
+            cancel: vec![orders[0].id, orders[1].id],
+        });
+    }
+
+    #[test]
+    fn adopt_policy_keeps_newest_per_side() {
+        let newer_buy = open_order(0, "100", 3);
+        let older_buy = open_order(0, "99", 1);
+        let sell = open_order(1, "101.5", 2);
+        let orders = [newer_buy.clone(), older_buy.clone(), sell.clone()];
+
+        let plan = plan_startup(StartupOrderPolicy::Adopt, &orders);
+
+        assert_eq!(plan.adopt, vec![
+            TrackedOrder {
+                client_order_id: newer_buy.client_order_id.unwrap(),
+                side: Side::Buy,
+                level: 0,
+                price_raw: 100_000_000_000,
+                qty_raw: 100_000_000,
+                state: OrderState::Open,
+                created_at_ms: 3,
+            },
+            TrackedOrder {
+                client_order_id: sell.client_order_id.unwrap(),
+                side: Side::Sell,
+                level: 0,
+                price_raw: 101_500_000_000,
+                qty_raw: 100_000_000,
+                state: OrderState::Open,
+                created_at_ms: 2,
+            },
+        ]);
+        assert_eq!(plan.cancel, vec![older_buy.id]);
+    }
+
+    #[test]
+    fn adopt_policy_cancels_orders_it_cannot_track() {
+        let mut foreign = open_order(0, "100", 1);
+        foreign.client_order_id = None;
+        let negative = open_order(1, "-1", 2);
+
+        let plan = plan_startup(StartupOrderPolicy::Adopt, &[
+            foreign.clone(),
+            negative.clone(),
+        ]);
+
+        assert!(plan.adopt.is_empty());
+        assert_eq!(plan.cancel, vec![foreign.id, negative.id]);
+    }
+
+    #[test]
+    fn adopted_orders_keep_their_fill() {
+        let mut partial = open_order(0, "100", 1);
+        partial.status = OrderStatus::FilledPartial;
+        partial.filled = BigDecimal::from_str("0.04").unwrap();
+
+        let plan = plan_startup(StartupOrderPolicy::Adopt, &[partial]);
+
+        assert_eq!(plan.adopt[0].state, OrderState::PartiallyFilled {
+            filled_raw: 40_000_000,
+            remaining_raw: 60_000_000,
+        });
+    }
+}
@@ -1,5 +1,9 @@
+use std::collections::BTreeMap;
+
 use uuid::Uuid;
 
+use super::position::PositionLedger;
+use crate::models::common::OrderStatus;
 use crate::models::dto::MarketPriceData;
 
 #[repr(u8)]
@@ -10,32 +14,234 @@ pub enum Side {
     Sell = 1,
 }
 
+impl Side {
+    /// Parses the exchange's wire encoding (0 = buy, 1 = sell).
+    pub fn from_raw(side: u8) -> Option<Self> {
+        match side {
+            0 => Some(Self::Buy),
+            1 => Some(Self::Sell),
+            _ => None,
+        }
+    }
+}
+
+/// Where a tracked order is in its lifecycle, as far as the loop has seen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderState {
+    /// Submitted and accepted over REST; the exchange has not confirmed it yet.
+    PendingNew,
+    Open,
+    PartiallyFilled {
+        filled_raw: u128,
+        remaining_raw: u128,
+    },
+    /// A cancel was requested; waiting for the terminal update. The order can
+    /// still fill until then.
+    PendingCancel {
+        filled_raw: u128,
+    },
+    Filled,
+    Canceled,
+    Rejected,
+    Expired,
+}
+
+impl OrderState {
+    pub fn is_terminal(self) -> bool {
+        matches!(
+            self,
+            Self::Filled | Self::Canceled | Self::Rejected | Self::Expired
+        )
+    }
+
+    /// Confirmed resting on the book, so it may be replaced or cancelled.
+    pub fn is_working(self) -> bool {
+        matches!(self, Self::Open | Self::PartiallyFilled { .. })
+    }
+
+    /// Not terminal and no cancel outstanding.
+    pub fn is_cancelable(self) -> bool {
+        !self.is_terminal() && !matches!(self, Self::PendingCancel { .. })
+    }
+
+    pub fn filled_raw(self) -> u128 {
+        match self {
+            Self::PartiallyFilled { filled_raw, .. } | Self::PendingCancel { filled_raw } => {
+                filled_raw
+            }
+            _ => 0,
+        }
+    }
+}
+
+/// An order the strategy loop placed or adopted and has not seen terminate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrackedOrder {
+    pub client_order_id: Uuid,
+    pub side: Side,
+    /// Ladder level the order quotes, 0 being closest to the touch.
+    pub level: u32,
+    pub price_raw: u128,
+    /// Original size of the order.
+    pub qty_raw: u128,
+    pub state: OrderState,
+    /// When the order was placed, in unix milliseconds.
+    pub created_at_ms: u64,
+}
+
+impl TrackedOrder {
+    pub fn remaining_qty_raw(&self) -> u128 {
+        self.qty_raw.saturating_sub(self.state.filled_raw())
+    }
+
+    /// Advances the lifecycle from an exchange status and the cumulative
+    /// filled quantity it carried, if any. Updates that would move the order
+    /// backwards, e.g. a late `NEW` after a partial fill, are ignored.
+    pub fn apply_status(&mut self, status: OrderStatus, filled_raw: Option<u128>) {
+        let filled_raw = filled_raw
+            .unwrap_or_default()
+            .max(self.state.filled_raw())
+            .min(self.qty_raw);
+
+        self.state = match (status, self.state) {
+            (OrderStatus::Filled, _) => OrderState::Filled,
+            (OrderStatus::Canceled, _) => OrderState::Canceled,
+            (OrderStatus::Rejected, _) => OrderState::Rejected,
+            (OrderStatus::Expired, _) => OrderState::Expired,
+            (_, state) if state.is_terminal() => state,
+            (_, OrderState::PendingCancel { .. }) => OrderState::PendingCancel { filled_raw },
+            (OrderStatus::FilledPartial, _) => OrderState::PartiallyFilled {
+                filled_raw,
+                remaining_raw: self.qty_raw - filled_raw,
+            },
+            (OrderStatus::New | OrderStatus::Pending, OrderState::PendingNew) => OrderState::Open,
+            (OrderStatus::New | OrderStatus::Pending, state) => state,
+        };
+    }
+}
+
+/// Working orders in the order they were placed.
 #[derive(Debug, Clone, Default)]
-pub struct SideState {
-    pub active_client_order_id: Option<Uuid>,
-    pub last_quoted_price_raw: Option<u128>,
-    pub inflight: bool,
+pub struct OrderTracker {
+    orders: Vec<TrackedOrder>,
+}
+
+impl OrderTracker {
+    pub fn iter(&self) -> impl Iterator<Item = &TrackedOrder> {
+        self.orders.iter()
+    }
+
+    pub fn get(&self, client_order_id: Uuid) -> Option<&TrackedOrder> {
+        self.orders
+            .iter()
+            .find(|order| order.client_order_id == client_order_id)
+    }
+
+    pub fn get_mut(&mut self, client_order_id: Uuid) -> Option<&mut TrackedOrder> {
+        self.orders
+            .iter_mut()
+            .find(|order| order.client_order_id == client_order_id)
+    }
+
+    /// The oldest order on `side`.
+    pub fn first_on(&self, side: Side) -> Option<&TrackedOrder> {
+        self.orders.iter().find(|order| order.side == side)
+    }
+
+    /// The orders quoting `side` by ladder level: the oldest per level
+    /// without a cancel outstanding.
+    pub fn ladder(&self, side: Side) -> BTreeMap<u32, &TrackedOrder> {
+        let mut ladder = BTreeMap::new();
+        for order in &self.orders {
+            if order.side == side && order.state.is_cancelable() {
+                ladder.entry(order.level).or_insert(order);
+            }
+        }
+        ladder
+    }
+
+    pub fn insert(&mut self, order: TrackedOrder) {
+        self.orders.push(order);
+    }
+
+    pub fn remove(&mut self, client_order_id: Uuid) -> Option<TrackedOrder> {
+        let index = self
+            .orders
+            .iter()
+            .position(|order| order.client_order_id == client_order_id)?;
+        Some(self.orders.remove(index))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.orders.is_empty()
+    }
 }
 
 #[derive(Debug, Clone, Default)]
 pub struct StrategyState {
-    pub buy: SideState,
-    pub sell: SideState,
+    pub orders: OrderTracker,
     pub last_market: Option<MarketPriceData>,
+    pub positions: PositionLedger,
+    /// Time of the event being handled, in unix milliseconds.
+    pub now_ms: u64,
 }
 
-impl StrategyState {
-    pub fn side_state(&self, side: Side) -> &SideState {
-        match side {
-            Side::Buy => &self.buy,
-            Side::Sell => &self.sell,
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use super::{OrderState, Side, TrackedOrder};
+    use crate::models::common::OrderStatus;
+
+    fn pending_order() -> TrackedOrder {
+        TrackedOrder {
+            client_order_id: Uuid::new_v4(),
+            side: Side::Buy,
+            level: 0,
+            price_raw: 100,
+            qty_raw: 10,
+            state: OrderState::PendingNew,
+            created_at_ms: 0,
         }
     }
 
-    pub fn side_state_mut(&mut self, side: Side) -> &mut SideState {
-        match side {
-            Side::Buy => &mut self.buy,
-            Side::Sell => &mut self.sell,
-        }
+    #[test]
+    fn lifecycle_follows_exchange_updates() {
+        let mut order = pending_order();
+
+        order.apply_status(OrderStatus::New, None);
+        assert_eq!(order.state, OrderState::Open);
+
+        order.apply_status(OrderStatus::FilledPartial, Some(4));
+        assert_eq!(order.state, OrderState::PartiallyFilled {
+            filled_raw: 4,
+            remaining_raw: 6,
+        });
+        assert_eq!(order.remaining_qty_raw(), 6);
+
+        order.apply_status(OrderStatus::New, Some(0));
+        assert_eq!(order.state, OrderState::PartiallyFilled {
+            filled_raw: 4,
+            remaining_raw: 6,
+        });
+
+        order.apply_status(OrderStatus::Filled, Some(10));
+        assert_eq!(order.state, OrderState::Filled);
+        order.apply_status(OrderStatus::New, None);
+        assert_eq!(order.state, OrderState::Filled);
+    }
+
+    #[test]
+    fn pending_cancel_keeps_counting_fills_until_terminal() {
+        let mut order = pending_order();
+        order.apply_status(OrderStatus::New, None);
+        order.state = OrderState::PendingCancel { filled_raw: 0 };
+
+        order.apply_status(OrderStatus::FilledPartial, Some(3));
+        assert_eq!(order.state, OrderState::PendingCancel { filled_raw: 3 });
+        assert!(!order.state.is_cancelable());
+
+        order.apply_status(OrderStatus::Canceled, Some(3));
+        assert!(order.state.is_terminal());
     }
 }
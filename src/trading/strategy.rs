@@ -0,0 +1,154 @@
+//! The interface between the strategy loop and the trading logic. The loop
+//! owns order tracking, positions and execution; a [`Strategy`] only reads
+//! that state and answers each callback with the orders it wants.
+
+use std::time::Duration;
+
+use uuid::Uuid;
+
+use super::policy::MarketMaker;
+use super::position::{Fill, PositionLedger};
+use super::settings::{StrategyConfig, StrategyKind};
+use super::state::{OrderTracker, Side, StrategyState};
+use crate::models::dto::{MarketPriceData, OrderUpdateData};
+
+/// An order change requested by a [`Strategy`]. Each side's intents are
+/// executed in the order returned, and the buy and sell sides concurrently.
+/// Cancels and replaces only go out for tracked orders with no cancel already
+/// outstanding.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OrderIntent {
+    /// `level` is recorded on the tracked order for strategies that quote a
+    /// ladder; others leave it at 0.
+    Place {
+        side: Side,
+        level: u32,
+        price_raw: u128,
+        qty_raw: u128,
+    },
+    Cancel {
+        client_order_id: Uuid,
+    },
+    /// Cancel `client_order_id` and place the new order on `side`, sending both
+    /// at once; nothing is placed when the cancel is skipped. If the cancel
+    /// fails once the new order is placed, the old one stays pending cancel.
+    Replace {
+        client_order_id: Uuid,
+        side: Side,
+        level: u32,
+        price_raw: u128,
+        qty_raw: u128,
+    },
+}
+
+/// Read-only view of the loop's state handed to every callback.
+#[derive(Debug, Clone, Copy)]
+pub struct StrategyContext<'a> {
+    pub config: &'a StrategyConfig,
+    pub orders: &'a OrderTracker,
+    pub positions: &'a PositionLedger,
+    pub last_market: Option<&'a MarketPriceData>,
+    /// Time of the event being handled, in unix milliseconds.
+    pub now_ms: u64,
+}
+
+impl StrategyState {
+    pub fn context<'a>(&'a self, config: &'a StrategyConfig) -> StrategyContext<'a> {
+        StrategyContext {
+            config,
+            orders: &self.orders,
+            positions: &self.positions,
+            last_market: self.last_market.as_ref(),
+            now_ms: self.now_ms,
+        }
+    }
+}
+
+/// Trading logic driven by the strategy loop or the backtest.
+///
+/// Callbacks run after the loop has updated its own state from the event, so
+/// `ctx` already reflects it. While trading is halted or the product's market
+/// data is stale, timers are not delivered and intents from the order update
+/// and fill callbacks are dropped; market ticks are not delivered while halted.
+pub trait Strategy: Send {
+    fn on_market_tick(
+        &mut self,
+        ctx: &StrategyContext<'_>,
+        tick: &MarketPriceData,
+    ) -> Vec<OrderIntent>;
+
+    fn on_order_update(
+        &mut self,
+        _ctx: &StrategyContext<'_>,
+        _update: &OrderUpdateData,
+    ) -> Vec<OrderIntent> {
+        Vec::new()
+    }
+
+    fn on_fill(&mut self, _ctx: &StrategyContext<'_>, _fill: &Fill) -> Vec<OrderIntent> {
+        Vec::new()
+    }
+
+    /// How often [`Self::on_timer`] fires; `None` disables it.
+    fn timer_interval(&self) -> Option<Duration> {
+        None
+    }
+
+    fn on_timer(&mut self, _ctx: &StrategyContext<'_>) -> Vec<OrderIntent> {
+        Vec::new()
+    }
+}
+
+/// The built-in strategies, selected by [`StrategyConfig::kind`].
+#[derive(Debug, Clone)]
+pub enum StrategyRuntime {
+    MarketMaker(MarketMaker),
+}
+
+impl StrategyRuntime {
+    pub fn from_config(config: &StrategyConfig) -> Self {
+        match config.kind {
+            StrategyKind::MarketMaker => Self::MarketMaker(MarketMaker::new(config)),
+        }
+    }
+}
+
+impl Strategy for StrategyRuntime {
+    fn on_market_tick(
+        &mut self,
+        ctx: &StrategyContext<'_>,
+        tick: &MarketPriceData,
+    ) -> Vec<OrderIntent> {
+        match self {
+            Self::MarketMaker(strategy) => strategy.on_market_tick(ctx, tick),
+        }
+    }
+
+    fn on_order_update(
+        &mut self,
+        ctx: &StrategyContext<'_>,
+        update: &OrderUpdateData,
+    ) -> Vec<OrderIntent> {
+        match self {
+            Self::MarketMaker(strategy) => strategy.on_order_update(ctx, update),
+        }
+    }
+
+    fn on_fill(&mut self, ctx: &StrategyContext<'_>, fill: &Fill) -> Vec<OrderIntent> {
+        match self {
+            Self::MarketMaker(strategy) => strategy.on_fill(ctx, fill),
+        }
+    }
+
+    fn timer_interval(&self) -> Option<Duration> {
+        match self {
+            Self::MarketMaker(strategy) => strategy.timer_interval(),
+        }
+    }
+
+    fn on_timer(&mut self, ctx: &StrategyContext<'_>) -> Vec<OrderIntent> {
+        match self {
+            Self::MarketMaker(strategy) => strategy.on_timer(ctx),
+        }
+    }
+}
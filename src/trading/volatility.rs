@@ -0,0 +1,81 @@
+//! Short-horizon realized volatility of the book mid.
+
+/// Exponentially weighted variance of log mid returns, normalised per
+/// second so irregular tick spacing does not bias it. The weight of a
+/// return halves every `half_life_ms` of elapsed time.
+#[derive(Debug, Clone)]
+pub struct VolatilityEstimator {
+    half_life_secs: f64,
+    last: Option<(f64, u64)>,
+    variance_per_sec: Option<f64>,
+}
+
+impl VolatilityEstimator {
+    pub fn new(half_life_ms: u64) -> Self {
+        Self {
+            half_life_secs: half_life_ms.max(1) as f64 / 1_000.0,
+            last: None,
+            variance_per_sec: None,
+        }
+    }
+
+    /// Folds in the mid observed at `now_ms`. Ticks sharing a timestamp are
+    /// treated as a millisecond apart.
+    pub fn update(&mut self, mid_raw: u128, now_ms: u64) {
+        if mid_raw == 0 {
+            return;
+        }
+        let log_mid = (mid_raw as f64).ln();
+
+        if let Some((last_log_mid, last_ms)) = self.last {
+            let elapsed_secs = now_ms.saturating_sub(last_ms).max(1) as f64 / 1_000.0;
+            let log_return = log_mid - last_log_mid;
+            let sample = log_return * log_return / elapsed_secs;
+            let weight = 1.0 - 0.5_f64.powf(elapsed_secs / self.half_life_secs);
+
+            self.variance_per_sec = Some(match self.variance_per_sec {
+                None => sample,
+                Some(variance) => variance + weight * (sample - variance),
+            });
+        }
+        self.last = Some((log_mid, now_ms.max(self.last.map_or(0, |(_, ms)| ms))));
+    }
+
+    /// Standard deviation of log returns per square root of a second; `None`
+    /// until two mids have been seen.
+    pub fn volatility(&self) -> Option<f64> {
+        self.variance_per_sec.map(f64::sqrt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::VolatilityEstimator;
+
+    #[test]
+    fn flat_mid_has_no_volatility() {
+        let mut estimator = VolatilityEstimator::new(10_000);
+        assert_eq!(estimator.volatility(), None);
+
+        for second in 0..5 {
+            estimator.update(100_000_000_000, second * 1_000);
+        }
+        assert_eq!(estimator.volatility(), Some(0.0));
+    }
+
+    #[test]
+    fn volatility_rises_with_moves_and_decays_when_quiet() {
+        let mut estimator = VolatilityEstimator::new(1_000);
+        estimator.update(100_000_000_000, 0);
+        estimator.update(101_000_000_000, 1_000);
+        let spike = estimator.volatility().unwrap();
+        assert!((spike - (1.01_f64).ln()).abs() < 1e-9);
+
+        estimator.update(101_000_000_000, 2_000);
+        let after_one_half_life = estimator.volatility().unwrap();
+        assert!((after_one_half_life - spike / 2.0_f64.sqrt()).abs() < 1e-9);
+
+        estimator.update(101_000_000_000, 12_000);
+        assert!(estimator.volatility().unwrap() < spike / 30.0);
+    }
+}